@@ -1,19 +1,16 @@
 #[macro_use]
 extern crate log;
 
-use std::sync::Arc;
 use std::thread::sleep;
 use std::time::Duration;
 
-use blatann_event::{AsyncEventHandler, Subscribable, Subscriber, SubscriberAction, Waitable};
+use blatann_event::{AsyncEventHandler, Waitable};
 use env_logger;
 use env_logger::Env;
 
 use blatann::advertise_data::{AdvData, AdvertisingFlags};
-use blatann::advertiser::{AdvType, Advertiser};
+use blatann::advertiser::AdvType;
 use blatann::device::BleDevice;
-use blatann::events::{AdvertisingTimeoutEvent, ConnectionEvent};
-use blatann::peer::Peer;
 
 fn configure_log() {
     env_logger::Builder::from_env(Env::default().default_filter_or("debug")).init();
@@ -25,8 +22,10 @@ fn main() {
 
     device.open().unwrap();
 
-    let handler = Arc::new(EventDummy {});
-    device.advertiser.on_timeout.subscribe(handler);
+    let _timeout_sub = device
+        .advertiser
+        .on_timeout
+        .subscribe_fn(|_advertiser, _event| info!("Got advertising timeout!"));
 
     let mut adv_data = AdvData::default();
     adv_data.set_flags(
@@ -57,27 +56,3 @@ fn main() {
     }
     info!("Done!")
 }
-
-struct EventDummy {}
-
-impl Subscriber<Peer, ConnectionEvent> for EventDummy {
-    fn handle(
-        self: Arc<Self>,
-        _sender: Arc<Peer>,
-        _event: ConnectionEvent,
-    ) -> Option<SubscriberAction> {
-        info!("Peer connected!");
-        return None;
-    }
-}
-
-impl Subscriber<Advertiser, AdvertisingTimeoutEvent> for EventDummy {
-    fn handle(
-        self: Arc<Self>,
-        _sender: Arc<Advertiser>,
-        _event: AdvertisingTimeoutEvent,
-    ) -> Option<SubscriberAction> {
-        info!("Got advertising timeout!");
-        return None;
-    }
-}
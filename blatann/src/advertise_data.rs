@@ -1,9 +1,14 @@
-use nrf_driver::gap::enums::BleAdvDataType;
+use nrf_driver::gap::enums::{BleAdvDataType, BleGapAppearance};
 use std::collections::HashMap;
+use std::fmt;
+
+use crate::assigned_numbers::{APPEARANCES, COMPANY_IDS, SERVICES};
+use crate::ble_uuid::BleUuid;
 
 pub const MAX_ADVERTISE_ENCODED_LEN: usize = 31;
 
 bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq)]
     pub struct AdvertisingFlags: u8 {
         const LIMITED_DISCOVERY_MODE = 0x01;
         const GENERAL_DISCOVERY_MODE = 0x02;
@@ -21,6 +26,85 @@ impl From<AdvertisingFlags> for u8 {
 
 pub type AdvDataType = BleAdvDataType;
 
+#[derive(Clone, PartialEq)]
+pub struct ManufacturerData {
+    pub company_id: u16,
+    pub data: Vec<u8>,
+}
+
+impl ManufacturerData {
+    pub fn company_name(&self) -> Option<&'static str> {
+        COMPANY_IDS.name(self.company_id)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.company_id.to_le_bytes().to_vec();
+        data.extend(&self.data);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < 2 {
+            return None;
+        }
+        Some(Self {
+            company_id: u16::from_le_bytes([data[0], data[1]]),
+            data: data[2..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Debug for ManufacturerData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let company = named_value(self.company_name(), self.company_id);
+        f.debug_struct("ManufacturerData")
+            .field("company", &format_args!("{}", company))
+            .field("data", &format_args!("{:02X?}", self.data))
+            .finish()
+    }
+}
+
+fn named_value(name: Option<&str>, value: u16) -> String {
+    match name {
+        Some(name) => format!("{} (0x{:04X})", name, value),
+        None => format!("0x{:04X}", value),
+    }
+}
+
+// Decodes an entry's value for printing, falling back to the raw bytes
+fn describe_entry(adv_type: u8, data: &[u8]) -> String {
+    let uuid16s = |data: &[u8]| -> String {
+        let uuids: Vec<String> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .map(|uuid| named_value(SERVICES.name(uuid), uuid))
+            .collect();
+        format!("[{}]", uuids.join(", "))
+    };
+
+    match AdvDataType::from_u8(adv_type) {
+        Some(AdvDataType::Flags) if data.len() == 1 => {
+            format!("{:?}", AdvertisingFlags::from_bits_retain(data[0]))
+        }
+        Some(AdvDataType::ShortLocalName) | Some(AdvDataType::CompleteLocalName) => {
+            format!("{:?}", String::from_utf8_lossy(data))
+        }
+        Some(AdvDataType::Service16bitUuidComplete)
+        | Some(AdvDataType::Service16bitUuidMoreAvailable)
+        | Some(AdvDataType::SolicitedSeviceUuids16bit) => uuid16s(data),
+        Some(AdvDataType::Appearance) if data.len() == 2 => {
+            let value = u16::from_le_bytes([data[0], data[1]]);
+            named_value(APPEARANCES.name(value), value)
+        }
+        Some(AdvDataType::TxPowerLevel) if data.len() == 1 => format!("{} dBm", data[0] as i8),
+        Some(AdvDataType::ManufacturerSpecificData) => match ManufacturerData::deserialize(data) {
+            Some(manufacturer_data) => format!("{:?}", manufacturer_data),
+            None => format!("{:02X?}", data),
+        },
+        _ => format!("{:02X?}", data),
+    }
+}
+
 // TODO: Rest of API
 pub struct AdvData {
     pub entries: HashMap<u8, Vec<u8>>,
@@ -54,6 +138,25 @@ impl AdvData {
         self.add_entry(adv_type.into(), data.into());
     }
 
+    pub fn set_appearance(&mut self, appearance: BleGapAppearance) {
+        let value = appearance as u16;
+        self.add_entry(AdvDataType::Appearance.into(), &value.to_le_bytes());
+    }
+
+    pub fn set_manufacturer_data(&mut self, manufacturer_data: &ManufacturerData) {
+        self.add_entry(
+            AdvDataType::ManufacturerSpecificData.into(),
+            &manufacturer_data.serialize(),
+        );
+    }
+
+    pub fn manufacturer_data(&self) -> Option<ManufacturerData> {
+        let adv_type: u8 = AdvDataType::ManufacturerSpecificData.into();
+        self.entries
+            .get(&adv_type)
+            .and_then(|data| ManufacturerData::deserialize(data))
+    }
+
     pub fn set_service_uuid16s(&mut self, uuids: &[u16], is_complete_list: bool) {
         let adv_type = if is_complete_list {
             AdvDataType::Service16bitUuidComplete
@@ -74,14 +177,56 @@ impl AdvData {
         } else {
             AdvDataType::Service128bitUuidMoreAvailable
         };
+        // Sent in little-endian byte order like every other field
         let data: Vec<u8> = uuids
             .iter()
-            .map(|x| x.as_bytes().to_owned())
+            .map(|x| BleUuid::Full(*x).to_le_bytes())
             .flatten()
             .collect();
         self.add_entry(adv_type.into(), &data);
     }
 
+    // Splits the UUIDs into the 16-bit and 128-bit lists
+    pub fn set_service_uuids(&mut self, uuids: &[BleUuid], is_complete_list: bool) {
+        let uuid16s: Vec<u16> = uuids.iter().filter_map(|u| u.as_sig()).collect();
+        let uuid128s: Vec<uuid::Uuid> = uuids
+            .iter()
+            .filter(|u| u.as_sig().is_none())
+            .map(|u| u.to_uuid128())
+            .collect();
+
+        if !uuid16s.is_empty() {
+            self.set_service_uuid16s(&uuid16s, is_complete_list);
+        }
+        if !uuid128s.is_empty() {
+            self.set_service_uuid128s(&uuid128s, is_complete_list);
+        }
+    }
+
+    pub fn service_uuids(&self) -> Vec<BleUuid> {
+        let mut uuids = vec![];
+        for (adv_type, data) in self.entries.iter() {
+            match AdvDataType::from_u8(*adv_type) {
+                Some(AdvDataType::Service16bitUuidComplete)
+                | Some(AdvDataType::Service16bitUuidMoreAvailable) => uuids.extend(
+                    data.chunks_exact(2)
+                        .map(|c| BleUuid::Sig(u16::from_le_bytes([c[0], c[1]]))),
+                ),
+                Some(AdvDataType::Service128bitUuidComplete)
+                | Some(AdvDataType::Service128bitUuidMoreAvailable) => {
+                    uuids.extend(data.chunks_exact(16).map(|c| {
+                        let mut bytes = [0u8; 16];
+                        bytes.copy_from_slice(c);
+                        bytes.reverse();
+                        BleUuid::from_uuid128(&uuid::Uuid::from_bytes(bytes))
+                    }))
+                }
+                _ => {}
+            }
+        }
+        uuids
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         // Data is in length-type-value format
         let mut adv_data = Vec::new();
@@ -94,6 +239,22 @@ impl AdvData {
         adv_data
     }
 
+    // Parses advertising or scan response data, ignoring any zero padding at the end
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let mut adv_data = Self::default();
+        let mut offset = 0;
+        while offset < data.len() {
+            let len = data[offset] as usize;
+            if len == 0 {
+                break;
+            }
+            let entry = data.get(offset + 1..offset + 1 + len)?;
+            adv_data.add_entry(entry[0], &entry[1..]);
+            offset += len + 1;
+        }
+        Some(adv_data)
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         let encoded_length = self.serialize().len();
         if encoded_length <= MAX_ADVERTISE_ENCODED_LEN {
@@ -103,3 +264,68 @@ impl AdvData {
         }
     }
 }
+
+impl fmt::Debug for AdvData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut adv_types: Vec<&u8> = self.entries.keys().collect();
+        adv_types.sort();
+
+        let mut map = f.debug_map();
+        for adv_type in adv_types {
+            let data = &self.entries[adv_type];
+            let key = match AdvDataType::from_u8(*adv_type) {
+                Some(t) => format!("{:?}", t),
+                None => format!("0x{:02X}", adv_type),
+            };
+            map.entry(
+                &format_args!("{}", key),
+                &format_args!("{}", describe_entry(*adv_type, data)),
+            );
+        }
+        map.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_stops_at_zero_padding() {
+        let mut data = vec![0x02, 0x01, 0x06, 0x04, 0x09, b'a', b'b', b'c'];
+        data.resize(MAX_ADVERTISE_ENCODED_LEN, 0);
+
+        let adv_data = AdvData::deserialize(&data).unwrap();
+
+        assert_eq!(adv_data.entries.len(), 2);
+        assert_eq!(adv_data.entries[&0x01], [0x06]);
+        assert_eq!(adv_data.entries[&0x09], b"abc");
+    }
+
+    #[test]
+    fn deserialize_rejects_an_entry_past_the_end() {
+        assert!(AdvData::deserialize(&[0x05, 0x09, b'a']).is_none());
+    }
+
+    #[test]
+    fn round_trips_service_uuids_and_manufacturer_data() {
+        let vendor = uuid::Uuid::from_u128(0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E);
+        let manufacturer_data = ManufacturerData {
+            company_id: 0x0059,
+            data: vec![1, 2, 3],
+        };
+        let mut adv_data = AdvData::default();
+        adv_data.set_service_uuids(&[BleUuid::Sig(0x180D), BleUuid::Full(vendor)], true);
+        adv_data.set_manufacturer_data(&manufacturer_data);
+
+        let parsed = AdvData::deserialize(&adv_data.serialize()).unwrap();
+        let mut uuids = parsed.service_uuids();
+        uuids.sort_by_key(|u| u.as_sig().is_none());
+
+        assert_eq!(
+            uuids,
+            [BleUuid::Sig(0x180D), BleUuid::from_uuid128(&vendor)]
+        );
+        assert_eq!(parsed.manufacturer_data(), Some(manufacturer_data));
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex};
 
-use blatann_event::{Publisher, Subscribable, Subscriber, SubscriberAction};
+use blatann_event::{Priority, Publisher, Subscribable, Subscriber, SubscriberResult};
 
 use nrf_driver::driver::NrfDriver;
 use nrf_driver::error::{NrfError, NrfErrorType, NrfResult};
@@ -54,9 +54,16 @@ impl Advertiser {
             state: Mutex::new(Default::default()),
         });
 
-        driver.events.gap_timeout.subscribe(advertiser.clone());
-        central.on_connect.subscribe(advertiser.clone());
-        central.on_disconnect.subscribe(advertiser.clone());
+        driver
+            .events
+            .gap_timeout
+            .subscribe_with_priority(advertiser.clone(), Priority::Internal);
+        central
+            .on_connect
+            .subscribe_with_priority(advertiser.clone(), Priority::Internal);
+        central
+            .on_disconnect
+            .subscribe_with_priority(advertiser.clone(), Priority::Internal);
 
         return advertiser;
     }
@@ -140,23 +147,15 @@ impl Advertiser {
 }
 
 impl Subscriber<Peer, ConnectionEvent> for Advertiser {
-    fn handle(
-        self: Arc<Self>,
-        _sender: Arc<Peer>,
-        _event: ConnectionEvent,
-    ) -> Option<SubscriberAction> {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: ConnectionEvent) -> SubscriberResult {
         let mut state = self.state.lock().unwrap();
         state.is_advertising = false;
-        return None;
+        return Ok(None);
     }
 }
 
 impl Subscriber<Peer, DisconnectionEvent> for Advertiser {
-    fn handle(
-        self: Arc<Self>,
-        _sender: Arc<Peer>,
-        _event: DisconnectionEvent,
-    ) -> Option<SubscriberAction> {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: DisconnectionEvent) -> SubscriberResult {
         let auto_restart_enabled = { self.state.lock().unwrap().auto_restart };
 
         if auto_restart_enabled {
@@ -166,7 +165,7 @@ impl Subscriber<Peer, DisconnectionEvent> for Advertiser {
             });
         }
 
-        return None;
+        return Ok(None);
     }
 }
 
@@ -175,7 +174,7 @@ impl Subscriber<NrfDriver, GapEventTimeout> for Advertiser {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         event: GapEventTimeout,
-    ) -> Option<SubscriberAction> {
+    ) -> SubscriberResult {
         if let BleGapTimeoutSource::Advertising = event.src {
             // Notify that advertising timed out first which may call stop() to disable auto-restart
             self.on_timeout
@@ -191,6 +190,6 @@ impl Subscriber<NrfDriver, GapEventTimeout> for Advertiser {
                 state.is_advertising = false;
             }
         }
-        return None;
+        return Ok(None);
     }
 }
@@ -0,0 +1,360 @@
+use std::fmt;
+
+use crate::ble_uuid::BleUuid;
+
+use crate::device::Appearance;
+
+// Value/name pairs from the Bluetooth SIG assigned numbers. Names are matched case-insensitively
+pub struct AssignedNumbers {
+    entries: &'static [(u16, &'static str)],
+}
+
+impl AssignedNumbers {
+    pub fn name(&self, value: u16) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .find(|(v, _)| *v == value)
+            .map(|(_, name)| *name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<u16> {
+        self.entries
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(value, _)| *value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &'static str)> {
+        self.entries.iter().copied()
+    }
+}
+
+pub static SERVICES: AssignedNumbers = AssignedNumbers {
+    entries: &[
+        (0x1800, "Generic Access"),
+        (0x1801, "Generic Attribute"),
+        (0x1802, "Immediate Alert"),
+        (0x1803, "Link Loss"),
+        (0x1804, "Tx Power"),
+        (0x1805, "Current Time Service"),
+        (0x1806, "Reference Time Update Service"),
+        (0x1807, "Next DST Change Service"),
+        (0x1808, "Glucose"),
+        (0x1809, "Health Thermometer"),
+        (0x180A, "Device Information"),
+        (0x180D, "Heart Rate"),
+        (0x180E, "Phone Alert Status Service"),
+        (0x180F, "Battery Service"),
+        (0x1810, "Blood Pressure"),
+        (0x1811, "Alert Notification Service"),
+        (0x1812, "Human Interface Device"),
+        (0x1813, "Scan Parameters"),
+        (0x1814, "Running Speed and Cadence"),
+        (0x1815, "Automation IO"),
+        (0x1816, "Cycling Speed and Cadence"),
+        (0x1818, "Cycling Power"),
+        (0x1819, "Location and Navigation"),
+        (0x181A, "Environmental Sensing"),
+        (0x181B, "Body Composition"),
+        (0x181C, "User Data"),
+        (0x181D, "Weight Scale"),
+        (0x181E, "Bond Management Service"),
+        (0x181F, "Continuous Glucose Monitoring"),
+        (0x1820, "Internet Protocol Support Service"),
+        (0x1821, "Indoor Positioning"),
+        (0x1822, "Pulse Oximeter Service"),
+        (0x1823, "HTTP Proxy"),
+        (0x1824, "Transport Discovery"),
+        (0x1825, "Object Transfer Service"),
+        (0x1826, "Fitness Machine"),
+        (0x1827, "Mesh Provisioning Service"),
+        (0x1828, "Mesh Proxy Service"),
+        (0x1829, "Reconnection Configuration"),
+    ],
+};
+
+pub static CHARACTERISTICS: AssignedNumbers = AssignedNumbers {
+    entries: &[
+        (0x2A00, "Device Name"),
+        (0x2A01, "Appearance"),
+        (0x2A02, "Peripheral Privacy Flag"),
+        (0x2A03, "Reconnection Address"),
+        (0x2A04, "Peripheral Preferred Connection Parameters"),
+        (0x2A05, "Service Changed"),
+        (0x2A06, "Alert Level"),
+        (0x2A07, "Tx Power Level"),
+        (0x2A08, "Date Time"),
+        (0x2A09, "Day of Week"),
+        (0x2A0A, "Day Date Time"),
+        (0x2A0C, "Exact Time 256"),
+        (0x2A0D, "DST Offset"),
+        (0x2A0E, "Time Zone"),
+        (0x2A0F, "Local Time Information"),
+        (0x2A11, "Time with DST"),
+        (0x2A12, "Time Accuracy"),
+        (0x2A13, "Time Source"),
+        (0x2A14, "Reference Time Information"),
+        (0x2A16, "Time Update Control Point"),
+        (0x2A17, "Time Update State"),
+        (0x2A18, "Glucose Measurement"),
+        (0x2A19, "Battery Level"),
+        (0x2A1C, "Temperature Measurement"),
+        (0x2A1D, "Temperature Type"),
+        (0x2A1E, "Intermediate Temperature"),
+        (0x2A21, "Measurement Interval"),
+        (0x2A22, "Boot Keyboard Input Report"),
+        (0x2A23, "System ID"),
+        (0x2A24, "Model Number String"),
+        (0x2A25, "Serial Number String"),
+        (0x2A26, "Firmware Revision String"),
+        (0x2A27, "Hardware Revision String"),
+        (0x2A28, "Software Revision String"),
+        (0x2A29, "Manufacturer Name String"),
+        (
+            0x2A2A,
+            "IEEE 11073-20601 Regulatory Certification Data List",
+        ),
+        (0x2A2B, "Current Time"),
+        (0x2A2C, "Magnetic Declination"),
+        (0x2A31, "Scan Refresh"),
+        (0x2A32, "Boot Keyboard Output Report"),
+        (0x2A33, "Boot Mouse Input Report"),
+        (0x2A34, "Glucose Measurement Context"),
+        (0x2A35, "Blood Pressure Measurement"),
+        (0x2A36, "Intermediate Cuff Pressure"),
+        (0x2A37, "Heart Rate Measurement"),
+        (0x2A38, "Body Sensor Location"),
+        (0x2A39, "Heart Rate Control Point"),
+        (0x2A3F, "Alert Status"),
+        (0x2A40, "Ringer Control Point"),
+        (0x2A41, "Ringer Setting"),
+        (0x2A42, "Alert Category ID Bit Mask"),
+        (0x2A43, "Alert Category ID"),
+        (0x2A44, "Alert Notification Control Point"),
+        (0x2A45, "Unread Alert Status"),
+        (0x2A46, "New Alert"),
+        (0x2A47, "Supported New Alert Category"),
+        (0x2A48, "Supported Unread Alert Category"),
+        (0x2A49, "Blood Pressure Feature"),
+        (0x2A4A, "HID Information"),
+        (0x2A4B, "Report Map"),
+        (0x2A4C, "HID Control Point"),
+        (0x2A4D, "Report"),
+        (0x2A4E, "Protocol Mode"),
+        (0x2A4F, "Scan Interval Window"),
+        (0x2A50, "PnP ID"),
+        (0x2A51, "Glucose Feature"),
+        (0x2A52, "Record Access Control Point"),
+        (0x2A53, "RSC Measurement"),
+        (0x2A54, "RSC Feature"),
+        (0x2A55, "SC Control Point"),
+        (0x2A5B, "CSC Measurement"),
+        (0x2A5C, "CSC Feature"),
+        (0x2A5D, "Sensor Location"),
+        (0x2A63, "Cycling Power Measurement"),
+        (0x2A64, "Cycling Power Vector"),
+        (0x2A65, "Cycling Power Feature"),
+        (0x2A66, "Cycling Power Control Point"),
+        (0x2A67, "Location and Speed"),
+        (0x2A68, "Navigation"),
+        (0x2A6D, "Pressure"),
+        (0x2A6E, "Temperature"),
+        (0x2A6F, "Humidity"),
+        (0x2A98, "Weight"),
+        (0x2A9D, "Weight Measurement"),
+        (0x2A9E, "Weight Scale Feature"),
+        (0x2AA6, "Central Address Resolution"),
+        (0x2AC9, "Resolvable Private Address Only"),
+    ],
+};
+
+pub static DESCRIPTORS: AssignedNumbers = AssignedNumbers {
+    entries: &[
+        (0x2900, "Characteristic Extended Properties"),
+        (0x2901, "Characteristic User Description"),
+        (0x2902, "Client Characteristic Configuration"),
+        (0x2903, "Server Characteristic Configuration"),
+        (0x2904, "Characteristic Presentation Format"),
+        (0x2905, "Characteristic Aggregate Format"),
+        (0x2906, "Valid Range"),
+        (0x2907, "External Report Reference"),
+        (0x2908, "Report Reference"),
+        (0x2909, "Number of Digitals"),
+        (0x290A, "Value Trigger Setting"),
+        (0x290B, "Environmental Sensing Configuration"),
+        (0x290C, "Environmental Sensing Measurement"),
+        (0x290D, "Environmental Sensing Trigger Setting"),
+        (0x290E, "Time Trigger Setting"),
+    ],
+};
+
+pub static APPEARANCES: AssignedNumbers = AssignedNumbers {
+    entries: &[
+        (0, "Unknown"),
+        (64, "Generic Phone"),
+        (128, "Generic Computer"),
+        (192, "Generic Watch"),
+        (193, "Watch: Sports Watch"),
+        (256, "Generic Clock"),
+        (320, "Generic Display"),
+        (384, "Generic Remote Control"),
+        (448, "Generic Eye-glasses"),
+        (512, "Generic Tag"),
+        (576, "Generic Keyring"),
+        (640, "Generic Media Player"),
+        (704, "Generic Barcode Scanner"),
+        (768, "Generic Thermometer"),
+        (769, "Thermometer: Ear"),
+        (832, "Generic Heart Rate Sensor"),
+        (833, "Heart Rate Sensor: Heart Rate Belt"),
+        (896, "Generic Blood Pressure"),
+        (897, "Blood Pressure: Arm"),
+        (898, "Blood Pressure: Wrist"),
+        (960, "Human Interface Device"),
+        (961, "Keyboard"),
+        (962, "Mouse"),
+        (963, "Joystick"),
+        (964, "Gamepad"),
+        (965, "Digitizer Tablet"),
+        (966, "Card Reader"),
+        (967, "Digital Pen"),
+        (968, "Barcode Scanner"),
+        (1024, "Generic Glucose Meter"),
+        (1088, "Generic Running Walking Sensor"),
+        (1089, "Running Walking Sensor: In-Shoe"),
+        (1090, "Running Walking Sensor: On-Shoe"),
+        (1091, "Running Walking Sensor: On-Hip"),
+        (1152, "Generic Cycling"),
+        (1153, "Cycling: Cycling Computer"),
+        (1154, "Cycling: Speed Sensor"),
+        (1155, "Cycling: Cadence Sensor"),
+        (1156, "Cycling: Power Sensor"),
+        (1157, "Cycling: Speed and Cadence Sensor"),
+        (3136, "Generic Pulse Oximeter"),
+        (3137, "Pulse Oximeter: Fingertip"),
+        (3138, "Pulse Oximeter: Wrist Worn"),
+        (3200, "Generic Weight Scale"),
+        (5184, "Generic Outdoor Sports Activity"),
+        (5185, "Outdoor Sports Activity: Location Display Device"),
+        (
+            5186,
+            "Outdoor Sports Activity: Location and Navigation Display Device",
+        ),
+        (5187, "Outdoor Sports Activity: Location Pod"),
+        (5188, "Outdoor Sports Activity: Location and Navigation Pod"),
+    ],
+};
+
+pub static COMPANY_IDS: AssignedNumbers = AssignedNumbers {
+    entries: &[
+        (0x0000, "Ericsson Technology Licensing"),
+        (0x0001, "Nokia Mobile Phones"),
+        (0x0002, "Intel Corp."),
+        (0x0003, "IBM Corp."),
+        (0x0004, "Toshiba Corp."),
+        (0x0005, "3Com"),
+        (0x0006, "Microsoft"),
+        (0x0007, "Lucent"),
+        (0x0008, "Motorola"),
+        (0x0009, "Infineon Technologies AG"),
+        (0x000A, "Qualcomm Technologies International, Ltd. (QTIL)"),
+        (0x000B, "Silicon Wave"),
+        (0x000C, "Digianswer A/S"),
+        (0x000D, "Texas Instruments Inc."),
+        (0x000E, "Parthus Technologies Inc."),
+        (0x000F, "Broadcom Corporation"),
+        (0x0010, "Mitel Semiconductor"),
+        (0x0011, "Widcomm, Inc."),
+        (0x0012, "Zeevo, Inc."),
+        (0x0013, "Atmel Corporation"),
+        (0x0014, "Mitsubishi Electric Corporation"),
+        (0x0015, "RTX Telecom A/S"),
+        (0x0016, "KC Technology Inc."),
+        (0x0017, "Newlogic"),
+        (0x0018, "Transilica, Inc."),
+        (0x0019, "Rohde & Schwarz GmbH & Co. KG"),
+        (0x001A, "TTPCom Limited"),
+        (0x001B, "Signia Technologies, Inc."),
+        (0x001C, "Conexant Systems Inc."),
+        (0x001D, "Qualcomm"),
+        (0x001E, "Inventel"),
+        (0x001F, "AVM Berlin"),
+        (0x0020, "BandSpeed, Inc."),
+        (0x0021, "Mansella Ltd"),
+        (0x0022, "NEC Corporation"),
+        (0x0023, "WavePlus Technology Co., Ltd."),
+        (0x0024, "Alcatel"),
+        (0x0025, "NXP Semiconductors"),
+        (0x0026, "C Technologies"),
+        (0x0027, "Open Interface"),
+        (0x0028, "R F Micro Devices"),
+        (0x0029, "Hitachi Ltd"),
+        (0x002A, "Symbol Technologies, Inc."),
+        (0x002B, "Tenovis"),
+        (0x002C, "Macronix International Co. Ltd."),
+        (0x002D, "GCT Semiconductor"),
+        (0x002E, "Norwood Systems"),
+        (0x002F, "MewTel Technology Inc."),
+        (0x0030, "ST Microelectronics"),
+        (0x0031, "Synopsys, Inc."),
+        (0x0032, "Red-M (Communications) Ltd"),
+        (0x0033, "Commil Ltd"),
+        (0x0034, "Computer Access Technology Corporation (CATC)"),
+        (0x0035, "Eclipse (HQ Espana) S.L."),
+        (0x0036, "Renesas Electronics Corporation"),
+        (0x0037, "Mobilian Corporation"),
+        (0x0046, "MediaTek, Inc."),
+        (0x004C, "Apple, Inc."),
+        (0x0057, "Harman International Industries, Inc."),
+        (0x0059, "Nordic Semiconductor ASA"),
+        (0x005D, "Realtek Semiconductor Corporation"),
+        (0x0065, "HP, Inc."),
+        (0x006B, "Polar Electro OY"),
+        (0x0075, "Samsung Electronics Co. Ltd."),
+        (0x0078, "Nike, Inc."),
+        (0x0087, "Garmin International, Inc."),
+        (0x009E, "Bose Corporation"),
+        (0x00C4, "LG Electronics"),
+        (0x00D2, "Dialog Semiconductor B.V."),
+        (0x00E0, "Google"),
+        (0x0118, "Radius Networks, Inc."),
+        (0x012D, "Sony Corporation"),
+        (0x0131, "Cypress Semiconductor"),
+        (0x0157, "Anhui Huami Information Technology Co., Ltd."),
+        (0x0171, "Amazon.com Services, LLC"),
+        (0x027D, "HUAWEI Technologies Co., Ltd."),
+        (0x02E5, "Espressif Incorporated"),
+        (0x0499, "Ruuvi Innovations Ltd."),
+        (0x0822, "Adafruit Industries"),
+        (0xFFFF, "Reserved for internal use"),
+    ],
+};
+
+// Name of a SIG-defined service, characteristic or descriptor UUID
+pub fn uuid_name(uuid: &BleUuid) -> Option<&'static str> {
+    let value = uuid.as_sig()?;
+    SERVICES
+        .name(value)
+        .or_else(|| CHARACTERISTICS.name(value))
+        .or_else(|| DESCRIPTORS.name(value))
+}
+
+pub fn appearance_name(appearance: Appearance) -> Option<&'static str> {
+    APPEARANCES.name(appearance as u16)
+}
+
+pub fn company_name(company_id: u16) -> Option<&'static str> {
+    COMPANY_IDS.name(company_id)
+}
+
+// Debug-formats a UUID with its SIG name when it has one
+pub(crate) struct NamedUuid<'a>(pub &'a BleUuid);
+
+impl fmt::Debug for NamedUuid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match uuid_name(self.0) {
+            Some(name) => write!(f, "{} ({})", name, self.0),
+            None => write!(f, "{:?}", self.0),
+        }
+    }
+}
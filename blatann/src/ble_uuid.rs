@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use nrf_driver::common::types::BleUuid as SdUuid;
+use nrf_driver::driver::NrfDriver;
+use nrf_driver::error::NrfResult;
+
+// 0000xxxx-0000-1000-8000-00805F9B34FB, SIG-assigned 16-bit UUIDs fill in the xxxx
+pub const BLUETOOTH_BASE_UUID: uuid::Uuid =
+    uuid::Uuid::from_u128(0x00000000_0000_1000_8000_00805F9B34FB);
+
+// Bytes 2-3 of a 128-bit UUID (big-endian order) hold the 16-bit offset into its base
+const OFFSET_INDEX: usize = 2;
+
+#[derive(Debug, Copy, Clone)]
+pub enum BleUuid {
+    // SIG-assigned UUID on the Bluetooth base
+    Sig(u16),
+    // Offset into a vendor-specific 128-bit base
+    Vendor { base: uuid::Uuid, offset: u16 },
+    Full(uuid::Uuid),
+}
+
+impl BleUuid {
+    pub const fn from_sig(value: u16) -> Self {
+        BleUuid::Sig(value)
+    }
+
+    pub fn vendor(base: &uuid::Uuid, offset: u16) -> Self {
+        BleUuid::Vendor {
+            base: with_offset(base, 0),
+            offset,
+        }
+    }
+
+    pub fn from_uuid128(uuid: &uuid::Uuid) -> Self {
+        let offset = offset_of(uuid);
+        if with_offset(uuid, 0) == BLUETOOTH_BASE_UUID {
+            BleUuid::Sig(offset)
+        } else {
+            BleUuid::Full(*uuid)
+        }
+    }
+
+    pub fn to_uuid128(&self) -> uuid::Uuid {
+        match self {
+            BleUuid::Sig(value) => with_offset(&BLUETOOTH_BASE_UUID, *value),
+            BleUuid::Vendor { base, offset } => with_offset(base, *offset),
+            BleUuid::Full(uuid) => *uuid,
+        }
+    }
+
+    // The base and offset the SoftDevice needs, None for SIG UUIDs
+    pub fn base_and_offset(&self) -> Option<(uuid::Uuid, u16)> {
+        match self {
+            BleUuid::Sig(_) => None,
+            BleUuid::Vendor { base, offset } => Some((*base, *offset)),
+            BleUuid::Full(uuid) => Some((with_offset(uuid, 0), offset_of(uuid))),
+        }
+    }
+
+    pub fn as_sig(&self) -> Option<u16> {
+        match self {
+            BleUuid::Sig(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // Little-endian bytes as sent over the air
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            BleUuid::Sig(value) => value.to_le_bytes().to_vec(),
+            _ => {
+                let mut bytes = self.to_uuid128().as_bytes().to_vec();
+                bytes.reverse();
+                bytes
+            }
+        }
+    }
+}
+
+fn offset_of(uuid: &uuid::Uuid) -> u16 {
+    let bytes = uuid.as_bytes();
+    u16::from_be_bytes([bytes[OFFSET_INDEX], bytes[OFFSET_INDEX + 1]])
+}
+
+fn with_offset(uuid: &uuid::Uuid, offset: u16) -> uuid::Uuid {
+    let mut bytes = *uuid.as_bytes();
+    bytes[OFFSET_INDEX..OFFSET_INDEX + 2].copy_from_slice(&offset.to_be_bytes());
+    uuid::Uuid::from_bytes(bytes)
+}
+
+impl From<u16> for BleUuid {
+    fn from(value: u16) -> Self {
+        BleUuid::Sig(value)
+    }
+}
+
+impl From<uuid::Uuid> for BleUuid {
+    fn from(uuid: uuid::Uuid) -> Self {
+        BleUuid::from_uuid128(&uuid)
+    }
+}
+
+// Vendor and full UUIDs are equal if they describe the same 128-bit UUID
+impl PartialEq for BleUuid {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_uuid128() == other.to_uuid128()
+    }
+}
+
+impl Eq for BleUuid {}
+
+impl Hash for BleUuid {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_uuid128().hash(state);
+    }
+}
+
+// SIG UUIDs print as 0x180F, the rest as 6e400001-b5a3-f393-e0a9-e50e24dcca9e
+impl fmt::Display for BleUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BleUuid::Sig(value) => write!(f, "0x{:04X}", value),
+            _ => write!(f, "{}", self.to_uuid128().hyphenated()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseUuidError {
+    pub input: String,
+}
+
+impl fmt::Display for ParseUuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid BLE UUID: {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseUuidError {}
+
+// Accepts 16-bit values with or without a 0x prefix, or any format the uuid crate parses
+impl FromStr for BleUuid {
+    type Err = ParseUuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseUuidError {
+            input: s.to_string(),
+        };
+        let short = s.trim_start_matches("0x").trim_start_matches("0X");
+        if short.len() == 4 {
+            return u16::from_str_radix(short, 16)
+                .map(BleUuid::Sig)
+                .map_err(|_| error());
+        }
+        uuid::Uuid::parse_str(s)
+            .map(|uuid| BleUuid::from_uuid128(&uuid))
+            .map_err(|_| error())
+    }
+}
+
+// Vendor bases registered with the SoftDevice, each is only added once
+pub struct UuidRegistry {
+    driver: Arc<NrfDriver>,
+    bases: Mutex<HashMap<uuid::Uuid, u8>>,
+}
+
+impl UuidRegistry {
+    pub(crate) fn new(driver: &Arc<NrfDriver>) -> Arc<Self> {
+        Arc::new(Self {
+            driver: driver.clone(),
+            bases: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Returns the SoftDevice's uuid type for the base, registering it if needed
+    pub(crate) fn register_base(&self, base: &uuid::Uuid) -> NrfResult<u8> {
+        let base = with_offset(base, 0);
+        let mut bases = self.bases.lock().unwrap();
+        if let Some(uuid_type) = bases.get(&base) {
+            return Ok(*uuid_type);
+        }
+
+        // The SoftDevice takes the base in little-endian byte order
+        let mut base_le = *base.as_bytes();
+        base_le.reverse();
+        let uuid_type = self.driver.ble_uuid_vs_add(&base_le)?;
+        bases.insert(base, uuid_type);
+        Ok(uuid_type)
+    }
+
+    // Converts to the SoftDevice's representation, registering the base if needed
+    pub(crate) fn encode(&self, uuid: &BleUuid) -> NrfResult<SdUuid> {
+        match uuid.base_and_offset() {
+            None => Ok(SdUuid::from_sig(uuid.as_sig().unwrap())),
+            Some((base, offset)) => self
+                .register_base(&base)
+                .map(|uuid_type| SdUuid::new(offset, uuid_type)),
+        }
+    }
+
+    // A uuid type that wasn't registered through the registry keeps its offset on a nil base,
+    // so it can't be mistaken for any real UUID
+    pub(crate) fn decode(&self, uuid: &SdUuid) -> BleUuid {
+        if uuid.is_sig() {
+            return BleUuid::Sig(uuid.value);
+        }
+        let bases = self.bases.lock().unwrap();
+        let base = bases
+            .iter()
+            .find(|(_, uuid_type)| **uuid_type == uuid.uuid_type)
+            .map(|(base, _)| *base)
+            .unwrap_or_else(uuid::Uuid::nil);
+        BleUuid::Vendor {
+            base,
+            offset: uuid.value,
+        }
+    }
+}
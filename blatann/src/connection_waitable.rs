@@ -1,107 +1,110 @@
 use crate::peer::Peer;
 use blatann_event::{
-    AsyncEventHandler, Subscribable, Subscriber, SubscriberAction, Unsubscribable, Waitable,
+    AsyncEventHandler, Completion, Subscribable, Subscriber, SubscriberResult, TimeoutError,
+    Unsubscribable, Waitable,
 };
 use nrf_driver::driver::NrfDriver;
 use nrf_driver::gap::enums::{BleGapRole, BleGapTimeoutSource};
 use nrf_driver::gap::events::{GapEventConnected, GapEventTimeout};
-use std::cell::RefCell;
-use std::sync::mpsc::{RecvError, RecvTimeoutError};
-use std::sync::{mpsc, Arc, Mutex};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::RecvError;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
 use std::time::Duration;
 use uuid::Uuid;
 
 pub struct ConnectionWaitable {
     role: BleGapRole,
     peer: Arc<Peer>,
-    sender: mpsc::Sender<bool>,
-    receiver: mpsc::Receiver<bool>,
-    timeout_sub_id: RefCell<Option<Uuid>>,
-    connect_sub_id: RefCell<Option<Uuid>>,
-    callbacks: Mutex<Vec<Box<dyn FnOnce(Option<Arc<Peer>>)>>>,
+    completion: Arc<Completion<Option<Arc<Peer>>>>,
+    timeout_sub_id: Mutex<Option<Uuid>>,
+    connect_sub_id: Mutex<Option<Uuid>>,
 }
 
 impl ConnectionWaitable {
     pub(crate) fn new(driver: Arc<NrfDriver>, peer: Arc<Peer>, role: BleGapRole) -> Arc<Self> {
-        let (sender, receiver) = mpsc::channel();
         let waitable = Arc::new(Self {
             role,
             peer,
-            sender,
-            receiver,
-            timeout_sub_id: RefCell::new(None),
-            connect_sub_id: RefCell::new(None),
-            callbacks: Mutex::new(vec![]),
+            completion: Completion::new(),
+            timeout_sub_id: Mutex::new(None),
+            connect_sub_id: Mutex::new(None),
         });
 
         let connected_uuid = driver.events.connected.subscribe(waitable.clone());
         let timeout_uuid = driver.events.gap_timeout.subscribe(waitable.clone());
-        waitable.timeout_sub_id.replace(Some(timeout_uuid));
-        waitable.connect_sub_id.replace(Some(connected_uuid));
+        *waitable.timeout_sub_id.lock().unwrap() = Some(timeout_uuid);
+        *waitable.connect_sub_id.lock().unwrap() = Some(connected_uuid);
 
         return waitable;
     }
 
     fn event_received(&self, driver: Arc<NrfDriver>, success: bool) {
-        let sub_id = self.timeout_sub_id.borrow();
-        if let Some(id) = *sub_id {
+        if let Some(id) = *self.timeout_sub_id.lock().unwrap() {
             driver.events.gap_timeout.unsubscribe(id)
         }
-        let sub_id = self.connect_sub_id.borrow();
-        if let Some(id) = *sub_id {
+        if let Some(id) = *self.connect_sub_id.lock().unwrap() {
             driver.events.connected.unsubscribe(id)
         }
-        self.sender.send(success).unwrap();
-        let mut callbacks = self.callbacks.lock().unwrap();
-
-        for cb in callbacks.drain(..) {
-            if success {
-                (cb)(Some(self.peer.clone()))
-            } else {
-                (cb)(None)
-            }
+        if success {
+            self.completion.complete(Some(self.peer.clone()));
+        } else {
+            self.completion.complete(None);
         }
     }
+
+    // The connected peer or None on timeout, if the connection attempt has finished
+    pub fn result(&self) -> Option<Option<Arc<Peer>>> {
+        self.completion.result()
+    }
+}
+
+impl Future for ConnectionWaitable {
+    type Output = Option<Arc<Peer>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.completion.poll_result(cx)
+    }
+}
+
+// Awaited by reference since the waitable is handed out in an Arc
+impl Future for &ConnectionWaitable {
+    type Output = Option<Arc<Peer>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.completion.poll_result(cx)
+    }
 }
 
+// Connections are awaited from spawned tasks, so the waitable must stay Send
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<ConnectionWaitable>();
+    assert_send::<&ConnectionWaitable>();
+};
+
 impl Waitable<Option<Arc<Peer>>> for ConnectionWaitable {
-    fn wait_timeout(&self, timeout: Duration) -> Result<Option<Arc<Peer>>, RecvTimeoutError> {
-        self.receiver.recv_timeout(timeout).and_then(|result| {
-            if result {
-                Ok(Some(self.peer.clone()))
-            } else {
-                Ok(None)
-            }
-        })
+    fn wait_timeout(&self, timeout: Duration) -> Result<Option<Arc<Peer>>, TimeoutError> {
+        self.completion.wait_timeout(timeout)
     }
 
     fn wait(&self) -> Result<Option<Arc<Peer>>, RecvError> {
-        self.receiver.recv().and_then(|result| {
-            if result {
-                Ok(Some(self.peer.clone()))
-            } else {
-                Ok(None)
-            }
-        })
+        self.completion.wait()
     }
 }
 
 impl AsyncEventHandler<Option<Arc<Peer>>> for ConnectionWaitable {
     fn then<F>(&self, f: F)
     where
-        F: 'static + FnOnce(Option<Arc<Peer>>),
+        F: 'static + Send + FnOnce(Option<Arc<Peer>>),
     {
-        let mut callbacks = self.callbacks.lock().unwrap();
-        callbacks.push(Box::new(f))
+        self.completion.then(f)
     }
 }
 
 impl Subscriber<NrfDriver, GapEventTimeout> for ConnectionWaitable {
-    fn handle(
-        self: Arc<Self>,
-        sender: Arc<NrfDriver>,
-        event: GapEventTimeout,
-    ) -> Option<SubscriberAction> {
+    fn handle(self: Arc<Self>, sender: Arc<NrfDriver>, event: GapEventTimeout) -> SubscriberResult {
         match (self.role, event.src) {
             (BleGapRole::Peripheral, BleGapTimeoutSource::Advertising)
             | (BleGapRole::Central, BleGapTimeoutSource::Conn) => {
@@ -110,7 +113,7 @@ impl Subscriber<NrfDriver, GapEventTimeout> for ConnectionWaitable {
             _ => {}
         }
 
-        return None;
+        return Ok(None);
     }
 }
 
@@ -119,13 +122,13 @@ impl Subscriber<NrfDriver, GapEventConnected> for ConnectionWaitable {
         self: Arc<Self>,
         sender: Arc<NrfDriver>,
         event: GapEventConnected,
-    ) -> Option<SubscriberAction> {
+    ) -> SubscriberResult {
         match (self.role, event.role) {
             (BleGapRole::Peripheral, BleGapRole::Peripheral)
             | (BleGapRole::Central, BleGapRole::Central) => self.event_received(sender, true),
             _ => {}
         };
 
-        return None;
+        return Ok(None);
     }
 }
@@ -5,21 +5,35 @@ use nrf_driver::error::NrfError;
 use nrf_driver::DRIVER_MANAGER;
 
 use crate::advertiser::Advertiser;
+use crate::ble_uuid::{BleUuid, UuidRegistry};
+use crate::events::DeviceNameWrittenEvent;
+use crate::gatt::database_cache::GattcDatabaseCache;
+use crate::gatt::gatts::GattsDatabase;
 use crate::peer::{Peer, PeerRole};
-use blatann_event::{Subscribable, Subscriber, SubscriberAction};
+use crate::security::BondDatabase;
+use blatann_event::{Priority, Publisher, Subscribable, Subscriber, SubscriberResult};
+use nrf_driver::common::config::BleConfig;
+use nrf_driver::common::consts::UUID_GAP_DEVICE_NAME;
 use nrf_driver::common::events::CommonEventMemRequest;
-use nrf_driver::gap::enums::BleGapRole;
+use nrf_driver::error::NrfResult;
+use nrf_driver::gap::enums::{BleGapAppearance, BleGapRole, BleGapSecurityMode};
 use nrf_driver::gap::events::{GapEventConnected, GapEventDisconnected};
 use nrf_driver::gap::types::BleGapConnParams;
+use nrf_driver::gatts::events::GattsEventWrite;
+
+pub type Appearance = BleGapAppearance;
+pub type SecurityMode = BleGapSecurityMode;
 
 struct State {
     default_conn_params: BleGapConnParams,
+    config: BleConfig,
 }
 
 impl State {
     fn new(conn_params: BleGapConnParams) -> Self {
         Self {
             default_conn_params: conn_params,
+            config: Default::default(),
         }
     }
 }
@@ -34,8 +48,13 @@ pub struct BleDevice {
     port: String,
     driver: Arc<NrfDriver>,
     state: Mutex<State>,
+    database: Arc<GattsDatabase>,
+    gattc_cache: Arc<GattcDatabaseCache>,
+    bond_db: Arc<BondDatabase>,
+    uuid_registry: Arc<UuidRegistry>,
     pub advertiser: Arc<Advertiser>,
     pub central: Arc<Peer>,
+    pub on_device_name_written: Publisher<Self, DeviceNameWrittenEvent>,
 }
 
 impl BleDevice {
@@ -45,8 +64,19 @@ impl BleDevice {
             manager.create(port.clone(), baud, true)
         };
         let state: State = Default::default();
-        let central = Peer::new(&driver, PeerRole::Peripheral, &state.default_conn_params);
+        let gattc_cache: Arc<GattcDatabaseCache> = Default::default();
+        let bond_db = Arc::new(BondDatabase::new(&gattc_cache));
+        let uuid_registry = UuidRegistry::new(&driver);
+        let central = Peer::new(
+            &driver,
+            PeerRole::Peripheral,
+            &state.default_conn_params,
+            &gattc_cache,
+            &bond_db,
+            &uuid_registry,
+        );
         let advertiser = Advertiser::new(&driver, &central);
+        let database = GattsDatabase::new(&driver, &central);
 
         let device = Arc::new(Self {
             port,
@@ -54,15 +84,72 @@ impl BleDevice {
             driver: driver.clone(),
             central: central.clone(),
             state: Mutex::new(state),
+            database,
+            gattc_cache,
+            bond_db,
+            uuid_registry,
+            on_device_name_written: Publisher::new("On Device Name Written"),
         });
-        driver.events.connected.subscribe(device.clone());
-        driver.events.disconnected.subscribe(device.clone());
+        driver
+            .events
+            .connected
+            .subscribe_with_priority(device.clone(), Priority::Internal);
+        driver
+            .events
+            .disconnected
+            .subscribe_with_priority(device.clone(), Priority::Internal);
+        driver
+            .events
+            .gatts_write
+            .subscribe_with_priority(device.clone(), Priority::Internal);
 
         return device;
     }
 
+    // Configuration is applied to the SoftDevice when opened, must be called before open()
+    pub fn configure(&self, config: BleConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.config = config;
+    }
+
     pub fn open(&self) -> Result<(), NrfError> {
-        self.driver.open().and_then(|_| self.driver.ble_enable())
+        let config = { self.state.lock().unwrap().config };
+
+        self.driver
+            .open()
+            .and_then(|_| self.driver.ble_cfg_set(&config))
+            .and_then(|_| self.driver.ble_enable())
+    }
+
+    pub fn database(&self) -> &Arc<GattsDatabase> {
+        &self.database
+    }
+
+    // Databases discovered on peers, reused when they reconnect
+    pub fn gattc_cache(&self) -> &Arc<GattcDatabaseCache> {
+        &self.gattc_cache
+    }
+
+    pub fn bond_db(&self) -> &Arc<BondDatabase> {
+        &self.bond_db
+    }
+
+    // Registers a 128-bit vendor UUID base up front, BleUuids on it are otherwise registered when first used
+    pub fn register_uuid_base(&self, base: &uuid::Uuid) -> NrfResult<()> {
+        self.uuid_registry.register_base(base).map(|_| ())
+    }
+
+    // Use SecurityMode::NoAccess to prevent peers from writing the device name
+    pub fn set_device_name(&self, name: &str, write_permission: SecurityMode) -> NrfResult<()> {
+        self.driver.ble_gap_device_name_set(name, write_permission)
+    }
+
+    pub fn set_appearance(&self, appearance: Appearance) -> NrfResult<()> {
+        self.driver.ble_gap_appearance_set(appearance)
+    }
+
+    pub fn set_preferred_connection_params(&self, conn_params: BleGapConnParams) -> NrfResult<()> {
+        self.driver.ble_gap_ppcp_set(&conn_params)
     }
 }
 
@@ -79,13 +166,13 @@ impl Subscriber<NrfDriver, CommonEventMemRequest> for BleDevice {
         self: Arc<Self>,
         sender: Arc<NrfDriver>,
         event: CommonEventMemRequest,
-    ) -> Option<SubscriberAction> {
+    ) -> SubscriberResult {
         sender
             .ble_user_mem_reply(event.conn_handle)
             .unwrap_or_else(|e| {
                 error!("ble_user_mem_reply got error {:?}", e);
             });
-        return None;
+        return Ok(None);
     }
 }
 
@@ -94,13 +181,13 @@ impl Subscriber<NrfDriver, GapEventConnected> for BleDevice {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         event: GapEventConnected,
-    ) -> Option<SubscriberAction> {
+    ) -> SubscriberResult {
         if let BleGapRole::Peripheral = event.role {
             info!("Peer connected!");
             self.central
                 .peer_connected(event.conn_handle, &event.address, &event.conn_params);
         }
-        return None;
+        return Ok(None);
     }
 }
 
@@ -109,8 +196,23 @@ impl Subscriber<NrfDriver, GapEventDisconnected> for BleDevice {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         _event: GapEventDisconnected,
-    ) -> Option<SubscriberAction> {
+    ) -> SubscriberResult {
         // TODO
-        return None;
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventWrite> for BleDevice {
+    fn handle(self: Arc<Self>, sender: Arc<NrfDriver>, event: GattsEventWrite) -> SubscriberResult {
+        if self.uuid_registry.decode(&event.uuid) == BleUuid::from_sig(UUID_GAP_DEVICE_NAME) {
+            // Writes may be partial, get the full name back from the SoftDevice
+            match sender.ble_gap_device_name_get() {
+                Ok(name) => self
+                    .on_device_name_written
+                    .dispatch(self.clone(), DeviceNameWrittenEvent { name }),
+                Err(e) => error!("Failed to get device name after write: {:?}", e),
+            }
+        }
+        return Ok(None);
     }
 }
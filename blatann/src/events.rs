@@ -1,5 +1,10 @@
+use crate::gatt::gattc::GattcDatabase;
+use crate::gatt::stream_writer::ThroughputStats;
+use crate::gatt::{GattResult, GattStatus, NotificationType};
 use crate::peer::Phy;
+use crate::security::SecurityStatus;
 use nrf_driver::common::enums::BleHciStatus;
+use nrf_driver::gap::enums::BleGapSecurityMode;
 
 // No params
 #[derive(Debug, Copy, Clone)]
@@ -14,12 +19,19 @@ pub struct DisconnectionEvent {
     pub reason: BleHciStatus,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct PhyUpdateEvent {
     pub tx_phy: Phy,
     pub rx_phy: Phy,
 }
 
+// Dispatched whenever an MTU exchange completes, the size is unchanged if it failed
+#[derive(Debug, Copy, Clone)]
+pub struct MtuSizeUpdatedEvent {
+    pub previous_mtu_size: u16,
+    pub current_mtu_size: u16,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct DataLengthUpdateEvent {
     pub tx_bytes: u16,
@@ -27,3 +39,82 @@ pub struct DataLengthUpdateEvent {
     pub tx_time_us: u16,
     pub rx_time_us: u16,
 }
+
+#[derive(Debug, Clone)]
+pub struct DeviceNameWrittenEvent {
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseDiscoveryCompleteEvent {
+    pub status: GattStatus,
+    pub database: GattcDatabase,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadCompleteEvent {
+    pub handle: u16,
+    pub result: GattResult<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WriteCompleteEvent {
+    pub handle: u16,
+    pub data: Vec<u8>,
+    pub result: GattResult<()>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionWriteCompleteEvent {
+    pub notification_type: Option<NotificationType>,
+    pub result: GattResult<()>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationReceivedEvent {
+    pub notification_type: NotificationType,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ServiceChangedEvent {
+    pub start_handle: u16,
+    pub end_handle: u16,
+}
+
+// No params
+#[derive(Debug, Copy, Clone)]
+pub struct ServiceChangedConfirmEvent {}
+
+#[derive(Debug, Clone)]
+pub struct CharacteristicWrittenEvent {
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SubscriptionStateChangeEvent {
+    pub notification_type: Option<NotificationType>,
+}
+
+// Notifications that left the SoftDevice's queue, making room for more
+#[derive(Debug, Copy, Clone)]
+pub struct NotificationsSentEvent {
+    pub count: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamWriteCompleteEvent {
+    pub result: GattResult<()>,
+    pub stats: ThroughputStats,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PairingCompleteEvent {
+    pub status: SecurityStatus,
+    pub bonded: bool,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SecurityLevelChangedEvent {
+    pub security_mode: BleGapSecurityMode,
+}
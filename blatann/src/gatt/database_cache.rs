@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use nrf_driver::gap::types::BleGapAddress;
+
+use crate::gatt::gattc::GattcDatabase;
+
+// Discovered databases of bonded peers, keyed by the identity address of the bond.
+// Only bonded clients are guaranteed Service Changed indications, so unbonded peers aren't cached
+#[derive(Default)]
+pub struct GattcDatabaseCache {
+    databases: Mutex<HashMap<BleGapAddress, GattcDatabase>>,
+}
+
+impl GattcDatabaseCache {
+    pub fn get(&self, address: &BleGapAddress) -> Option<GattcDatabase> {
+        let databases = self.databases.lock().unwrap();
+        databases.get(address).cloned()
+    }
+
+    pub fn contains(&self, address: &BleGapAddress) -> bool {
+        let databases = self.databases.lock().unwrap();
+        databases.contains_key(address)
+    }
+
+    pub fn invalidate(&self, address: &BleGapAddress) {
+        let mut databases = self.databases.lock().unwrap();
+        databases.remove(address);
+    }
+
+    pub fn clear(&self) {
+        let mut databases = self.databases.lock().unwrap();
+        databases.clear();
+    }
+
+    pub(crate) fn store(&self, address: &BleGapAddress, database: &GattcDatabase) {
+        let mut databases = self.databases.lock().unwrap();
+        databases.insert(*address, database.clone());
+    }
+}
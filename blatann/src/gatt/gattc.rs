@@ -0,0 +1,656 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::common::consts::{CONN_HANDLE_INVALID, UUID_DESCRIPTOR_CCCD};
+use nrf_driver::common::types::ConnHandle;
+use nrf_driver::driver::NrfDriver;
+use nrf_driver::error::{NrfErrorType, NrfResult};
+use nrf_driver::gatt::enums::BleGattWriteOperation;
+use nrf_driver::gattc::events::{
+    GattcEventHvx, GattcEventReadResponse, GattcEventWriteCmdTxComplete, GattcEventWriteResponse,
+};
+
+use crate::assigned_numbers::NamedUuid;
+use crate::ble_uuid::BleUuid;
+use crate::consts::MTU_SIZE_DEFAULT;
+use crate::events::{
+    NotificationReceivedEvent, ReadCompleteEvent, SubscriptionWriteCompleteEvent,
+    WriteCompleteEvent,
+};
+use crate::gatt::{CharacteristicProperties, GattError, GattResult, GattStatus, NotificationType};
+
+// ATT read response opcode
+const READ_RSP_OVERHEAD: usize = 1;
+
+#[derive(Clone)]
+pub struct GattcDescriptor {
+    pub uuid: BleUuid,
+    pub handle: u16,
+}
+
+impl fmt::Debug for GattcDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GattcDescriptor")
+            .field("uuid", &NamedUuid(&self.uuid))
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+pub struct GattcCharacteristic {
+    pub uuid: BleUuid,
+    pub properties: CharacteristicProperties,
+    pub declaration_handle: u16,
+    pub value_handle: u16,
+    pub end_handle: u16,
+    pub descriptors: Vec<GattcDescriptor>,
+    client: Arc<GattcClient>,
+
+    pub on_read_complete: Publisher<Self, ReadCompleteEvent>,
+    pub on_write_complete: Publisher<Self, WriteCompleteEvent>,
+    pub on_subscription_change: Publisher<Self, SubscriptionWriteCompleteEvent>,
+    pub on_notification_received: Publisher<Self, NotificationReceivedEvent>,
+}
+
+impl GattcCharacteristic {
+    pub(crate) fn new(
+        client: &Arc<GattcClient>,
+        uuid: BleUuid,
+        properties: CharacteristicProperties,
+        declaration_handle: u16,
+        value_handle: u16,
+        end_handle: u16,
+        descriptors: Vec<GattcDescriptor>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            uuid,
+            properties,
+            declaration_handle,
+            value_handle,
+            end_handle,
+            descriptors,
+            client: client.clone(),
+            on_read_complete: Publisher::new("On Read Complete"),
+            on_write_complete: Publisher::new("On Write Complete"),
+            on_subscription_change: Publisher::new("On Subscription Change"),
+            on_notification_received: Publisher::new("On Notification Received"),
+        })
+    }
+
+    pub fn find_descriptor(&self, uuid: &BleUuid) -> Option<&GattcDescriptor> {
+        self.descriptors.iter().find(|d| d.uuid == *uuid)
+    }
+
+    pub fn cccd_handle(&self) -> Option<u16> {
+        self.find_descriptor(&BleUuid::from_sig(UUID_DESCRIPTOR_CCCD))
+            .map(|d| d.handle)
+    }
+
+    pub fn subscribe(
+        self: &Arc<Self>,
+        notification_type: NotificationType,
+    ) -> NrfResult<Arc<EventWaitable<Self, SubscriptionWriteCompleteEvent>>> {
+        let supported = match notification_type {
+            NotificationType::Notification => {
+                self.properties.contains(CharacteristicProperties::NOTIFY)
+            }
+            NotificationType::Indication => {
+                self.properties.contains(CharacteristicProperties::INDICATE)
+            }
+            NotificationType::Invalid => false,
+        };
+        if !supported {
+            return Err(NrfErrorType::NotSupported.to_error());
+        }
+
+        // The CCCD bits line up with the hvx type values (notification = 1, indication = 2)
+        self.write_cccd(notification_type as u16)
+    }
+
+    pub fn unsubscribe(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, SubscriptionWriteCompleteEvent>>> {
+        self.write_cccd(0)
+    }
+
+    fn write_cccd(
+        self: &Arc<Self>,
+        value: u16,
+    ) -> NrfResult<Arc<EventWaitable<Self, SubscriptionWriteCompleteEvent>>> {
+        let cccd_handle = self
+            .cccd_handle()
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        let waitable = EventWaitable::new(&self.on_subscription_change);
+
+        self.client
+            .write(
+                self,
+                cccd_handle,
+                BleGattWriteOperation::WriteRequest,
+                &value.to_le_bytes(),
+            )
+            .and_then(|_| Ok(waitable))
+    }
+
+    pub fn read(self: &Arc<Self>) -> NrfResult<Arc<EventWaitable<Self, ReadCompleteEvent>>> {
+        let waitable = EventWaitable::new(&self.on_read_complete);
+
+        self.client
+            .read(self, self.value_handle)
+            .and_then(|_| Ok(waitable))
+    }
+
+    pub fn write(
+        self: &Arc<Self>,
+        data: &[u8],
+    ) -> NrfResult<Arc<EventWaitable<Self, WriteCompleteEvent>>> {
+        self.write_impl(BleGattWriteOperation::WriteRequest, data)
+    }
+
+    pub fn write_without_response(
+        self: &Arc<Self>,
+        data: &[u8],
+    ) -> NrfResult<Arc<EventWaitable<Self, WriteCompleteEvent>>> {
+        self.write_impl(BleGattWriteOperation::WriteCommand, data)
+    }
+
+    fn write_impl(
+        self: &Arc<Self>,
+        write_op: BleGattWriteOperation,
+        data: &[u8],
+    ) -> NrfResult<Arc<EventWaitable<Self, WriteCompleteEvent>>> {
+        let waitable = EventWaitable::new(&self.on_write_complete);
+
+        self.client
+            .write(self, self.value_handle, write_op, data)
+            .and_then(|_| Ok(waitable))
+    }
+
+    // Queues a write command without creating a waitable, used for streaming
+    pub(crate) fn write_command(self: &Arc<Self>, data: &[u8]) -> NrfResult<()> {
+        self.client.write(
+            self,
+            self.value_handle,
+            BleGattWriteOperation::WriteCommand,
+            data,
+        )
+    }
+
+    pub(crate) fn mtu_size(&self) -> u16 {
+        self.client.mtu_size()
+    }
+
+    pub(crate) fn client(&self) -> &Arc<GattcClient> {
+        &self.client
+    }
+
+    fn read_complete(self: &Arc<Self>, handle: u16, result: GattResult<Vec<u8>>) {
+        self.on_read_complete
+            .dispatch(self.clone(), ReadCompleteEvent { handle, result });
+    }
+
+    fn write_complete(self: &Arc<Self>, handle: u16, data: Vec<u8>, result: GattResult<()>) {
+        if Some(handle) == self.cccd_handle() {
+            let notification_type = match data.first() {
+                Some(1) => Some(NotificationType::Notification),
+                Some(2) => Some(NotificationType::Indication),
+                _ => None,
+            };
+            self.on_subscription_change.dispatch(
+                self.clone(),
+                SubscriptionWriteCompleteEvent {
+                    notification_type,
+                    result,
+                },
+            );
+            return;
+        }
+
+        self.on_write_complete.dispatch(
+            self.clone(),
+            WriteCompleteEvent {
+                handle,
+                data,
+                result,
+            },
+        );
+    }
+
+    fn notification_received(self: &Arc<Self>, notification_type: NotificationType, data: Vec<u8>) {
+        self.on_notification_received.dispatch(
+            self.clone(),
+            NotificationReceivedEvent {
+                notification_type,
+                data,
+            },
+        );
+    }
+}
+
+impl fmt::Debug for GattcCharacteristic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GattcCharacteristic")
+            .field("uuid", &NamedUuid(&self.uuid))
+            .field("properties", &self.properties)
+            .field("declaration_handle", &self.declaration_handle)
+            .field("value_handle", &self.value_handle)
+            .field("end_handle", &self.end_handle)
+            .field("descriptors", &self.descriptors)
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct GattcService {
+    pub uuid: BleUuid,
+    pub start_handle: u16,
+    pub end_handle: u16,
+    pub characteristics: Vec<Arc<GattcCharacteristic>>,
+}
+
+impl fmt::Debug for GattcService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GattcService")
+            .field("uuid", &NamedUuid(&self.uuid))
+            .field("start_handle", &self.start_handle)
+            .field("end_handle", &self.end_handle)
+            .field("characteristics", &self.characteristics)
+            .finish()
+    }
+}
+
+impl GattcService {
+    pub fn find_characteristic(&self, uuid: &BleUuid) -> Option<&Arc<GattcCharacteristic>> {
+        self.characteristics.iter().find(|c| c.uuid == *uuid)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GattcDatabase {
+    pub services: Vec<GattcService>,
+}
+
+impl GattcDatabase {
+    pub fn find_service(&self, uuid: &BleUuid) -> Option<&GattcService> {
+        self.services.iter().find(|s| s.uuid == *uuid)
+    }
+
+    pub fn find_characteristic(&self, uuid: &BleUuid) -> Option<&Arc<GattcCharacteristic>> {
+        self.characteristics().find(|c| c.uuid == *uuid)
+    }
+
+    pub fn characteristics(&self) -> impl Iterator<Item = &Arc<GattcCharacteristic>> {
+        self.services.iter().flat_map(|s| s.characteristics.iter())
+    }
+
+    // Copy of the database with new characteristic objects bound to the client
+    pub(crate) fn rebind(&self, client: &Arc<GattcClient>) -> Self {
+        let services = self
+            .services
+            .iter()
+            .map(|s| GattcService {
+                uuid: s.uuid,
+                start_handle: s.start_handle,
+                end_handle: s.end_handle,
+                characteristics: s
+                    .characteristics
+                    .iter()
+                    .map(|c| {
+                        GattcCharacteristic::new(
+                            client,
+                            c.uuid,
+                            c.properties,
+                            c.declaration_handle,
+                            c.value_handle,
+                            c.end_handle,
+                            c.descriptors.clone(),
+                        )
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self { services }
+    }
+}
+
+struct PendingRead {
+    characteristic: Arc<GattcCharacteristic>,
+    handle: u16,
+    data: Vec<u8>,
+}
+
+struct PendingWrite {
+    characteristic: Arc<GattcCharacteristic>,
+    handle: u16,
+    data: Vec<u8>,
+}
+
+// Finished operations, dispatched once the client state is unlocked
+enum Completion {
+    Read(PendingRead, GattResult<()>),
+    Write(PendingWrite, GattResult<()>),
+}
+
+impl Completion {
+    fn dispatch(self) {
+        match self {
+            Completion::Read(op, result) => {
+                let PendingRead {
+                    characteristic,
+                    handle,
+                    data,
+                } = op;
+                characteristic.read_complete(handle, result.map(|_| data))
+            }
+            Completion::Write(op, result) => {
+                op.characteristic.write_complete(op.handle, op.data, result)
+            }
+        }
+    }
+}
+
+// Write commands sent on the connection, from any characteristic
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WriteCmdTxCompleteEvent;
+
+struct ClientState {
+    conn_handle: ConnHandle,
+    mtu_size: u16,
+    pending_read: Option<PendingRead>,
+    pending_write: Option<PendingWrite>,
+    pending_write_cmds: VecDeque<PendingWrite>,
+    // Characteristics of the peer's current database, notifications are only routed to these
+    characteristics: Vec<Weak<GattcCharacteristic>>,
+}
+
+impl Default for ClientState {
+    fn default() -> Self {
+        Self {
+            conn_handle: CONN_HANDLE_INVALID,
+            mtu_size: MTU_SIZE_DEFAULT as u16,
+            pending_read: None,
+            pending_write: None,
+            pending_write_cmds: VecDeque::new(),
+            characteristics: vec![],
+        }
+    }
+}
+
+// Tracks the in-flight GATT client operations for a single connection
+pub(crate) struct GattcClient {
+    driver: Arc<NrfDriver>,
+    state: Mutex<ClientState>,
+    // Dispatched after the characteristics' write completions, room has been freed in the
+    // SoftDevice's queue
+    pub on_write_cmd_tx_complete: Publisher<Self, WriteCmdTxCompleteEvent>,
+}
+
+impl GattcClient {
+    pub(crate) fn new(driver: &Arc<NrfDriver>) -> Arc<Self> {
+        let client = Arc::new(Self {
+            driver: driver.clone(),
+            state: Mutex::new(Default::default()),
+            on_write_cmd_tx_complete: Publisher::new("On Write Command TX Complete"),
+        });
+
+        driver
+            .events
+            .read_response
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        driver
+            .events
+            .write_response
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        driver
+            .events
+            .hvx
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        driver
+            .events
+            .write_cmd_tx_complete
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+
+        return client;
+    }
+
+    pub(crate) fn connection_started(&self, conn_handle: ConnHandle, mtu_size: u16) {
+        let mut state = self.state.lock().unwrap();
+        *state = ClientState {
+            conn_handle,
+            mtu_size,
+            ..Default::default()
+        };
+    }
+
+    pub(crate) fn connection_ended(&self) {
+        let completions: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            state.conn_handle = CONN_HANDLE_INVALID;
+
+            let reads = state
+                .pending_read
+                .take()
+                .into_iter()
+                .map(|read| Completion::Read(read, Err(GattError::Disconnected)));
+            let writes = state
+                .pending_write
+                .take()
+                .into_iter()
+                .chain(state.pending_write_cmds.drain(..))
+                .map(|write| Completion::Write(write, Err(GattError::Disconnected)));
+            reads.chain(writes).collect()
+        };
+
+        for completion in completions {
+            completion.dispatch();
+        }
+    }
+
+    pub(crate) fn mtu_size_updated(&self, mtu_size: u16) {
+        self.state.lock().unwrap().mtu_size = mtu_size;
+    }
+
+    fn mtu_size(&self) -> u16 {
+        self.state.lock().unwrap().mtu_size
+    }
+
+    // Replaces the characteristics notifications are routed to, e.g. with an empty database once
+    // the peer's has changed
+    pub(crate) fn set_database(&self, database: &GattcDatabase) {
+        let mut state = self.state.lock().unwrap();
+        state.characteristics = database.characteristics().map(Arc::downgrade).collect();
+    }
+
+    fn read(&self, characteristic: &Arc<GattcCharacteristic>, handle: u16) -> NrfResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.driver.ble_gattc_read(state.conn_handle, handle, 0)?;
+
+        state.pending_read = Some(PendingRead {
+            characteristic: characteristic.clone(),
+            handle,
+            data: vec![],
+        });
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        characteristic: &Arc<GattcCharacteristic>,
+        handle: u16,
+        write_op: BleGattWriteOperation,
+        data: &[u8],
+    ) -> NrfResult<()> {
+        let mut state = self.state.lock().unwrap();
+        self.driver
+            .ble_gattc_write(state.conn_handle, write_op, handle, 0, data)?;
+
+        let op = PendingWrite {
+            characteristic: characteristic.clone(),
+            handle,
+            data: data.to_vec(),
+        };
+        match write_op {
+            BleGattWriteOperation::WriteCommand => state.pending_write_cmds.push_back(op),
+            _ => state.pending_write = Some(op),
+        }
+        Ok(())
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventReadResponse> for GattcClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventReadResponse,
+    ) -> SubscriberResult {
+        let completion = {
+            let mut state = self.state.lock().unwrap();
+            let handle = match event.status {
+                GattStatus::Success => event.handle,
+                _ => event.error_handle,
+            };
+            match state.pending_read {
+                Some(ref read)
+                    if state.conn_handle == event.conn_handle && read.handle == handle => {}
+                _ => return Ok(None),
+            }
+            let mut read = state.pending_read.take().unwrap();
+
+            let result = match event.status {
+                GattStatus::Success => {
+                    read.data.extend_from_slice(&event.data);
+                    let max_chunk_size = state.mtu_size as usize - READ_RSP_OVERHEAD;
+                    if event.data.len() < max_chunk_size {
+                        Ok(())
+                    } else {
+                        // The value may continue past this response, keep reading with blob reads
+                        let offset = read.data.len() as u16;
+                        match self
+                            .driver
+                            .ble_gattc_read(state.conn_handle, handle, offset)
+                        {
+                            Ok(_) => {
+                                state.pending_read = Some(read);
+                                return Ok(None);
+                            }
+                            Err(e) => Err(e.into()),
+                        }
+                    }
+                }
+                // The value ended exactly on the previous response boundary
+                GattStatus::AttributeNotLong | GattStatus::InvalidOffset
+                    if !read.data.is_empty() =>
+                {
+                    Ok(())
+                }
+                status => Err(status.into()),
+            };
+            Completion::Read(read, result)
+        };
+
+        completion.dispatch();
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventWriteResponse> for GattcClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventWriteResponse,
+    ) -> SubscriberResult {
+        let completion = {
+            let mut state = self.state.lock().unwrap();
+            let handle = match event.status {
+                GattStatus::Success => event.handle,
+                _ => event.error_handle,
+            };
+            match state.pending_write {
+                Some(ref write)
+                    if state.conn_handle == event.conn_handle && write.handle == handle => {}
+                _ => return Ok(None),
+            }
+            let write = state.pending_write.take().unwrap();
+
+            let result = match event.status {
+                GattStatus::Success => Ok(()),
+                status => Err(status.into()),
+            };
+            Completion::Write(write, result)
+        };
+
+        completion.dispatch();
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventWriteCmdTxComplete> for GattcClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventWriteCmdTxComplete,
+    ) -> SubscriberResult {
+        let completions: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            if state.conn_handle != event.conn_handle {
+                return Ok(None);
+            }
+            let count = (event.count as usize).min(state.pending_write_cmds.len());
+            state
+                .pending_write_cmds
+                .drain(..count)
+                .map(|write| Completion::Write(write, Ok(())))
+                .collect()
+        };
+
+        for completion in completions {
+            completion.dispatch();
+        }
+        self.on_write_cmd_tx_complete
+            .dispatch(self.clone(), WriteCmdTxCompleteEvent);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventHvx> for GattcClient {
+    fn handle(self: Arc<Self>, _sender: Arc<NrfDriver>, event: GattcEventHvx) -> SubscriberResult {
+        let characteristic = {
+            let state = self.state.lock().unwrap();
+            if state.conn_handle != event.conn_handle {
+                return Ok(None);
+            }
+            state
+                .characteristics
+                .iter()
+                .filter_map(|c| c.upgrade())
+                .find(|c| c.value_handle == event.handle)
+        };
+
+        if event.hvx_type == NotificationType::Indication {
+            if let Err(e) = self
+                .driver
+                .ble_gattc_hv_confirm(event.conn_handle, event.handle)
+            {
+                error!(
+                    "Failed to confirm indication on handle {}: {:?}",
+                    event.handle, e
+                );
+            }
+        }
+
+        match characteristic {
+            Some(c) => c.notification_received(event.hvx_type, event.data),
+            None => warn!(
+                "Received {:?} for unknown handle {}",
+                event.hvx_type, event.handle
+            ),
+        }
+        return Ok(None);
+    }
+}
@@ -0,0 +1,398 @@
+use std::sync::{Arc, Mutex};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::common::consts::GATT_HANDLE_INVALID;
+use nrf_driver::driver::NrfDriver;
+use nrf_driver::error::NrfResult;
+use nrf_driver::gap::enums::BleGapSecurityMode;
+use nrf_driver::gatts::enums::BleGattsServiceType;
+use nrf_driver::gatts::events::{
+    BleGattsAuthorizeRequest, GattsEventHvnTxComplete, GattsEventRwAuthorizeRequest,
+    GattsEventScConfirm, GattsEventSysAttrMissing, GattsEventWrite,
+};
+
+use crate::ble_uuid::BleUuid;
+use crate::events::{
+    CharacteristicWrittenEvent, DisconnectionEvent, NotificationsSentEvent,
+    ServiceChangedConfirmEvent, SubscriptionStateChangeEvent,
+};
+use crate::gatt::{
+    CharacteristicParams, CharacteristicProperties, GattStatus, HandleRange, NotificationType,
+};
+use crate::peer::Peer;
+
+// GATT status codes of ATT errors are the error code offset by this
+const GATT_STATUS_ATTERR_BASE: u16 = 0x0100;
+
+// Returns the ATT error code to reject the write with
+type WriteAuthorizer = Arc<dyn Fn(&[u8]) -> Result<(), u8> + Send + Sync>;
+
+pub struct GattsCharacteristic {
+    pub uuid: BleUuid,
+    pub properties: CharacteristicProperties,
+    pub value_handle: u16,
+    pub user_desc_handle: u16,
+    pub cccd_handle: u16,
+    driver: Arc<NrfDriver>,
+    peer: Arc<Peer>,
+    subscription: Mutex<Option<NotificationType>>,
+    write_authorizer: Mutex<Option<WriteAuthorizer>>,
+
+    pub on_write: Publisher<Self, CharacteristicWrittenEvent>,
+    pub on_subscription_change: Publisher<Self, SubscriptionStateChangeEvent>,
+}
+
+impl GattsCharacteristic {
+    fn new(
+        driver: &Arc<NrfDriver>,
+        peer: &Arc<Peer>,
+        params: &CharacteristicParams,
+        service_handle: u16,
+    ) -> NrfResult<Arc<Self>> {
+        let uuid = peer.uuid_registry().encode(&params.uuid)?;
+        let handles =
+            driver.ble_gatts_characteristic_add(service_handle, &params.replace_uuid(uuid))?;
+
+        let characteristic = Arc::new(Self {
+            uuid: params.uuid,
+            properties: params.properties,
+            value_handle: handles.value_handle,
+            user_desc_handle: handles.user_desc_handle,
+            cccd_handle: handles.cccd_handle,
+            driver: driver.clone(),
+            peer: peer.clone(),
+            subscription: Mutex::new(None),
+            write_authorizer: Mutex::new(None),
+            on_write: Publisher::new("On Characteristic Written"),
+            on_subscription_change: Publisher::new("On Subscription State Change"),
+        });
+
+        driver
+            .events
+            .gatts_write
+            .subscribe_with_priority(characteristic.clone(), Priority::Internal);
+        if params.write_authorization {
+            driver
+                .events
+                .rw_authorize_request
+                .subscribe_with_priority(characteristic.clone(), Priority::Internal);
+        }
+        peer.on_disconnect
+            .subscribe_with_priority(characteristic.clone(), Priority::Internal);
+
+        return Ok(characteristic);
+    }
+
+    pub fn set_value(&self, data: &[u8]) -> NrfResult<()> {
+        self.driver.ble_gatts_value_set(self.value_handle, data)
+    }
+
+    pub fn get_value(&self) -> NrfResult<Vec<u8>> {
+        self.driver.ble_gatts_value_get(self.value_handle)
+    }
+
+    // Adds a read-only descriptor. The SoftDevice places descriptors after the last added
+    // characteristic, so this must be called before adding the next one
+    pub fn add_descriptor(
+        &self,
+        uuid: &BleUuid,
+        value: &[u8],
+        read_permission: BleGapSecurityMode,
+    ) -> NrfResult<u16> {
+        let uuid = self.peer.uuid_registry().encode(uuid)?;
+        self.driver.ble_gatts_descriptor_add(
+            GATT_HANDLE_INVALID,
+            &uuid,
+            read_permission,
+            BleGapSecurityMode::NoAccess,
+            value.len() as u16,
+            value,
+        )
+    }
+
+    // Checks writes to a characteristic added with write authorization before they're applied.
+    // Without an authorizer all writes are accepted
+    pub fn set_write_authorizer<F>(&self, f: F)
+    where
+        F: Fn(&[u8]) -> Result<(), u8> + Send + Sync + 'static,
+    {
+        *self.write_authorizer.lock().unwrap() = Some(Arc::new(f));
+    }
+
+    pub fn subscription(&self) -> Option<NotificationType> {
+        *self.subscription.lock().unwrap()
+    }
+
+    // Updates the value and sends it to the client if it has subscribed to the characteristic
+    pub fn notify(&self, data: &[u8]) -> NrfResult<()> {
+        match self.subscription() {
+            Some(hvx_type) => self.driver.ble_gatts_hvx(
+                self.peer.conn_handle(),
+                hvx_type,
+                self.value_handle,
+                data,
+            ),
+            None => self.set_value(data),
+        }
+    }
+
+    fn cccd_written(self: &Arc<Self>, data: &[u8]) {
+        let cccd = data.get(0).copied().unwrap_or(0);
+        // Indications take precedence if the client enabled both
+        let notification_type = if cccd & NotificationType::Indication as u8 != 0 {
+            Some(NotificationType::Indication)
+        } else if cccd & NotificationType::Notification as u8 != 0 {
+            Some(NotificationType::Notification)
+        } else {
+            None
+        };
+
+        *self.subscription.lock().unwrap() = notification_type;
+        self.on_subscription_change.dispatch(
+            self.clone(),
+            SubscriptionStateChangeEvent { notification_type },
+        );
+    }
+
+    fn value_written(self: &Arc<Self>) {
+        // Writes may be partial, get the full value back from the SoftDevice
+        match self.get_value() {
+            Ok(value) => self
+                .on_write
+                .dispatch(self.clone(), CharacteristicWrittenEvent { value }),
+            Err(e) => error!("Failed to get characteristic value after write: {:?}", e),
+        }
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventWrite> for GattsCharacteristic {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattsEventWrite,
+    ) -> SubscriberResult {
+        if event.conn_handle != self.peer.conn_handle() {
+            return Ok(None);
+        }
+
+        if self.cccd_handle != 0 && event.handle == self.cccd_handle {
+            self.cccd_written(&event.data);
+        } else if event.handle == self.value_handle {
+            self.value_written();
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventRwAuthorizeRequest> for GattsCharacteristic {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GattsEventRwAuthorizeRequest,
+    ) -> SubscriberResult {
+        if event.conn_handle != self.peer.conn_handle() {
+            return Ok(None);
+        }
+        let write = match event.request {
+            BleGattsAuthorizeRequest::Write(write) if write.handle == self.value_handle => write,
+            _ => return Ok(None),
+        };
+
+        let authorizer = self.write_authorizer.lock().unwrap().clone();
+        let result = match authorizer {
+            Some(f) => f(&write.data),
+            None => Ok(()),
+        };
+        let gatt_status = match result {
+            Ok(()) => GattStatus::Success as u16,
+            Err(code) => GATT_STATUS_ATTERR_BASE | code as u16,
+        };
+        sender.ble_gatts_write_authorize_reply(
+            event.conn_handle,
+            gatt_status,
+            write.offset,
+            &write.data,
+        )?;
+
+        if result.is_ok() {
+            self.value_written();
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<Peer, DisconnectionEvent> for GattsCharacteristic {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: DisconnectionEvent) -> SubscriberResult {
+        *self.subscription.lock().unwrap() = None;
+        return Ok(None);
+    }
+}
+
+pub struct GattsService {
+    pub uuid: BleUuid,
+    pub service_handle: u16,
+    driver: Arc<NrfDriver>,
+    peer: Arc<Peer>,
+    characteristics: Mutex<Vec<Arc<GattsCharacteristic>>>,
+}
+
+impl GattsService {
+    pub fn add_characteristic(
+        &self,
+        params: &CharacteristicParams,
+    ) -> NrfResult<Arc<GattsCharacteristic>> {
+        let characteristic =
+            GattsCharacteristic::new(&self.driver, &self.peer, params, self.service_handle)?;
+
+        let mut characteristics = self.characteristics.lock().unwrap();
+        characteristics.push(characteristic.clone());
+        Ok(characteristic)
+    }
+
+    pub fn characteristics(&self) -> Vec<Arc<GattsCharacteristic>> {
+        self.characteristics.lock().unwrap().clone()
+    }
+}
+
+// The local GATT server database
+pub struct GattsDatabase {
+    driver: Arc<NrfDriver>,
+    peer: Arc<Peer>,
+    services: Mutex<Vec<Arc<GattsService>>>,
+
+    pub on_service_changed_confirmed: Publisher<Self, ServiceChangedConfirmEvent>,
+    pub on_notifications_sent: Publisher<Self, NotificationsSentEvent>,
+}
+
+impl GattsDatabase {
+    pub(crate) fn new(driver: &Arc<NrfDriver>, peer: &Arc<Peer>) -> Arc<Self> {
+        let database = Arc::new(Self {
+            driver: driver.clone(),
+            peer: peer.clone(),
+            services: Mutex::new(vec![]),
+            on_service_changed_confirmed: Publisher::new("On Service Changed Confirmed"),
+            on_notifications_sent: Publisher::new("On Notifications Sent"),
+        });
+
+        driver
+            .events
+            .sys_attr_missing
+            .subscribe_with_priority(database.clone(), Priority::Internal);
+        driver
+            .events
+            .service_changed_confirm
+            .subscribe_with_priority(database.clone(), Priority::Internal);
+        driver
+            .events
+            .hvn_tx_complete
+            .subscribe_with_priority(database.clone(), Priority::Internal);
+
+        return database;
+    }
+
+    pub fn add_service(&self, uuid: BleUuid) -> NrfResult<Arc<GattsService>> {
+        let sd_uuid = self.peer.uuid_registry().encode(&uuid)?;
+        let service_handle = self
+            .driver
+            .ble_gatts_service_add(BleGattsServiceType::Primary, &sd_uuid)?;
+
+        let service = Arc::new(GattsService {
+            uuid,
+            service_handle,
+            driver: self.driver.clone(),
+            peer: self.peer.clone(),
+            characteristics: Mutex::new(vec![]),
+        });
+
+        let mut services = self.services.lock().unwrap();
+        services.push(service.clone());
+        Ok(service)
+    }
+
+    pub fn services(&self) -> Vec<Arc<GattsService>> {
+        self.services.lock().unwrap().clone()
+    }
+
+    pub(crate) fn peer(&self) -> &Arc<Peer> {
+        &self.peer
+    }
+
+    // Indicates to the connected client that the attributes within the range were modified
+    pub fn notify_service_changed(
+        self: &Arc<Self>,
+        range: HandleRange,
+    ) -> NrfResult<Arc<EventWaitable<Self, ServiceChangedConfirmEvent>>> {
+        let waitable = EventWaitable::new(&self.on_service_changed_confirmed);
+
+        self.driver
+            .ble_gatts_service_changed(
+                self.peer.conn_handle(),
+                range.start_handle,
+                range.end_handle,
+            )
+            .and_then(|_| Ok(waitable))
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventSysAttrMissing> for GattsDatabase {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GattsEventSysAttrMissing,
+    ) -> SubscriberResult {
+        let sys_attr = if event.conn_handle == self.peer.conn_handle() {
+            self.peer.security().bond().and_then(|bond| bond.sys_attr)
+        } else {
+            None
+        };
+
+        // Only unbonded peers, or bonded ones that haven't disconnected yet, start from defaults
+        let result = match sys_attr {
+            Some(sys_attr) => sender
+                .ble_gatts_sys_attr_set(event.conn_handle, &sys_attr)
+                .or_else(|e| {
+                    warn!(
+                        "Failed to restore system attributes, using defaults: {:?}",
+                        e
+                    );
+                    sender.ble_gatts_sys_attr_set_default(event.conn_handle)
+                }),
+            None => sender.ble_gatts_sys_attr_set_default(event.conn_handle),
+        };
+        result.unwrap_or_else(|e| {
+            error!("Failed to set system attributes: {:?}", e);
+        });
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventScConfirm> for GattsDatabase {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattsEventScConfirm,
+    ) -> SubscriberResult {
+        if event.conn_handle == self.peer.conn_handle() {
+            self.on_service_changed_confirmed
+                .dispatch(self.clone(), ServiceChangedConfirmEvent {});
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattsEventHvnTxComplete> for GattsDatabase {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattsEventHvnTxComplete,
+    ) -> SubscriberResult {
+        if event.conn_handle == self.peer.conn_handle() {
+            self.on_notifications_sent
+                .dispatch(self.clone(), NotificationsSentEvent { count: event.count });
+        }
+        return Ok(None);
+    }
+}
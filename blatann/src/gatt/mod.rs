@@ -0,0 +1,54 @@
+use std::fmt;
+
+use nrf_driver::error::NrfError;
+use nrf_driver::gatt::enums::{BleGattCharProperties, BleGattHvxType, BleGattStatusCode};
+use nrf_driver::gattc::types::BleGattcHandleRange;
+use nrf_driver::gatts::types::BleGattsCharacteristicParams;
+
+use crate::ble_uuid::BleUuid;
+
+pub mod database_cache;
+pub mod gattc;
+pub mod gatts;
+pub mod service_discovery;
+pub mod stream_writer;
+
+pub type CharacteristicProperties = BleGattCharProperties;
+pub type GattStatus = BleGattStatusCode;
+pub type NotificationType = BleGattHvxType;
+pub type HandleRange = BleGattcHandleRange;
+pub type CharacteristicParams = BleGattsCharacteristicParams<BleUuid>;
+
+pub type GattResult<T> = Result<T, GattError>;
+
+#[derive(Debug, Copy, Clone)]
+pub enum GattError {
+    // The peer responded with an ATT error
+    Status(GattStatus),
+    // The driver failed to issue a follow-up request for the operation
+    Driver(NrfError),
+    // The peer disconnected before the operation completed
+    Disconnected,
+}
+
+impl From<GattStatus> for GattError {
+    fn from(status: GattStatus) -> Self {
+        GattError::Status(status)
+    }
+}
+
+impl From<NrfError> for GattError {
+    fn from(err: NrfError) -> Self {
+        GattError::Driver(err)
+    }
+}
+
+impl fmt::Display for GattError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GattError::Status(status) => write!(f, "Gatt status {:?}", status),
+            GattError::Driver(err) => write!(f, "Driver error {}", err),
+            GattError::Disconnected => write!(f, "Disconnected"),
+        }
+    }
+}
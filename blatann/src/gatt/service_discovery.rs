@@ -0,0 +1,503 @@
+use std::convert::TryInto;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use blatann_event::{Priority, Publisher, Subscribable, Subscriber, SubscriberResult};
+
+use nrf_driver::common::consts::CONN_HANDLE_INVALID;
+use nrf_driver::common::types::{BleUuid as SdUuid, ConnHandle};
+use nrf_driver::driver::NrfDriver;
+use nrf_driver::error::NrfResult;
+use nrf_driver::gattc::events::{
+    GattcEventCharacteristicDiscoveryResponse, GattcEventDescriptorDiscoveryResponse,
+    GattcEventPrimaryServiceDiscoveryResponse, GattcEventReadResponse,
+};
+use nrf_driver::gattc::types::BleGattcHandleRange;
+
+use crate::ble_uuid::{self, UuidRegistry};
+use crate::events::DatabaseDiscoveryCompleteEvent;
+use crate::gatt::gattc::{
+    GattcCharacteristic, GattcClient, GattcDatabase, GattcDescriptor, GattcService,
+};
+use crate::gatt::{CharacteristicProperties, GattStatus};
+
+const HANDLE_START: u16 = 0x0001;
+const HANDLE_MAX: u16 = 0xFFFF;
+const UUID128_LEN: usize = 16;
+// Characteristic declaration value: properties (1), value handle (2), uuid
+const CHAR_DECL_UUID_OFFSET: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Stage {
+    Idle,
+    Services,
+    ServiceUuids,
+    Characteristics,
+    CharacteristicUuids,
+    Descriptors,
+}
+
+// Discovered attributes, converted into the client database once discovery is complete
+struct DiscoveredCharacteristic {
+    uuid: SdUuid,
+    properties: CharacteristicProperties,
+    declaration_handle: u16,
+    value_handle: u16,
+    end_handle: u16,
+    descriptors: Vec<GattcDescriptor>,
+}
+
+struct DiscoveredService {
+    uuid: SdUuid,
+    start_handle: u16,
+    end_handle: u16,
+    characteristics: Vec<DiscoveredCharacteristic>,
+}
+
+struct State {
+    conn_handle: ConnHandle,
+    stage: Stage,
+    services: Vec<DiscoveredService>,
+    service_index: usize,
+    char_index: usize,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            conn_handle: CONN_HANDLE_INVALID,
+            stage: Stage::Idle,
+            services: vec![],
+            service_index: 0,
+            char_index: 0,
+        }
+    }
+}
+
+// Outcome of processing a response, acted upon after the state lock is released
+enum Step {
+    Continue,
+    Complete,
+    Failed(GattStatus),
+}
+
+pub struct DatabaseDiscoverer {
+    driver: Arc<NrfDriver>,
+    client: Arc<GattcClient>,
+    uuid_registry: Arc<UuidRegistry>,
+    state: Mutex<State>,
+    pub on_discovery_complete: Publisher<Self, DatabaseDiscoveryCompleteEvent>,
+}
+
+impl DatabaseDiscoverer {
+    pub(crate) fn new(
+        driver: &Arc<NrfDriver>,
+        client: &Arc<GattcClient>,
+        uuid_registry: &Arc<UuidRegistry>,
+    ) -> Arc<Self> {
+        let discoverer = Arc::new(Self {
+            driver: driver.clone(),
+            client: client.clone(),
+            uuid_registry: uuid_registry.clone(),
+            state: Mutex::new(Default::default()),
+            on_discovery_complete: Publisher::new("On Discovery Complete"),
+        });
+
+        driver
+            .events
+            .primary_service_discovery_response
+            .subscribe_with_priority(discoverer.clone(), Priority::Internal);
+        driver
+            .events
+            .characteristic_discovery_response
+            .subscribe_with_priority(discoverer.clone(), Priority::Internal);
+        driver
+            .events
+            .descriptor_discovery_response
+            .subscribe_with_priority(discoverer.clone(), Priority::Internal);
+        driver
+            .events
+            .read_response
+            .subscribe_with_priority(discoverer.clone(), Priority::Internal);
+
+        return discoverer;
+    }
+
+    pub(crate) fn start(&self, conn_handle: ConnHandle) -> NrfResult<()> {
+        let mut state = self.state.lock().unwrap();
+        *state = State {
+            conn_handle,
+            stage: Stage::Services,
+            ..Default::default()
+        };
+
+        let result =
+            self.driver
+                .ble_gattc_primary_services_discover(conn_handle, HANDLE_START, None);
+        if result.is_err() {
+            state.stage = Stage::Idle;
+        }
+        result
+    }
+
+    fn active_state_for(
+        &self,
+        conn_handle: ConnHandle,
+        stage: Stage,
+    ) -> Option<MutexGuard<'_, State>> {
+        let state = self.state.lock().unwrap();
+        if state.conn_handle == conn_handle && state.stage == stage {
+            Some(state)
+        } else {
+            None
+        }
+    }
+
+    fn finish(self: &Arc<Self>, step: NrfResult<Step>) {
+        let status = match step {
+            Ok(Step::Continue) => return,
+            Ok(Step::Complete) => GattStatus::Success,
+            Ok(Step::Failed(status)) => status,
+            Err(e) => {
+                error!("Database discovery failed with error {:?}", e);
+                GattStatus::Unknown
+            }
+        };
+
+        let services = {
+            let mut state = self.state.lock().unwrap();
+            state.stage = Stage::Idle;
+            state
+                .services
+                .drain(..)
+                .map(|s| GattcService {
+                    uuid: self.uuid_registry.decode(&s.uuid),
+                    start_handle: s.start_handle,
+                    end_handle: s.end_handle,
+                    characteristics: s
+                        .characteristics
+                        .into_iter()
+                        .map(|c| {
+                            GattcCharacteristic::new(
+                                &self.client,
+                                self.uuid_registry.decode(&c.uuid),
+                                c.properties,
+                                c.declaration_handle,
+                                c.value_handle,
+                                c.end_handle,
+                                c.descriptors,
+                            )
+                        })
+                        .collect(),
+                })
+                .collect()
+        };
+
+        self.on_discovery_complete.dispatch(
+            self.clone(),
+            DatabaseDiscoveryCompleteEvent {
+                status,
+                database: GattcDatabase { services },
+            },
+        );
+    }
+
+    fn next_service_uuid(&self, state: &mut State) -> NrfResult<Step> {
+        let next = (state.service_index..state.services.len())
+            .find(|i| state.services[*i].uuid.is_unknown());
+
+        match next {
+            Some(i) => {
+                state.stage = Stage::ServiceUuids;
+                state.service_index = i;
+                self.driver
+                    .ble_gattc_read(state.conn_handle, state.services[i].start_handle, 0)
+                    .and(Ok(Step::Continue))
+            }
+            None => {
+                state.stage = Stage::Characteristics;
+                state.service_index = 0;
+                self.next_characteristics(state, None)
+            }
+        }
+    }
+
+    fn next_characteristics(
+        &self,
+        state: &mut State,
+        start_handle: Option<u16>,
+    ) -> NrfResult<Step> {
+        let mut start_handle = start_handle;
+        while state.service_index < state.services.len() {
+            let service = &state.services[state.service_index];
+            let start = start_handle.unwrap_or(service.start_handle);
+            if start <= service.end_handle {
+                let range = BleGattcHandleRange::new(start, service.end_handle);
+                return self
+                    .driver
+                    .ble_gattc_characteristics_discover(state.conn_handle, &range)
+                    .and(Ok(Step::Continue));
+            }
+            state.service_index += 1;
+            start_handle = None;
+        }
+
+        state.service_index = 0;
+        state.char_index = 0;
+        self.next_characteristic_uuid(state)
+    }
+
+    fn next_characteristic_uuid(&self, state: &mut State) -> NrfResult<Step> {
+        state.stage = Stage::CharacteristicUuids;
+        while state.service_index < state.services.len() {
+            let characteristics = &state.services[state.service_index].characteristics;
+            if let Some(c) = characteristics.get(state.char_index) {
+                if c.uuid.is_unknown() {
+                    return self
+                        .driver
+                        .ble_gattc_read(state.conn_handle, c.declaration_handle, 0)
+                        .and(Ok(Step::Continue));
+                }
+                state.char_index += 1;
+            } else {
+                state.service_index += 1;
+                state.char_index = 0;
+            }
+        }
+
+        state.stage = Stage::Descriptors;
+        state.service_index = 0;
+        state.char_index = 0;
+        self.next_descriptors(state, None)
+    }
+
+    fn next_descriptors(&self, state: &mut State, start_handle: Option<u16>) -> NrfResult<Step> {
+        let mut start_handle = start_handle;
+        while state.service_index < state.services.len() {
+            let characteristics = &state.services[state.service_index].characteristics;
+            if let Some(c) = characteristics.get(state.char_index) {
+                // Descriptors are located between the value handle and the end of the characteristic
+                let start = start_handle.unwrap_or(c.value_handle.saturating_add(1));
+                if c.value_handle < c.end_handle && start <= c.end_handle {
+                    let range = BleGattcHandleRange::new(start, c.end_handle);
+                    return self
+                        .driver
+                        .ble_gattc_descriptors_discover(state.conn_handle, &range)
+                        .and(Ok(Step::Continue));
+                }
+                state.char_index += 1;
+            } else {
+                state.service_index += 1;
+                state.char_index = 0;
+            }
+            start_handle = None;
+        }
+
+        Ok(Step::Complete)
+    }
+
+    fn register_uuid(&self, uuid_le: &[u8]) -> NrfResult<SdUuid> {
+        let mut bytes: [u8; UUID128_LEN] = uuid_le.try_into().unwrap();
+        bytes.reverse();
+        let uuid = ble_uuid::BleUuid::from_uuid128(&uuid::Uuid::from_bytes(bytes));
+        self.uuid_registry.encode(&uuid)
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventPrimaryServiceDiscoveryResponse> for DatabaseDiscoverer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventPrimaryServiceDiscoveryResponse,
+    ) -> SubscriberResult {
+        let step = {
+            let mut state = match self.active_state_for(event.conn_handle, Stage::Services) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            match event.status {
+                GattStatus::Success => {
+                    state
+                        .services
+                        .extend(event.services.iter().map(|s| DiscoveredService {
+                            uuid: s.uuid,
+                            start_handle: s.handle_range.start_handle,
+                            end_handle: s.handle_range.end_handle,
+                            characteristics: vec![],
+                        }));
+                    match event.services.last() {
+                        Some(s) if s.handle_range.end_handle < HANDLE_MAX => self
+                            .driver
+                            .ble_gattc_primary_services_discover(
+                                state.conn_handle,
+                                s.handle_range.end_handle + 1,
+                                None,
+                            )
+                            .and(Ok(Step::Continue)),
+                        _ => {
+                            state.service_index = 0;
+                            self.next_service_uuid(&mut state)
+                        }
+                    }
+                }
+                GattStatus::AttributeNotFound => {
+                    state.service_index = 0;
+                    self.next_service_uuid(&mut state)
+                }
+                status => Ok(Step::Failed(status)),
+            }
+        };
+
+        self.finish(step);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventCharacteristicDiscoveryResponse> for DatabaseDiscoverer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventCharacteristicDiscoveryResponse,
+    ) -> SubscriberResult {
+        let step = {
+            let mut state = match self.active_state_for(event.conn_handle, Stage::Characteristics) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let index = state.service_index;
+            match event.status {
+                GattStatus::Success => {
+                    let service = &mut state.services[index];
+                    for c in event.characteristics.iter() {
+                        // The previous characteristic ends right before this one's declaration
+                        if let Some(previous) = service.characteristics.last_mut() {
+                            previous.end_handle = c.handle_decl.saturating_sub(1);
+                        }
+                        service.characteristics.push(DiscoveredCharacteristic {
+                            uuid: c.uuid,
+                            properties: c.properties,
+                            declaration_handle: c.handle_decl,
+                            value_handle: c.handle_value,
+                            end_handle: service.end_handle,
+                            descriptors: vec![],
+                        });
+                    }
+                    // A value handle at the top of the handle range also ends the service
+                    let next_handle = event
+                        .characteristics
+                        .last()
+                        .and_then(|c| c.handle_value.checked_add(1));
+                    match next_handle {
+                        Some(h) => self.next_characteristics(&mut state, Some(h)),
+                        None => {
+                            state.service_index += 1;
+                            self.next_characteristics(&mut state, None)
+                        }
+                    }
+                }
+                GattStatus::AttributeNotFound => {
+                    state.service_index += 1;
+                    self.next_characteristics(&mut state, None)
+                }
+                status => Ok(Step::Failed(status)),
+            }
+        };
+
+        self.finish(step);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventDescriptorDiscoveryResponse> for DatabaseDiscoverer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventDescriptorDiscoveryResponse,
+    ) -> SubscriberResult {
+        let step = {
+            let mut state = match self.active_state_for(event.conn_handle, Stage::Descriptors) {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let (s, c) = (state.service_index, state.char_index);
+            match event.status {
+                GattStatus::Success => {
+                    let characteristic = &mut state.services[s].characteristics[c];
+                    characteristic
+                        .descriptors
+                        .extend(event.descriptors.iter().map(|d| GattcDescriptor {
+                            uuid: self.uuid_registry.decode(&d.uuid),
+                            handle: d.handle,
+                        }));
+                    match event.descriptors.last() {
+                        Some(d) if d.handle < characteristic.end_handle => {
+                            self.next_descriptors(&mut state, Some(d.handle + 1))
+                        }
+                        _ => {
+                            state.char_index += 1;
+                            self.next_descriptors(&mut state, None)
+                        }
+                    }
+                }
+                GattStatus::AttributeNotFound => {
+                    state.char_index += 1;
+                    self.next_descriptors(&mut state, None)
+                }
+                status => Ok(Step::Failed(status)),
+            }
+        };
+
+        self.finish(step);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventReadResponse> for DatabaseDiscoverer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventReadResponse,
+    ) -> SubscriberResult {
+        let step = {
+            let mut state = self.state.lock().unwrap();
+            if state.conn_handle != event.conn_handle {
+                return Ok(None);
+            }
+            match state.stage {
+                Stage::ServiceUuids => {
+                    let index = state.service_index;
+                    if event.status == GattStatus::Success && event.data.len() == UUID128_LEN {
+                        match self.register_uuid(&event.data) {
+                            Ok(uuid) => state.services[index].uuid = uuid,
+                            Err(e) => warn!("Failed to register service uuid: {:?}", e),
+                        }
+                    } else {
+                        warn!("Unable to read uuid of service at handle {}", event.handle);
+                    }
+                    state.service_index += 1;
+                    self.next_service_uuid(&mut state)
+                }
+                Stage::CharacteristicUuids => {
+                    let (s, c) = (state.service_index, state.char_index);
+                    let uuid_end = CHAR_DECL_UUID_OFFSET + UUID128_LEN;
+                    if event.status == GattStatus::Success && event.data.len() == uuid_end {
+                        match self.register_uuid(&event.data[CHAR_DECL_UUID_OFFSET..uuid_end]) {
+                            Ok(uuid) => state.services[s].characteristics[c].uuid = uuid,
+                            Err(e) => warn!("Failed to register characteristic uuid: {:?}", e),
+                        }
+                    } else {
+                        warn!(
+                            "Unable to read uuid of characteristic at handle {}",
+                            event.handle
+                        );
+                    }
+                    state.char_index += 1;
+                    self.next_characteristic_uuid(&mut state)
+                }
+                _ => return Ok(None),
+            }
+        };
+
+        self.finish(step);
+        return Ok(None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+
+use crate::events::{StreamWriteCompleteEvent, WriteCompleteEvent};
+use crate::gatt::gattc::{GattcCharacteristic, GattcClient, WriteCmdTxCompleteEvent};
+use crate::gatt::{CharacteristicProperties, GattError, GattResult};
+
+// ATT write command header: opcode (1), handle (2)
+const WRITE_CMD_OVERHEAD: usize = 3;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ThroughputStats {
+    pub bytes_sent: usize,
+    pub packets_sent: usize,
+    pub duration: Duration,
+}
+
+impl ThroughputStats {
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.duration.as_secs_f64();
+        if seconds > 0_f64 {
+            self.bytes_sent as f64 / seconds
+        } else {
+            0_f64
+        }
+    }
+
+    pub fn kilobits_per_second(&self) -> f64 {
+        self.bytes_per_second() * 8_f64 / 1000_f64
+    }
+}
+
+struct Stream {
+    data: Vec<u8>,
+    offset: usize,
+    chunk_size: usize,
+    in_flight: usize,
+    bytes_sent: usize,
+    packets_sent: usize,
+    started: Instant,
+}
+
+impl Stream {
+    fn stats(&self) -> ThroughputStats {
+        ThroughputStats {
+            bytes_sent: self.bytes_sent,
+            packets_sent: self.packets_sent,
+            duration: self.started.elapsed(),
+        }
+    }
+}
+
+// Streams a buffer to a characteristic with write commands, keeping the SoftDevice's queue full
+pub struct StreamWriter {
+    characteristic: Arc<GattcCharacteristic>,
+    stream: Mutex<Option<Stream>>,
+    pub on_write_complete: Publisher<Self, StreamWriteCompleteEvent>,
+}
+
+impl StreamWriter {
+    pub fn new(characteristic: &Arc<GattcCharacteristic>) -> Arc<Self> {
+        let writer = Arc::new(Self {
+            characteristic: characteristic.clone(),
+            stream: Mutex::new(None),
+            on_write_complete: Publisher::new("On Stream Write Complete"),
+        });
+
+        characteristic
+            .on_write_complete
+            .subscribe_with_priority(writer.clone(), Priority::Internal);
+        // Other characteristics' writes share the queue, refill whenever any of them are sent
+        characteristic
+            .client()
+            .on_write_cmd_tx_complete
+            .subscribe_with_priority(writer.clone(), Priority::Internal);
+
+        return writer;
+    }
+
+    pub fn write(
+        self: &Arc<Self>,
+        data: &[u8],
+    ) -> NrfResult<Arc<EventWaitable<Self, StreamWriteCompleteEvent>>> {
+        if !self
+            .characteristic
+            .properties
+            .contains(CharacteristicProperties::WRITE_WITHOUT_RESPONSE)
+        {
+            return Err(NrfErrorType::NotSupported.to_error());
+        }
+
+        let waitable = EventWaitable::new(&self.on_write_complete);
+        let result = {
+            let mut stream = self.stream.lock().unwrap();
+            if stream.is_some() {
+                return Err(NrfErrorType::Busy.to_error());
+            }
+            let mut new_stream = Stream {
+                data: data.to_vec(),
+                offset: 0,
+                chunk_size: self.characteristic.mtu_size() as usize - WRITE_CMD_OVERHEAD,
+                in_flight: 0,
+                bytes_sent: 0,
+                packets_sent: 0,
+                started: Instant::now(),
+            };
+            let result = self.fill_queue(&mut new_stream);
+            if result.is_ok() {
+                *stream = Some(new_stream);
+            }
+            result
+        };
+
+        result?;
+        self.complete_if_done(Ok(()));
+        Ok(waitable)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.stream.lock().unwrap().is_some()
+    }
+
+    // Queues chunks until the data runs out or the SoftDevice's queue is full
+    fn fill_queue(&self, stream: &mut Stream) -> NrfResult<()> {
+        while stream.offset < stream.data.len() {
+            let end = (stream.offset + stream.chunk_size).min(stream.data.len());
+            match self
+                .characteristic
+                .write_command(&stream.data[stream.offset..end])
+            {
+                Ok(_) => {
+                    stream.offset = end;
+                    stream.in_flight += 1;
+                }
+                Err(e) if matches!(e.error_type, NrfErrorType::Resources) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn complete_if_done(self: &Arc<Self>, result: GattResult<()>) {
+        let stats = {
+            let mut stream = self.stream.lock().unwrap();
+            let done = match (&result, stream.as_ref()) {
+                (Err(_), Some(_)) => true,
+                (Ok(_), Some(s)) => s.offset == s.data.len() && s.in_flight == 0,
+                (_, None) => false,
+            };
+            if !done {
+                return;
+            }
+            stream.take().map(|s| s.stats())
+        };
+
+        if let Some(stats) = stats {
+            self.on_write_complete
+                .dispatch(self.clone(), StreamWriteCompleteEvent { result, stats });
+        }
+    }
+}
+
+impl Subscriber<GattcCharacteristic, WriteCompleteEvent> for StreamWriter {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: WriteCompleteEvent,
+    ) -> SubscriberResult {
+        let result = {
+            let mut stream = self.stream.lock().unwrap();
+            let stream = match stream.as_mut() {
+                Some(stream) => stream,
+                None => return Ok(None),
+            };
+
+            match event.result {
+                Ok(_) => {
+                    // Other writes can share the queue, only count the stream's own chunks
+                    if stream.in_flight > 0 {
+                        stream.in_flight -= 1;
+                        stream.bytes_sent += event.data.len();
+                        stream.packets_sent += 1;
+                    }
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        self.complete_if_done(result);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattcClient, WriteCmdTxCompleteEvent> for StreamWriter {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcClient>,
+        _event: WriteCmdTxCompleteEvent,
+    ) -> SubscriberResult {
+        let result = {
+            let mut stream = self.stream.lock().unwrap();
+            match stream.as_mut() {
+                Some(stream) => self.fill_queue(stream).map_err(GattError::from),
+                None => return Ok(None),
+            }
+        };
+
+        self.complete_if_done(result);
+        return Ok(None);
+    }
+}
@@ -6,8 +6,13 @@ extern crate bitflags;
 
 pub mod advertise_data;
 pub mod advertiser;
+pub mod assigned_numbers;
+pub mod ble_uuid;
 pub mod connection_waitable;
 pub mod consts;
 pub mod device;
 pub mod events;
+pub mod gatt;
 pub mod peer;
+pub mod security;
+pub mod services;
@@ -1,10 +1,13 @@
+use std::any::Any;
 use std::sync::{Arc, Mutex, MutexGuard};
 
-use blatann_event::{EventWaitable, Publisher, Subscribable, Subscriber, SubscriberAction};
-use uuid::Uuid;
+use blatann_event::{
+    EventWaitable, Priority, Publisher, StatePublisher, Subscribable, SubscribableExt, Subscriber,
+    SubscriberFailure, SubscriberResult,
+};
 
-use nrf_driver::ble_event::{BleEventDataType, BleEventId};
-use nrf_driver::common::consts::CONN_HANDLE_INVALID;
+use nrf_driver::ble_event::BleEventDataType;
+use nrf_driver::common::consts::{CONN_HANDLE_INVALID, UUID_GATT_SERVICE_CHANGED};
 use nrf_driver::common::types::ConnHandle;
 use nrf_driver::driver::NrfDriver;
 use nrf_driver::driver_events::NrfEventPublisher;
@@ -15,13 +18,27 @@ use nrf_driver::gap::events::{
     GapEventPhyUpdate, GapEventPhyUpdateRequest,
 };
 use nrf_driver::gap::types::{BleGapAddress, BleGapConnParams};
+use nrf_driver::gattc::events::GattcEventExchangeMtuResponse;
+use nrf_driver::gatts::events::GattsEventExchangeMtuRequest;
 
-use crate::consts::MTU_SIZE_DEFAULT;
+use crate::ble_uuid::{BleUuid, UuidRegistry};
+use crate::consts::{MTU_SIZE_DEFAULT, MTU_SIZE_MAX};
 use crate::events::*;
+use crate::gatt::database_cache::GattcDatabaseCache;
+use crate::gatt::gattc::{GattcCharacteristic, GattcClient, GattcDatabase};
+use crate::gatt::service_discovery::DatabaseDiscoverer;
+use crate::gatt::{GattStatus, NotificationType};
+use crate::security::{BondDatabase, SecurityManager};
 
 pub type PeerRole = BleGapRole;
 pub type Phy = BleGapPhy;
 
+const DEFAULT_PHY: PhyUpdateEvent = PhyUpdateEvent {
+    tx_phy: Phy::ONE_MBPS,
+    rx_phy: Phy::ONE_MBPS,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PeerState {
     Disconnected,
     Connecting,
@@ -31,45 +48,58 @@ pub enum PeerState {
 struct State {
     conn_handle: ConnHandle,
     peer_address: Option<BleGapAddress>,
-    connection_state: PeerState,
     conn_params: BleGapConnParams,
-    mtu_size: u32,
-    preferred_mtu_size: u32,
-    negotiated_mtu_size: Option<usize>,
+    mtu_size: u16,
+    preferred_mtu_size: u16,
+    // Our receive MTU in an exchange we started, the response doesn't repeat it
+    requested_mtu_size: Option<u16>,
     preferred_phy: Phy,
-    current_phy: Phy,
+    phy: PhyUpdateEvent,
     disconnection_reason: u32,
-    connection_based_subs: Vec<(BleEventId, Uuid)>,
+    // Driver events filtered to this connection, dropping them unsubscribes
+    connection_events: Vec<Box<dyn Any + Send + Sync>>,
+    database: GattcDatabase,
+    // Discovery result held back until the Service Changed indications are enabled
+    pending_discovery: Option<DatabaseDiscoveryCompleteEvent>,
 }
 
 impl State {
-    fn new(connection_state: PeerState, conn_params: &BleGapConnParams) -> Self {
+    fn new(conn_params: &BleGapConnParams) -> Self {
         Self {
             conn_handle: CONN_HANDLE_INVALID,
             peer_address: None,
-            connection_state,
             conn_params: conn_params.clone(),
-            mtu_size: MTU_SIZE_DEFAULT,
-            preferred_mtu_size: MTU_SIZE_DEFAULT,
-            negotiated_mtu_size: None,
+            mtu_size: MTU_SIZE_DEFAULT as u16,
+            preferred_mtu_size: MTU_SIZE_MAX as u16,
+            requested_mtu_size: None,
             preferred_phy: Phy::AUTO,
-            current_phy: Phy::ONE_MBPS,
+            phy: DEFAULT_PHY,
             disconnection_reason: 0,
-            connection_based_subs: vec![],
+            connection_events: vec![],
+            database: Default::default(),
+            pending_discovery: None,
         }
     }
 }
 
 pub struct Peer {
     role: PeerRole,
-    max_mtu_size: usize,
     state: Mutex<State>,
     driver: Arc<NrfDriver>,
+    client: Arc<GattcClient>,
+    discoverer: Arc<DatabaseDiscoverer>,
+    database_cache: Arc<GattcDatabaseCache>,
+    security: Arc<SecurityManager>,
+    uuid_registry: Arc<UuidRegistry>,
 
+    pub connection_state: StatePublisher<Self, PeerState>,
     pub on_connect: Publisher<Self, ConnectionEvent>,
     pub on_disconnect: Publisher<Self, DisconnectionEvent>,
     pub on_phy_updated: Publisher<Self, PhyUpdateEvent>,
+    pub on_mtu_size_updated: Publisher<Self, MtuSizeUpdatedEvent>,
     pub on_data_length_updated: Publisher<Self, DataLengthUpdateEvent>,
+    pub on_database_discovery_complete: Publisher<Self, DatabaseDiscoveryCompleteEvent>,
+    pub on_service_changed: Publisher<Self, ServiceChangedEvent>,
 }
 
 impl Peer {
@@ -77,6 +107,9 @@ impl Peer {
         driver: &Arc<NrfDriver>,
         role: PeerRole,
         conn_params: &BleGapConnParams,
+        database_cache: &Arc<GattcDatabaseCache>,
+        bond_db: &Arc<BondDatabase>,
+        uuid_registry: &Arc<UuidRegistry>,
     ) -> Arc<Self> {
         let init_conn_state = match role {
             BleGapRole::Invalid => panic!("Shouldn't use this!"),
@@ -84,23 +117,50 @@ impl Peer {
             BleGapRole::Central => PeerState::Connecting,
         };
 
+        let client = GattcClient::new(driver);
         let peer = Arc::new(Self {
             role,
-            max_mtu_size: 23, // TODO magic number
-            state: Mutex::new(State::new(init_conn_state, conn_params)),
+            state: Mutex::new(State::new(conn_params)),
             driver: driver.clone(),
+            discoverer: DatabaseDiscoverer::new(driver, &client, uuid_registry),
+            client,
+            database_cache: database_cache.clone(),
+            security: SecurityManager::new(driver, bond_db),
+            uuid_registry: uuid_registry.clone(),
 
+            connection_state: StatePublisher::new("Connection State", init_conn_state, true),
             on_connect: Publisher::new("On Connect"),
             on_disconnect: Publisher::new("On Disconnect"),
             on_phy_updated: Publisher::new("On Phy Update"),
+            on_mtu_size_updated: Publisher::new("On MTU Size Updated"),
             on_data_length_updated: Publisher::new("On Data Length Update"),
+            on_database_discovery_complete: Publisher::new("On Database Discovery Complete"),
+            on_service_changed: Publisher::new("On Service Changed"),
         });
 
-        driver.events.disconnected.subscribe(peer.clone());
+        driver
+            .events
+            .disconnected
+            .subscribe_with_priority(peer.clone(), Priority::Internal);
+        peer.discoverer
+            .on_discovery_complete
+            .subscribe_with_priority(peer.clone(), Priority::Internal);
+
+        peer.connection_state
+            .set_error_handler(Self::report_subscriber_error);
+
+        // Binds the sender so the initial value is replayed to subscribers
+        peer.connection_state.set(&peer, init_conn_state);
 
         return peer;
     }
 
+    // State subscriber failures, including on replay, are reported like those of driver events
+    fn report_subscriber_error(peer: Arc<Self>, failure: SubscriberFailure) {
+        let driver = peer.driver.clone();
+        driver.on_subscriber_error.dispatch(driver.clone(), failure);
+    }
+
     pub fn disconnect(self: &Arc<Self>) -> NrfResult<Arc<EventWaitable<Self, DisconnectionEvent>>> {
         let conn_handle = { self.state.lock().unwrap().conn_handle };
 
@@ -109,6 +169,163 @@ impl Peer {
             .and_then(|_| Ok(EventWaitable::new(&self.on_disconnect)))
     }
 
+    pub fn discover_services(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, DatabaseDiscoveryCompleteEvent>>> {
+        let waitable = EventWaitable::new(&self.on_database_discovery_complete);
+
+        let cached_database = self
+            .bond_address()
+            .and_then(|address| self.database_cache.get(&address));
+        if let Some(database) = cached_database {
+            debug!("Using cached database for peer");
+            let event = DatabaseDiscoveryCompleteEvent {
+                status: GattStatus::Success,
+                database: database.rebind(&self.client),
+            };
+            self.database_discovered(event, true);
+            return Ok(waitable);
+        }
+
+        self.discoverer
+            .start(self.conn_handle())
+            .and_then(|_| Ok(waitable))
+    }
+
+    pub fn database(&self) -> GattcDatabase {
+        self.read_state(|s| s.database.clone())
+    }
+
+    pub fn security(&self) -> &Arc<SecurityManager> {
+        &self.security
+    }
+
+    pub(crate) fn uuid_registry(&self) -> &Arc<UuidRegistry> {
+        &self.uuid_registry
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection_state.get() == PeerState::Connected
+    }
+
+    // PHYs in use on the connection, on_phy_updated is dispatched when they change
+    pub fn phy(&self) -> PhyUpdateEvent {
+        self.read_state(|s| s.phy)
+    }
+
+    pub fn mtu_size(&self) -> u16 {
+        self.read_state(|s| s.mtu_size)
+    }
+
+    // Limited by the ATT MTU the driver was configured with
+    pub fn max_mtu_size(&self) -> u16 {
+        self.driver.att_mtu_max()
+    }
+
+    pub fn preferred_mtu_size(&self) -> u16 {
+        self.read_state(|s| s.preferred_mtu_size)
+            .min(self.max_mtu_size())
+    }
+
+    // MTU offered in exchanges, whether started by us or by the peer
+    pub fn set_preferred_mtu_size(&self, mtu_size: u16) {
+        let mtu_size = mtu_size.max(MTU_SIZE_DEFAULT as u16);
+        self.update_state(|s| s.preferred_mtu_size = mtu_size);
+    }
+
+    // Only one exchange can be started per connection, the peer may start its own as well
+    pub fn exchange_mtu(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, MtuSizeUpdatedEvent>>> {
+        let mtu_size = self.preferred_mtu_size();
+        let waitable = EventWaitable::new(&self.on_mtu_size_updated);
+
+        self.driver
+            .ble_gattc_exchange_mtu_request(self.conn_handle(), mtu_size)
+            .and_then(|_| {
+                self.update_state(|s| s.requested_mtu_size = Some(mtu_size));
+                Ok(waitable)
+            })
+    }
+
+    fn mtu_size_updated(self: &Arc<Self>, mtu_size: u16) {
+        let previous_mtu_size = self.update_state(|s| std::mem::replace(&mut s.mtu_size, mtu_size));
+        self.client.mtu_size_updated(mtu_size);
+        if mtu_size != previous_mtu_size {
+            info!(
+                "MTU size updated from {} to {}",
+                previous_mtu_size, mtu_size
+            );
+        }
+
+        self.on_mtu_size_updated.dispatch(
+            self.clone(),
+            MtuSizeUpdatedEvent {
+                previous_mtu_size,
+                current_mtu_size: mtu_size,
+            },
+        );
+    }
+
+    // Databases are cached under the identity address of the peer's bond
+    fn bond_address(&self) -> Option<BleGapAddress> {
+        self.security.bond().map(|bond| bond.identity_address())
+    }
+
+    fn database_discovered(
+        self: &Arc<Self>,
+        event: DatabaseDiscoveryCompleteEvent,
+        from_cache: bool,
+    ) {
+        self.update_state(|s| {
+            s.database = event.database.clone();
+            s.pending_discovery = None;
+        });
+        self.client.set_database(&event.database);
+
+        let service_changed = event
+            .database
+            .find_characteristic(&BleUuid::from_sig(UUID_GATT_SERVICE_CHANGED))
+            .cloned();
+        if let Some(characteristic) = service_changed {
+            characteristic
+                .on_notification_received
+                .subscribe_with_priority(self.clone(), Priority::Internal);
+            characteristic
+                .on_subscription_change
+                .subscribe_with_priority(self.clone(), Priority::Internal);
+
+            // Bonded servers remember the CCCD, so it only needs writing for a fresh discovery
+            if event.status == GattStatus::Success && !from_cache {
+                self.update_state(|s| s.pending_discovery = Some(event.clone()));
+                match characteristic.subscribe(NotificationType::Indication) {
+                    Ok(_) => return,
+                    Err(e) => {
+                        warn!("Failed to enable Service Changed indications: {:?}", e);
+                        self.update_state(|s| s.pending_discovery = None);
+                    }
+                }
+            }
+        }
+
+        self.discovery_complete(event, from_cache);
+    }
+
+    fn discovery_complete(
+        self: &Arc<Self>,
+        event: DatabaseDiscoveryCompleteEvent,
+        from_cache: bool,
+    ) {
+        if event.status == GattStatus::Success && !from_cache {
+            if let Some(address) = self.bond_address() {
+                self.database_cache.store(&address, &event.database);
+            }
+        }
+
+        self.on_database_discovery_complete
+            .dispatch(self.clone(), event);
+    }
+
     pub(crate) fn peer_connected(
         self: &Arc<Self>,
         conn_handle: ConnHandle,
@@ -116,42 +333,51 @@ impl Peer {
         conn_params: &BleGapConnParams,
     ) {
         self.update_state(|state| {
-            state.connection_state = PeerState::Connected;
             state.conn_handle = conn_handle;
             state.peer_address = Some(address.clone());
             state.conn_params = conn_params.clone();
-            state.negotiated_mtu_size = None;
-            state.mtu_size = 23; // TODO magic number
-
-            // disconnect from all the connection-based event handlers
-            for (event_id, sub_id) in state.connection_based_subs.iter() {
-                self.driver.unsubscribe_from_event(*event_id, *sub_id)
-            }
-            state.connection_based_subs.clear();
+            state.requested_mtu_size = None;
+            state.mtu_size = MTU_SIZE_DEFAULT as u16;
+            state.database = Default::default();
+            state.pending_discovery = None;
+            state.phy = DEFAULT_PHY;
+            state.connection_events.clear();
         });
+        self.connection_state.set(self, PeerState::Connected);
 
-        self.subscribe_for_connection(self.clone(), &self.driver.events.phy_update_request);
-        self.subscribe_for_connection(self.clone(), &self.driver.events.phy_update);
-        self.subscribe_for_connection(self.clone(), &self.driver.events.data_length_update_request);
-        self.subscribe_for_connection(self.clone(), &self.driver.events.data_length_update);
+        let mtu_size = self.read_state(|s| s.mtu_size);
+        self.client.connection_started(conn_handle, mtu_size);
+        self.security.connection_started(conn_handle, address);
+
+        let events = &self.driver.events;
+        self.subscribe_for_connection(&events.phy_update_request, |e| e.conn_handle);
+        self.subscribe_for_connection(&events.phy_update, |e| e.conn_handle);
+        self.subscribe_for_connection(&events.data_length_update_request, |e| e.conn_handle);
+        self.subscribe_for_connection(&events.data_length_update, |e| e.conn_handle);
+        self.subscribe_for_connection(&events.exchange_mtu_request, |e| e.conn_handle);
+        self.subscribe_for_connection(&events.exchange_mtu_response, |e| e.conn_handle);
 
         self.on_connect.dispatch(self.clone(), ConnectionEvent {})
     }
 
-    pub(crate) fn subscribe_for_connection<S: 'static, E: BleEventDataType>(
+    fn subscribe_for_connection<E: BleEventDataType + 'static>(
         self: &Arc<Self>,
-        subscriber: Arc<S>,
         event: &NrfEventPublisher<E>,
+        conn_handle_of: fn(&E) -> ConnHandle,
     ) where
-        S: Subscriber<NrfDriver, E>,
+        Self: Subscriber<NrfDriver, E>,
     {
-        let event_id = event.id();
-        let subscription_id = event.subscribe(subscriber);
+        let conn_handle = self.conn_handle();
+        let filtered = event.filter_with_priority(
+            move |e| conn_handle_of(e) == conn_handle,
+            Priority::Internal,
+        );
+        filtered.subscribe_with_priority(self.clone(), Priority::Internal);
 
-        self.update_state(|s| s.connection_based_subs.push((event_id, subscription_id)));
+        self.update_state(|s| s.connection_events.push(Box::new(filtered)));
     }
 
-    fn conn_handle(&self) -> ConnHandle {
+    pub(crate) fn conn_handle(&self) -> ConnHandle {
         let state = self.state.lock().unwrap();
         state.conn_handle
     }
@@ -172,18 +398,6 @@ impl Peer {
         f(&mut state)
     }
 
-    fn read_state_if<F, T>(&self, conn_handle: ConnHandle, f: F) -> Option<T>
-    where
-        F: FnOnce(&MutexGuard<State>) -> T,
-    {
-        let state = self.state.lock().unwrap();
-        if conn_handle == state.conn_handle {
-            Some(f(&state))
-        } else {
-            None
-        }
-    }
-
     fn update_state_if<F, T>(&self, conn_handle: ConnHandle, f: F) -> Option<T>
     where
         F: FnOnce(&mut MutexGuard<State>) -> T,
@@ -202,16 +416,18 @@ impl Subscriber<NrfDriver, GapEventDisconnected> for Peer {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         event: GapEventDisconnected,
-    ) -> Option<SubscriberAction> {
-        self.update_state_if(event.conn_handle, |s| {
-            s.connection_state = PeerState::Disconnected;
+    ) -> SubscriberResult {
+        let disconnected = self.update_state_if(event.conn_handle, |s| {
             s.conn_handle = CONN_HANDLE_INVALID;
+            s.connection_events.clear();
+        });
+        if disconnected.is_none() {
+            return Ok(None);
+        }
+        self.connection_state.set(&self, PeerState::Disconnected);
 
-            // disconnect from all the connection-based event handlers
-            for (event_id, sub_id) in s.connection_based_subs.iter() {
-                self.driver.unsubscribe_from_event(*event_id, *sub_id);
-            }
-        })?;
+        self.client.connection_ended();
+        self.security.connection_ended();
 
         self.on_disconnect.dispatch(
             self.clone(),
@@ -220,7 +436,7 @@ impl Subscriber<NrfDriver, GapEventDisconnected> for Peer {
             },
         );
 
-        return None;
+        return Ok(None);
     }
 }
 
@@ -229,20 +445,17 @@ impl Subscriber<NrfDriver, GapEventPhyUpdateRequest> for Peer {
         self: Arc<Self>,
         sender: Arc<NrfDriver>,
         event: GapEventPhyUpdateRequest,
-    ) -> Option<SubscriberAction> {
-        let (conn_handle, preferred_phy) =
-            self.read_state_if(event.conn_handle, |s| (s.conn_handle, s.preferred_phy))?;
+    ) -> SubscriberResult {
+        let preferred_phy = self.read_state(|s| s.preferred_phy);
 
         debug!(
             "Peer-preferred phy - rx:{:?}, tx:{:?}, ours - {:?}",
             event.peer_preferred_phys.rx_phys, event.peer_preferred_phys.tx_phys, preferred_phy,
         );
 
-        sender
-            .ble_gap_phy_update(conn_handle, preferred_phy, preferred_phy)
-            .unwrap();
+        sender.ble_gap_phy_update(event.conn_handle, preferred_phy, preferred_phy)?;
 
-        return None;
+        return Ok(None);
     }
 }
 
@@ -251,19 +464,14 @@ impl Subscriber<NrfDriver, GapEventPhyUpdate> for Peer {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         event: GapEventPhyUpdate,
-    ) -> Option<SubscriberAction> {
-        self.update_state_if(event.conn_handle, |s| {
-            s.current_phy = event.tx_phy;
-        })?;
-
-        self.on_phy_updated.dispatch(
-            self.clone(),
-            PhyUpdateEvent {
-                tx_phy: event.tx_phy,
-                rx_phy: event.rx_phy,
-            },
-        );
-        return None;
+    ) -> SubscriberResult {
+        let phy = PhyUpdateEvent {
+            tx_phy: event.tx_phy,
+            rx_phy: event.rx_phy,
+        };
+        self.update_state(|s| s.phy = phy);
+        self.on_phy_updated.dispatch(self.clone(), phy);
+        return Ok(None);
     }
 }
 
@@ -272,14 +480,10 @@ impl Subscriber<NrfDriver, GapEventDataLengthUpdateRequest> for Peer {
         self: Arc<Self>,
         sender: Arc<NrfDriver>,
         event: GapEventDataLengthUpdateRequest,
-    ) -> Option<SubscriberAction> {
-        if self.conn_handle() == event.conn_handle {
-            sender
-                .ble_gap_data_length_update(event.conn_handle, None)
-                .unwrap();
-        }
+    ) -> SubscriberResult {
+        sender.ble_gap_data_length_update(event.conn_handle, None)?;
 
-        None
+        Ok(None)
     }
 }
 
@@ -288,18 +492,128 @@ impl Subscriber<NrfDriver, GapEventDataLengthUpdate> for Peer {
         self: Arc<Self>,
         _sender: Arc<NrfDriver>,
         event: GapEventDataLengthUpdate,
-    ) -> Option<SubscriberAction> {
-        if self.conn_handle() == event.conn_handle {
-            let params = DataLengthUpdateEvent {
-                tx_bytes: event.effective_params.max_tx_octets,
-                rx_bytes: event.effective_params.max_rx_octets,
-                tx_time_us: event.effective_params.max_tx_time_us,
-                rx_time_us: event.effective_params.max_rx_time_us,
-            };
+    ) -> SubscriberResult {
+        let params = DataLengthUpdateEvent {
+            tx_bytes: event.effective_params.max_tx_octets,
+            rx_bytes: event.effective_params.max_rx_octets,
+            tx_time_us: event.effective_params.max_tx_time_us,
+            rx_time_us: event.effective_params.max_rx_time_us,
+        };
+
+        self.on_data_length_updated.dispatch(self.clone(), params);
+
+        Ok(None)
+    }
+}
 
-            self.on_data_length_updated.dispatch(self.clone(), params);
+impl Subscriber<NrfDriver, GattsEventExchangeMtuRequest> for Peer {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GattsEventExchangeMtuRequest,
+    ) -> SubscriberResult {
+        let server_rx_mtu = self.preferred_mtu_size();
+        debug!(
+            "Peer requested MTU {}, replying with {}",
+            event.client_rx_mtu, server_rx_mtu
+        );
+        sender.ble_gatts_exchange_mtu_reply(event.conn_handle, server_rx_mtu)?;
+
+        let mtu_size = event
+            .client_rx_mtu
+            .min(server_rx_mtu)
+            .max(MTU_SIZE_DEFAULT as u16);
+        self.mtu_size_updated(mtu_size);
+        Ok(None)
+    }
+}
+
+impl Subscriber<NrfDriver, GattcEventExchangeMtuResponse> for Peer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GattcEventExchangeMtuResponse,
+    ) -> SubscriberResult {
+        let client_rx_mtu = match self.update_state(|s| s.requested_mtu_size.take()) {
+            Some(mtu_size) => mtu_size,
+            None => return Ok(None),
+        };
+
+        let mtu_size = if event.status == GattStatus::Success {
+            event
+                .server_rx_mtu
+                .min(client_rx_mtu)
+                .max(MTU_SIZE_DEFAULT as u16)
+        } else {
+            warn!("MTU exchange failed: {:?}", event.status);
+            self.mtu_size()
+        };
+        self.mtu_size_updated(mtu_size);
+        Ok(None)
+    }
+}
+
+impl Subscriber<DatabaseDiscoverer, DatabaseDiscoveryCompleteEvent> for Peer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<DatabaseDiscoverer>,
+        event: DatabaseDiscoveryCompleteEvent,
+    ) -> SubscriberResult {
+        self.database_discovered(event, false);
+        Ok(None)
+    }
+}
+
+impl Subscriber<GattcCharacteristic, SubscriptionWriteCompleteEvent> for Peer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: SubscriptionWriteCompleteEvent,
+    ) -> SubscriberResult {
+        if let Err(e) = event.result {
+            warn!("Failed to enable Service Changed indications: {}", e);
+        }
+
+        if let Some(discovery) = self.update_state(|s| s.pending_discovery.take()) {
+            self.discovery_complete(discovery, false);
         }
+        Ok(None)
+    }
+}
 
-        None
+impl Subscriber<GattcCharacteristic, NotificationReceivedEvent> for Peer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: NotificationReceivedEvent,
+    ) -> SubscriberResult {
+        // Service Changed value: start handle (2), end handle (2)
+        if event.data.len() < 4 {
+            warn!(
+                "Received malformed Service Changed indication: {:?}",
+                event.data
+            );
+            return Ok(None);
+        }
+        let changed = ServiceChangedEvent {
+            start_handle: u16::from_le_bytes([event.data[0], event.data[1]]),
+            end_handle: u16::from_le_bytes([event.data[2], event.data[3]]),
+        };
+
+        if let Some(address) = self.bond_address() {
+            self.database_cache.invalidate(&address);
+        }
+        self.update_state(|s| s.database = Default::default());
+        self.client.set_database(&Default::default());
+        self.on_service_changed.dispatch(self.clone(), changed);
+
+        info!(
+            "Peer database changed in handles {}-{}, rediscovering",
+            changed.start_handle, changed.end_handle
+        );
+        if let Err(e) = self.discoverer.start(self.conn_handle()) {
+            error!("Failed to start rediscovery: {:?}", e);
+        }
+        Ok(None)
     }
 }
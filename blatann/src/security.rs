@@ -0,0 +1,418 @@
+use std::sync::{Arc, Mutex};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::common::consts::CONN_HANDLE_INVALID;
+use nrf_driver::common::types::ConnHandle;
+use nrf_driver::driver::NrfDriver;
+use nrf_driver::error::{NrfErrorType, NrfResult};
+use nrf_driver::gap::enums::{
+    BleGapAddressType, BleGapIoCaps, BleGapSecStatus, BleGapSecurityMode,
+};
+use nrf_driver::gap::events::{
+    GapEventAuthStatus, GapEventConnSecUpdate, GapEventSecInfoRequest, GapEventSecParamsRequest,
+};
+use nrf_driver::gap::types::{
+    BleGapAddress, BleGapEncKey, BleGapIdKey, BleGapMasterId, BleGapSecKeyDist, BleGapSecParams,
+};
+
+use crate::events::{PairingCompleteEvent, SecurityLevelChangedEvent};
+use crate::gatt::database_cache::GattcDatabaseCache;
+
+pub type IoCapabilities = BleGapIoCaps;
+pub type SecurityStatus = BleGapSecStatus;
+
+const KEY_SIZE_MIN: u8 = 7;
+const KEY_SIZE_MAX: u8 = 16;
+
+#[derive(Debug, Copy, Clone)]
+pub struct SecurityParams {
+    pub bond: bool,
+    pub mitm: bool,
+    pub io_capabilities: IoCapabilities,
+    pub reject_pairing: bool,
+}
+
+impl Default for SecurityParams {
+    // Just Works bonding
+    fn default() -> Self {
+        Self {
+            bond: true,
+            mitm: false,
+            io_capabilities: IoCapabilities::None,
+            reject_pairing: false,
+        }
+    }
+}
+
+impl SecurityParams {
+    fn to_sec_params(&self) -> BleGapSecParams {
+        BleGapSecParams {
+            bond: self.bond,
+            mitm: self.mitm,
+            lesc: false,
+            keypress: false,
+            io_caps: self.io_capabilities,
+            oob: false,
+            min_key_size: KEY_SIZE_MIN,
+            max_key_size: KEY_SIZE_MAX,
+            kdist_own: BleGapSecKeyDist {
+                enc_key: self.bond,
+                ..Default::default()
+            },
+            // The peer's identity key ties its private addresses back to the bond
+            kdist_peer: BleGapSecKeyDist {
+                id_key: self.bond,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BondEntry {
+    pub peer_address: BleGapAddress,
+    pub own_key: BleGapEncKey,
+    pub peer_id_key: Option<BleGapIdKey>,
+    // The local server's CCCDs as the peer left them, restored when it reconnects
+    pub sys_attr: Option<Vec<u8>>,
+}
+
+impl BondEntry {
+    // Peers that didn't distribute an identity key are known by the address they bonded with
+    pub fn identity_address(&self) -> BleGapAddress {
+        match self.peer_id_key {
+            Some(id_key) => id_key.id_address,
+            None => self.peer_address,
+        }
+    }
+
+    fn matches(&self, address: &BleGapAddress) -> bool {
+        self.peer_address == *address || self.identity_address() == *address
+    }
+}
+
+// Keys of bonded peers, only kept in memory for the lifetime of the device
+pub struct BondDatabase {
+    entries: Mutex<Vec<BondEntry>>,
+    // Cached peer databases belong to the bond, they're dropped along with it
+    gattc_cache: Arc<GattcDatabaseCache>,
+}
+
+impl BondDatabase {
+    pub(crate) fn new(gattc_cache: &Arc<GattcDatabaseCache>) -> Self {
+        Self {
+            entries: Mutex::new(vec![]),
+            gattc_cache: gattc_cache.clone(),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<BondEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    // Bonded centrals identify themselves with the master id we distributed
+    pub fn find(&self, master_id: &BleGapMasterId) -> Option<BondEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .find(|e| e.own_key.master_id == *master_id)
+            .cloned()
+    }
+
+    // Matches either the address the peer bonded with or its identity address
+    pub fn find_by_address(&self, address: &BleGapAddress) -> Option<BondEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().find(|e| e.matches(address)).cloned()
+    }
+
+    pub fn delete(&self, address: &BleGapAddress) {
+        let mut entries = self.entries.lock().unwrap();
+        self.remove_where(&mut entries, |e| e.matches(address));
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.remove_where(&mut entries, |_| true);
+    }
+
+    pub(crate) fn add(&self, entry: BondEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        // Re-pairing replaces the old keys, the peer's database may have changed in the meantime
+        let identity_address = entry.identity_address();
+        self.remove_where(&mut entries, |e| e.identity_address() == identity_address);
+        entries.push(entry);
+    }
+
+    pub(crate) fn set_sys_attr(&self, master_id: &BleGapMasterId, sys_attr: Vec<u8>) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries
+            .iter_mut()
+            .find(|e| e.own_key.master_id == *master_id)
+        {
+            entry.sys_attr = Some(sys_attr);
+        }
+    }
+
+    fn remove_where<F: Fn(&BondEntry) -> bool>(&self, entries: &mut Vec<BondEntry>, f: F) {
+        for entry in entries.iter().filter(|e| f(e)) {
+            self.gattc_cache.invalidate(&entry.identity_address());
+        }
+        entries.retain(|e| !f(e));
+    }
+}
+
+struct State {
+    conn_handle: ConnHandle,
+    peer_address: Option<BleGapAddress>,
+    bond: Option<BondEntry>,
+    params: SecurityParams,
+    security_mode: BleGapSecurityMode,
+}
+
+// Handles pairing and encryption for a single connection
+pub struct SecurityManager {
+    driver: Arc<NrfDriver>,
+    bond_db: Arc<BondDatabase>,
+    state: Mutex<State>,
+
+    pub on_pairing_complete: Publisher<Self, PairingCompleteEvent>,
+    pub on_security_level_changed: Publisher<Self, SecurityLevelChangedEvent>,
+}
+
+impl SecurityManager {
+    pub(crate) fn new(driver: &Arc<NrfDriver>, bond_db: &Arc<BondDatabase>) -> Arc<Self> {
+        let manager = Arc::new(Self {
+            driver: driver.clone(),
+            bond_db: bond_db.clone(),
+            state: Mutex::new(State {
+                conn_handle: CONN_HANDLE_INVALID,
+                peer_address: None,
+                bond: None,
+                params: Default::default(),
+                security_mode: BleGapSecurityMode::Open,
+            }),
+            on_pairing_complete: Publisher::new("On Pairing Complete"),
+            on_security_level_changed: Publisher::new("On Security Level Changed"),
+        });
+
+        driver
+            .events
+            .sec_params_request
+            .subscribe_with_priority(manager.clone(), Priority::Internal);
+        driver
+            .events
+            .sec_info_request
+            .subscribe_with_priority(manager.clone(), Priority::Internal);
+        driver
+            .events
+            .auth_status
+            .subscribe_with_priority(manager.clone(), Priority::Internal);
+        driver
+            .events
+            .conn_sec_update
+            .subscribe_with_priority(manager.clone(), Priority::Internal);
+
+        return manager;
+    }
+
+    pub fn set_security_params(&self, params: SecurityParams) {
+        self.state.lock().unwrap().params = params;
+    }
+
+    pub fn security_level(&self) -> BleGapSecurityMode {
+        self.state.lock().unwrap().security_mode
+    }
+
+    // The bond of the connected peer, known once it pairs or encrypts with stored keys.
+    // Peers with an identity address are recognized as soon as they connect
+    pub fn bond(&self) -> Option<BondEntry> {
+        let bond = self.state.lock().unwrap().bond.clone()?;
+        // The bond may have been deleted since
+        self.bond_db.find(&bond.own_key.master_id)
+    }
+
+    // Asks the central to start pairing, or to encrypt with the existing keys if bonded
+    pub fn pair(self: &Arc<Self>) -> NrfResult<Arc<EventWaitable<Self, PairingCompleteEvent>>> {
+        let (conn_handle, params) = {
+            let state = self.state.lock().unwrap();
+            (state.conn_handle, state.params)
+        };
+        if conn_handle == CONN_HANDLE_INVALID {
+            return Err(NrfErrorType::InvalidState.to_error());
+        }
+
+        let waitable = EventWaitable::new(&self.on_pairing_complete);
+        self.driver
+            .ble_gap_authenticate(conn_handle, &params.to_sec_params())
+            .and_then(|_| Ok(waitable))
+    }
+
+    pub(crate) fn connection_started(&self, conn_handle: ConnHandle, address: &BleGapAddress) {
+        let mut state = self.state.lock().unwrap();
+        state.conn_handle = conn_handle;
+        state.peer_address = Some(*address);
+        state.bond = match address.address_type {
+            BleGapAddressType::Public | BleGapAddressType::Static => {
+                self.bond_db.find_by_address(address)
+            }
+            _ => None,
+        };
+        state.security_mode = BleGapSecurityMode::Open;
+    }
+
+    pub(crate) fn connection_ended(&self) {
+        let (conn_handle, bond) = {
+            let mut state = self.state.lock().unwrap();
+            let conn_handle = state.conn_handle;
+            state.conn_handle = CONN_HANDLE_INVALID;
+            state.security_mode = BleGapSecurityMode::Open;
+            (conn_handle, state.bond.take())
+        };
+        // Bonded clients don't rewrite their CCCDs when they reconnect, keep them with the bond
+        if let Some(bond) = bond {
+            match self.driver.ble_gatts_sys_attr_get(conn_handle) {
+                Ok(sys_attr) => self.bond_db.set_sys_attr(&bond.own_key.master_id, sys_attr),
+                Err(e) => warn!("Failed to read system attributes: {:?}", e),
+            }
+        }
+        // Drop the keys of a pairing that didn't finish
+        self.driver.ble_gap_sec_keys_take(conn_handle);
+    }
+
+    fn is_connection(&self, conn_handle: ConnHandle) -> bool {
+        self.state.lock().unwrap().conn_handle == conn_handle
+    }
+}
+
+impl Subscriber<NrfDriver, GapEventSecParamsRequest> for SecurityManager {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GapEventSecParamsRequest,
+    ) -> SubscriberResult {
+        if !self.is_connection(event.conn_handle) {
+            return Ok(None);
+        }
+        let params = self.state.lock().unwrap().params;
+
+        let result = if params.reject_pairing {
+            sender.ble_gap_sec_params_reply(
+                event.conn_handle,
+                BleGapSecStatus::PairingNotSupported,
+                None,
+            )
+        } else {
+            debug!("Pairing requested, peer params: {:?}", event.peer_params);
+            sender.ble_gap_sec_params_reply(
+                event.conn_handle,
+                BleGapSecStatus::Success,
+                Some(&params.to_sec_params()),
+            )
+        };
+        if let Err(e) = result {
+            error!("Failed to reply to security params request: {:?}", e);
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GapEventSecInfoRequest> for SecurityManager {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GapEventSecInfoRequest,
+    ) -> SubscriberResult {
+        if !self.is_connection(event.conn_handle) {
+            return Ok(None);
+        }
+
+        let bond = if event.enc_info {
+            self.bond_db.find(&event.master_id)
+        } else {
+            None
+        };
+        let enc_info = bond.as_ref().map(|e| e.own_key.enc_info);
+        match bond {
+            Some(bond) => self.state.lock().unwrap().bond = Some(bond),
+            None => info!("No bond found for peer {:?}", event.peer_address),
+        }
+
+        if let Err(e) = sender.ble_gap_sec_info_reply(event.conn_handle, enc_info.as_ref()) {
+            error!("Failed to reply to security info request: {:?}", e);
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GapEventAuthStatus> for SecurityManager {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<NrfDriver>,
+        event: GapEventAuthStatus,
+    ) -> SubscriberResult {
+        let peer_address = {
+            let state = self.state.lock().unwrap();
+            if state.conn_handle != event.conn_handle {
+                return Ok(None);
+            }
+            state.peer_address
+        };
+
+        let keys = sender.ble_gap_sec_keys_take(event.conn_handle);
+        if event.auth_status == BleGapSecStatus::Success && event.bonded {
+            match (peer_address, keys) {
+                (Some(peer_address), Some(keys)) if event.kdist_own.enc_key => {
+                    let entry = BondEntry {
+                        peer_address,
+                        own_key: keys.own_enc_key,
+                        peer_id_key: if event.kdist_peer.id_key {
+                            Some(keys.peer_id_key)
+                        } else {
+                            None
+                        },
+                        sys_attr: None,
+                    };
+                    self.bond_db.add(entry.clone());
+                    self.state.lock().unwrap().bond = Some(entry);
+                }
+                _ => warn!("Bonded without an encryption key to store"),
+            }
+        }
+
+        self.on_pairing_complete.dispatch(
+            self.clone(),
+            PairingCompleteEvent {
+                status: event.auth_status,
+                bonded: event.bonded,
+            },
+        );
+        return Ok(None);
+    }
+}
+
+impl Subscriber<NrfDriver, GapEventConnSecUpdate> for SecurityManager {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<NrfDriver>,
+        event: GapEventConnSecUpdate,
+    ) -> SubscriberResult {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.conn_handle != event.conn_handle {
+                return Ok(None);
+            }
+            state.security_mode = event.security_mode;
+        }
+
+        self.on_security_level_changed.dispatch(
+            self.clone(),
+            SecurityLevelChangedEvent {
+                security_mode: event.security_mode,
+            },
+        );
+        return Ok(None);
+    }
+}
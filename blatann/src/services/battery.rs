@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+
+use crate::ble_uuid::BleUuid;
+use crate::events::{NotificationReceivedEvent, ReadCompleteEvent, SubscriptionWriteCompleteEvent};
+use crate::gatt::gattc::{GattcCharacteristic, GattcDatabase};
+use crate::gatt::gatts::{GattsCharacteristic, GattsDatabase, GattsService};
+use crate::gatt::{
+    CharacteristicParams, CharacteristicProperties, GattError, GattResult, GattStatus,
+    NotificationType,
+};
+
+pub const BATTERY_SERVICE_UUID: u16 = 0x180F;
+pub const BATTERY_LEVEL_UUID: u16 = 0x2A19;
+
+const BATTERY_LEVEL_MAX: u8 = 100;
+
+#[derive(Debug, Copy, Clone)]
+pub struct BatteryLevelReadEvent {
+    pub result: GattResult<u8>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BatteryLevelChangedEvent {
+    pub level: u8,
+}
+
+pub struct BatteryServer {
+    pub service: Arc<GattsService>,
+    battery_level: Arc<GattsCharacteristic>,
+}
+
+impl BatteryServer {
+    pub fn add_to_database(database: &GattsDatabase, initial_level: u8) -> NrfResult<Arc<Self>> {
+        if initial_level > BATTERY_LEVEL_MAX {
+            return Err(NrfErrorType::InvalidParam.to_error());
+        }
+
+        let service = database.add_service(BleUuid::from_sig(BATTERY_SERVICE_UUID))?;
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(BATTERY_LEVEL_UUID),
+            CharacteristicProperties::READ | CharacteristicProperties::NOTIFY,
+            1,
+        )
+        .with_fixed_length()
+        .with_initial_value(&[initial_level]);
+        let battery_level = service.add_characteristic(&params)?;
+
+        return Ok(Arc::new(Self {
+            service,
+            battery_level,
+        }));
+    }
+
+    // Level is a percentage, notified to the client if it has subscribed
+    pub fn set_battery_level(&self, level: u8) -> NrfResult<()> {
+        if level > BATTERY_LEVEL_MAX {
+            return Err(NrfErrorType::InvalidParam.to_error());
+        }
+        self.battery_level.notify(&[level])
+    }
+
+    pub fn battery_level(&self) -> NrfResult<u8> {
+        let value = self.battery_level.get_value()?;
+        Ok(value.get(0).copied().unwrap_or(0))
+    }
+
+    pub fn is_client_subscribed(&self) -> bool {
+        self.battery_level.subscription().is_some()
+    }
+}
+
+pub struct BatteryClient {
+    battery_level: Arc<GattcCharacteristic>,
+
+    pub on_battery_level_read: Publisher<Self, BatteryLevelReadEvent>,
+    pub on_battery_level_changed: Publisher<Self, BatteryLevelChangedEvent>,
+}
+
+impl BatteryClient {
+    // Finds the battery service within the peer's discovered database
+    pub fn find(database: &GattcDatabase) -> Option<Arc<Self>> {
+        let battery_level = database
+            .find_service(&BleUuid::from_sig(BATTERY_SERVICE_UUID))?
+            .find_characteristic(&BleUuid::from_sig(BATTERY_LEVEL_UUID))?;
+
+        let client = Arc::new(Self {
+            battery_level: battery_level.clone(),
+            on_battery_level_read: Publisher::new("On Battery Level Read"),
+            on_battery_level_changed: Publisher::new("On Battery Level Changed"),
+        });
+
+        battery_level
+            .on_read_complete
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        battery_level
+            .on_notification_received
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+
+        return Some(client);
+    }
+
+    pub fn read_level(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, BatteryLevelReadEvent>>> {
+        let waitable = EventWaitable::new(&self.on_battery_level_read);
+
+        self.battery_level.read().and_then(|_| Ok(waitable))
+    }
+
+    pub fn can_notify(&self) -> bool {
+        self.battery_level
+            .properties
+            .contains(CharacteristicProperties::NOTIFY)
+    }
+
+    pub fn enable_notifications(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.battery_level.subscribe(NotificationType::Notification)
+    }
+
+    pub fn disable_notifications(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.battery_level.unsubscribe()
+    }
+}
+
+impl Subscriber<GattcCharacteristic, ReadCompleteEvent> for BatteryClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: ReadCompleteEvent,
+    ) -> SubscriberResult {
+        let result = event.result.and_then(|data| {
+            data.get(0)
+                .copied()
+                .ok_or(GattError::Status(GattStatus::InvalidAttributeValueLength))
+        });
+
+        self.on_battery_level_read
+            .dispatch(self.clone(), BatteryLevelReadEvent { result });
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattcCharacteristic, NotificationReceivedEvent> for BatteryClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: NotificationReceivedEvent,
+    ) -> SubscriberResult {
+        match event.data.get(0) {
+            Some(level) => self
+                .on_battery_level_changed
+                .dispatch(self.clone(), BatteryLevelChangedEvent { level: *level }),
+            None => warn!("Received empty battery level notification"),
+        }
+        return Ok(None);
+    }
+}
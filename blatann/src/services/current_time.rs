@@ -0,0 +1,505 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+
+use crate::ble_uuid::BleUuid;
+use crate::events::{NotificationReceivedEvent, ReadCompleteEvent, SubscriptionWriteCompleteEvent};
+use crate::gatt::gattc::{GattcCharacteristic, GattcDatabase};
+use crate::gatt::gatts::{GattsCharacteristic, GattsDatabase, GattsService};
+use crate::gatt::{
+    CharacteristicParams, CharacteristicProperties, GattError, GattResult, GattStatus,
+    NotificationType,
+};
+
+pub const CURRENT_TIME_SERVICE_UUID: u16 = 0x1805;
+pub const LOCAL_TIME_INFO_UUID: u16 = 0x2A0F;
+pub const CURRENT_TIME_UUID: u16 = 0x2A2B;
+
+const DATE_TIME_LEN: usize = 7;
+const EXACT_TIME_256_LEN: usize = DATE_TIME_LEN + 2;
+const CURRENT_TIME_LEN: usize = EXACT_TIME_256_LEN + 1;
+const LOCAL_TIME_INFO_LEN: usize = 2;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+// Year, month and day of 0 mean the field is unknown
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+impl DateTime {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.year.to_le_bytes().to_vec();
+        data.extend_from_slice(&[self.month, self.day, self.hours, self.minutes, self.seconds]);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < DATE_TIME_LEN {
+            return None;
+        }
+        Some(Self {
+            year: u16::from_le_bytes(data[0..2].try_into().unwrap()),
+            month: data[2],
+            day: data[3],
+            hours: data[4],
+            minutes: data[5],
+            seconds: data[6],
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DayOfWeek {
+    Unknown = 0,
+    Monday = 1,
+    Tuesday = 2,
+    Wednesday = 3,
+    Thursday = 4,
+    Friday = 5,
+    Saturday = 6,
+    Sunday = 7,
+}
+
+impl DayOfWeek {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => DayOfWeek::Monday,
+            2 => DayOfWeek::Tuesday,
+            3 => DayOfWeek::Wednesday,
+            4 => DayOfWeek::Thursday,
+            5 => DayOfWeek::Friday,
+            6 => DayOfWeek::Saturday,
+            7 => DayOfWeek::Sunday,
+            _ => DayOfWeek::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ExactTime256 {
+    pub date_time: DateTime,
+    pub day_of_week: DayOfWeek,
+    // Fractions of a second in 1/256th units
+    pub fractions256: u8,
+}
+
+impl ExactTime256 {
+    // Converts to UTC, the local time offset goes in the Local Time Information
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let seconds = since_epoch.as_secs();
+        let days = seconds / SECONDS_PER_DAY;
+        let seconds_of_day = seconds % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+
+        Self {
+            date_time: DateTime {
+                year,
+                month,
+                day,
+                hours: (seconds_of_day / 3600) as u8,
+                minutes: (seconds_of_day / 60 % 60) as u8,
+                seconds: (seconds_of_day % 60) as u8,
+            },
+            // 1970-01-01 was a Thursday
+            day_of_week: DayOfWeek::from_u8(((days + 3) % 7 + 1) as u8),
+            fractions256: (since_epoch.subsec_nanos() as u64 * 256 / 1_000_000_000) as u8,
+        }
+    }
+
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.date_time.serialize();
+        data.extend_from_slice(&[self.day_of_week as u8, self.fractions256]);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < EXACT_TIME_256_LEN {
+            return None;
+        }
+        Some(Self {
+            date_time: DateTime::deserialize(data)?,
+            day_of_week: DayOfWeek::from_u8(data[7]),
+            fractions256: data[8],
+        })
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct AdjustReason: u8 {
+        const MANUAL_TIME_UPDATE = 0x01;
+        const EXTERNAL_REFERENCE_TIME_UPDATE = 0x02;
+        const TIME_ZONE_CHANGE = 0x04;
+        const DST_CHANGE = 0x08;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CurrentTime {
+    pub exact_time: ExactTime256,
+    pub adjust_reason: AdjustReason,
+}
+
+impl CurrentTime {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.exact_time.serialize();
+        data.push(self.adjust_reason.bits());
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < CURRENT_TIME_LEN {
+            return None;
+        }
+        Some(Self {
+            exact_time: ExactTime256::deserialize(data)?,
+            adjust_reason: AdjustReason::from_bits_truncate(data[9]),
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DstOffset {
+    StandardTime = 0,
+    HalfHourDaylightTime = 2,
+    DaylightTime = 4,
+    DoubleDaylightTime = 8,
+    Unknown = 255,
+}
+
+impl DstOffset {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => DstOffset::StandardTime,
+            2 => DstOffset::HalfHourDaylightTime,
+            4 => DstOffset::DaylightTime,
+            8 => DstOffset::DoubleDaylightTime,
+            _ => DstOffset::Unknown,
+        }
+    }
+}
+
+pub const TIME_ZONE_UNKNOWN: i8 = -128;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LocalTimeInfo {
+    // Offset from UTC in 15 minute increments, without the DST offset
+    pub time_zone: i8,
+    pub dst_offset: DstOffset,
+}
+
+impl LocalTimeInfo {
+    pub fn serialize(&self) -> Vec<u8> {
+        vec![self.time_zone as u8, self.dst_offset as u8]
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < LOCAL_TIME_INFO_LEN {
+            return None;
+        }
+        Some(Self {
+            time_zone: data[0] as i8,
+            dst_offset: DstOffset::from_u8(data[1]),
+        })
+    }
+}
+
+// Days since 1970-01-01 to a (year, month, day) in the proleptic Gregorian calendar
+fn civil_from_days(days: u64) -> (u16, u8, u8) {
+    let z = days as i64 + 719468;
+    let era = z / 146097;
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year as u16, month as u8, day as u8)
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct CurrentTimeReadEvent {
+    pub result: GattResult<CurrentTime>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct LocalTimeInfoReadEvent {
+    pub result: GattResult<LocalTimeInfo>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct CurrentTimeUpdatedEvent {
+    pub current_time: CurrentTime,
+}
+
+pub struct CurrentTimeServer {
+    pub service: Arc<GattsService>,
+    current_time: Arc<GattsCharacteristic>,
+    local_time_info: Option<Arc<GattsCharacteristic>>,
+}
+
+impl CurrentTimeServer {
+    // The server doesn't keep time, the application should update it periodically and on adjustments
+    pub fn add_to_database(
+        database: &GattsDatabase,
+        current_time: &CurrentTime,
+        local_time_info: Option<&LocalTimeInfo>,
+    ) -> NrfResult<Arc<Self>> {
+        let service = database.add_service(BleUuid::from_sig(CURRENT_TIME_SERVICE_UUID))?;
+
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(CURRENT_TIME_UUID),
+            CharacteristicProperties::READ | CharacteristicProperties::NOTIFY,
+            CURRENT_TIME_LEN as u16,
+        )
+        .with_fixed_length()
+        .with_initial_value(&current_time.serialize());
+        let current_time = service.add_characteristic(&params)?;
+
+        let local_time_info = match local_time_info {
+            Some(info) => {
+                let params = CharacteristicParams::new(
+                    BleUuid::from_sig(LOCAL_TIME_INFO_UUID),
+                    CharacteristicProperties::READ,
+                    LOCAL_TIME_INFO_LEN as u16,
+                )
+                .with_fixed_length()
+                .with_initial_value(&info.serialize());
+                Some(service.add_characteristic(&params)?)
+            }
+            None => None,
+        };
+
+        return Ok(Arc::new(Self {
+            service,
+            current_time,
+            local_time_info,
+        }));
+    }
+
+    // Updates the time and notifies the client if it has subscribed
+    pub fn set_time(
+        &self,
+        exact_time: &ExactTime256,
+        adjust_reason: AdjustReason,
+    ) -> NrfResult<()> {
+        let current_time = CurrentTime {
+            exact_time: *exact_time,
+            adjust_reason,
+        };
+        self.current_time.notify(&current_time.serialize())
+    }
+
+    pub fn set_local_time_info(&self, info: &LocalTimeInfo) -> NrfResult<()> {
+        match &self.local_time_info {
+            Some(characteristic) => characteristic.set_value(&info.serialize()),
+            None => Err(NrfErrorType::NotSupported.to_error()),
+        }
+    }
+}
+
+pub struct CurrentTimeClient {
+    current_time: Arc<GattcCharacteristic>,
+    local_time_info: Option<Arc<GattcCharacteristic>>,
+
+    pub on_current_time_read: Publisher<Self, CurrentTimeReadEvent>,
+    pub on_local_time_info_read: Publisher<Self, LocalTimeInfoReadEvent>,
+    pub on_current_time_updated: Publisher<Self, CurrentTimeUpdatedEvent>,
+}
+
+impl CurrentTimeClient {
+    // Finds the current time service within the peer's discovered database
+    pub fn find(database: &GattcDatabase) -> Option<Arc<Self>> {
+        let service = database.find_service(&BleUuid::from_sig(CURRENT_TIME_SERVICE_UUID))?;
+        let current_time = service.find_characteristic(&BleUuid::from_sig(CURRENT_TIME_UUID))?;
+        let local_time_info = service
+            .find_characteristic(&BleUuid::from_sig(LOCAL_TIME_INFO_UUID))
+            .cloned();
+
+        let client = Arc::new(Self {
+            current_time: current_time.clone(),
+            local_time_info,
+            on_current_time_read: Publisher::new("On Current Time Read"),
+            on_local_time_info_read: Publisher::new("On Local Time Info Read"),
+            on_current_time_updated: Publisher::new("On Current Time Updated"),
+        });
+
+        current_time
+            .on_read_complete
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        current_time
+            .on_notification_received
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        if let Some(local_time_info) = &client.local_time_info {
+            local_time_info
+                .on_read_complete
+                .subscribe_with_priority(client.clone(), Priority::Internal);
+        }
+
+        return Some(client);
+    }
+
+    pub fn has_local_time_info(&self) -> bool {
+        self.local_time_info.is_some()
+    }
+
+    pub fn read_time(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, CurrentTimeReadEvent>>> {
+        let waitable = EventWaitable::new(&self.on_current_time_read);
+
+        self.current_time.read().and_then(|_| Ok(waitable))
+    }
+
+    pub fn read_local_time_info(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, LocalTimeInfoReadEvent>>> {
+        let local_time_info = self
+            .local_time_info
+            .as_ref()
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        let waitable = EventWaitable::new(&self.on_local_time_info_read);
+
+        local_time_info.read().and_then(|_| Ok(waitable))
+    }
+
+    pub fn subscribe(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.current_time.subscribe(NotificationType::Notification)
+    }
+
+    pub fn unsubscribe(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.current_time.unsubscribe()
+    }
+}
+
+fn decode<T, F: FnOnce(&[u8]) -> Option<T>>(result: GattResult<Vec<u8>>, f: F) -> GattResult<T> {
+    result
+        .and_then(|data| f(&data).ok_or(GattError::Status(GattStatus::InvalidAttributeValueLength)))
+}
+
+impl Subscriber<GattcCharacteristic, ReadCompleteEvent> for CurrentTimeClient {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<GattcCharacteristic>,
+        event: ReadCompleteEvent,
+    ) -> SubscriberResult {
+        if Arc::ptr_eq(&sender, &self.current_time) {
+            let result = decode(event.result, CurrentTime::deserialize);
+            self.on_current_time_read
+                .dispatch(self.clone(), CurrentTimeReadEvent { result });
+        } else {
+            let result = decode(event.result, LocalTimeInfo::deserialize);
+            self.on_local_time_info_read
+                .dispatch(self.clone(), LocalTimeInfoReadEvent { result });
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattcCharacteristic, NotificationReceivedEvent> for CurrentTimeClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: NotificationReceivedEvent,
+    ) -> SubscriberResult {
+        match CurrentTime::deserialize(&event.data) {
+            Some(current_time) => self
+                .on_current_time_updated
+                .dispatch(self.clone(), CurrentTimeUpdatedEvent { current_time }),
+            None => warn!(
+                "Received invalid current time notification: {:?}",
+                event.data
+            ),
+        }
+        return Ok(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn leap_day() -> ExactTime256 {
+        ExactTime256 {
+            date_time: DateTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hours: 13,
+                minutes: 45,
+                seconds: 30,
+            },
+            day_of_week: DayOfWeek::Thursday,
+            fractions256: 128,
+        }
+    }
+
+    #[test]
+    fn exact_time_256_known_vector() {
+        let data = [0xE8, 0x07, 2, 29, 13, 45, 30, 4, 128];
+
+        assert_eq!(leap_day().serialize(), data);
+        assert_eq!(ExactTime256::deserialize(&data), Some(leap_day()));
+        assert_eq!(ExactTime256::deserialize(&data[..8]), None);
+    }
+
+    #[test]
+    fn converts_from_system_time() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_214_330_500);
+
+        assert_eq!(ExactTime256::from_system_time(time), leap_day());
+    }
+
+    #[test]
+    fn current_time_round_trips() {
+        let current_time = CurrentTime {
+            exact_time: leap_day(),
+            adjust_reason: AdjustReason::MANUAL_TIME_UPDATE | AdjustReason::DST_CHANGE,
+        };
+        let data = current_time.serialize();
+
+        assert_eq!(data.len(), CURRENT_TIME_LEN);
+        assert_eq!(data[9], 0x09);
+        assert_eq!(CurrentTime::deserialize(&data), Some(current_time));
+    }
+
+    #[test]
+    fn local_time_info_round_trips() {
+        let info = LocalTimeInfo {
+            time_zone: -20,
+            dst_offset: DstOffset::DaylightTime,
+        };
+
+        assert_eq!(info.serialize(), [0xEC, 4]);
+        assert_eq!(LocalTimeInfo::deserialize(&[0xEC, 4]), Some(info));
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+
+use crate::ble_uuid::BleUuid;
+use crate::events::ReadCompleteEvent;
+use crate::gatt::gattc::{GattcCharacteristic, GattcDatabase};
+use crate::gatt::gatts::{GattsDatabase, GattsService};
+use crate::gatt::{
+    CharacteristicParams, CharacteristicProperties, GattError, GattResult, GattStatus,
+};
+
+pub const DEVICE_INFO_SERVICE_UUID: u16 = 0x180A;
+pub const SYSTEM_ID_UUID: u16 = 0x2A23;
+pub const MODEL_NUMBER_UUID: u16 = 0x2A24;
+pub const SERIAL_NUMBER_UUID: u16 = 0x2A25;
+pub const FIRMWARE_REVISION_UUID: u16 = 0x2A26;
+pub const HARDWARE_REVISION_UUID: u16 = 0x2A27;
+pub const SOFTWARE_REVISION_UUID: u16 = 0x2A28;
+pub const MANUFACTURER_NAME_UUID: u16 = 0x2A29;
+pub const PNP_ID_UUID: u16 = 0x2A50;
+
+const SYSTEM_ID_LEN: usize = 8;
+const PNP_ID_LEN: usize = 7;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SystemId {
+    // 40-bit manufacturer-defined identifier
+    pub manufacturer_id: u64,
+    // 24-bit IEEE OUI
+    pub organizationally_unique_id: u32,
+}
+
+impl SystemId {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.manufacturer_id.to_le_bytes()[..5].to_vec();
+        data.extend_from_slice(&self.organizationally_unique_id.to_le_bytes()[..3]);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() != SYSTEM_ID_LEN {
+            return None;
+        }
+        let mut manufacturer_id = [0u8; 8];
+        manufacturer_id[..5].copy_from_slice(&data[..5]);
+        let mut oui = [0u8; 4];
+        oui[..3].copy_from_slice(&data[5..]);
+
+        Some(Self {
+            manufacturer_id: u64::from_le_bytes(manufacturer_id),
+            organizationally_unique_id: u32::from_le_bytes(oui),
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PnpVendorIdSource {
+    Bluetooth = 1,
+    Usb = 2,
+}
+
+impl PnpVendorIdSource {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(PnpVendorIdSource::Bluetooth),
+            2 => Some(PnpVendorIdSource::Usb),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PnpId {
+    pub vendor_id_source: PnpVendorIdSource,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub product_version: u16,
+}
+
+impl PnpId {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = vec![self.vendor_id_source as u8];
+        data.extend_from_slice(&self.vendor_id.to_le_bytes());
+        data.extend_from_slice(&self.product_id.to_le_bytes());
+        data.extend_from_slice(&self.product_version.to_le_bytes());
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() != PNP_ID_LEN {
+            return None;
+        }
+        let u16_at = |i: usize| u16::from_le_bytes(data[i..i + 2].try_into().unwrap());
+
+        Some(Self {
+            vendor_id_source: PnpVendorIdSource::from_u8(data[0])?,
+            vendor_id: u16_at(1),
+            product_id: u16_at(3),
+            product_version: u16_at(5),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceInfo {
+    pub manufacturer_name: Option<String>,
+    pub model_number: Option<String>,
+    pub serial_number: Option<String>,
+    pub hardware_revision: Option<String>,
+    pub firmware_revision: Option<String>,
+    pub software_revision: Option<String>,
+    pub system_id: Option<SystemId>,
+    pub pnp_id: Option<PnpId>,
+}
+
+impl DeviceInfo {
+    // The characteristic values of the fields that are present, in the order they're registered
+    fn values(&self) -> Vec<(u16, Vec<u8>)> {
+        let strings = [
+            (MANUFACTURER_NAME_UUID, &self.manufacturer_name),
+            (MODEL_NUMBER_UUID, &self.model_number),
+            (SERIAL_NUMBER_UUID, &self.serial_number),
+            (HARDWARE_REVISION_UUID, &self.hardware_revision),
+            (FIRMWARE_REVISION_UUID, &self.firmware_revision),
+            (SOFTWARE_REVISION_UUID, &self.software_revision),
+        ];
+
+        let mut values: Vec<(u16, Vec<u8>)> = strings
+            .iter()
+            .filter_map(|(uuid, s)| s.as_ref().map(|s| (*uuid, s.as_bytes().to_vec())))
+            .collect();
+        if let Some(system_id) = &self.system_id {
+            values.push((SYSTEM_ID_UUID, system_id.serialize()));
+        }
+        if let Some(pnp_id) = &self.pnp_id {
+            values.push((PNP_ID_UUID, pnp_id.serialize()));
+        }
+        values
+    }
+
+    fn set_value(&mut self, uuid: u16, data: &[u8]) -> GattResult<()> {
+        let string = || Some(String::from_utf8_lossy(data).into_owned());
+        let invalid = GattError::Status(GattStatus::InvalidAttributeValueLength);
+
+        match uuid {
+            MANUFACTURER_NAME_UUID => self.manufacturer_name = string(),
+            MODEL_NUMBER_UUID => self.model_number = string(),
+            SERIAL_NUMBER_UUID => self.serial_number = string(),
+            HARDWARE_REVISION_UUID => self.hardware_revision = string(),
+            FIRMWARE_REVISION_UUID => self.firmware_revision = string(),
+            SOFTWARE_REVISION_UUID => self.software_revision = string(),
+            SYSTEM_ID_UUID => self.system_id = Some(SystemId::deserialize(data).ok_or(invalid)?),
+            PNP_ID_UUID => self.pnp_id = Some(PnpId::deserialize(data).ok_or(invalid)?),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceInfoReadEvent {
+    pub result: GattResult<DeviceInfo>,
+}
+
+pub struct DeviceInfoServer {
+    pub service: Arc<GattsService>,
+}
+
+impl DeviceInfoServer {
+    pub fn add_to_database(database: &GattsDatabase, info: &DeviceInfo) -> NrfResult<Arc<Self>> {
+        let service = database.add_service(BleUuid::from_sig(DEVICE_INFO_SERVICE_UUID))?;
+
+        for (uuid, value) in info.values() {
+            // The SoftDevice rejects a zero max length, which an empty string would give
+            let params = CharacteristicParams::new(
+                BleUuid::from_sig(uuid),
+                CharacteristicProperties::READ,
+                (value.len() as u16).max(1),
+            )
+            .with_initial_value(&value);
+            service.add_characteristic(&params)?;
+        }
+
+        return Ok(Arc::new(Self { service }));
+    }
+}
+
+struct PendingRead {
+    remaining: VecDeque<Arc<GattcCharacteristic>>,
+    info: DeviceInfo,
+}
+
+pub struct DeviceInfoClient {
+    characteristics: Vec<Arc<GattcCharacteristic>>,
+    pending_read: Mutex<Option<PendingRead>>,
+
+    pub on_read_complete: Publisher<Self, DeviceInfoReadEvent>,
+}
+
+impl DeviceInfoClient {
+    // Finds the device information service within the peer's discovered database
+    pub fn find(database: &GattcDatabase) -> Option<Arc<Self>> {
+        let service = database.find_service(&BleUuid::from_sig(DEVICE_INFO_SERVICE_UUID))?;
+
+        let client = Arc::new(Self {
+            characteristics: service.characteristics.clone(),
+            pending_read: Mutex::new(None),
+            on_read_complete: Publisher::new("On Device Info Read Complete"),
+        });
+
+        for characteristic in client.characteristics.iter() {
+            characteristic
+                .on_read_complete
+                .subscribe_with_priority(client.clone(), Priority::Internal);
+        }
+
+        return Some(client);
+    }
+
+    // Reads every characteristic the peer's service has, one after the other
+    pub fn read(self: &Arc<Self>) -> NrfResult<Arc<EventWaitable<Self, DeviceInfoReadEvent>>> {
+        let waitable = EventWaitable::new(&self.on_read_complete);
+        {
+            let mut pending_read = self.pending_read.lock().unwrap();
+            if pending_read.is_some() {
+                return Err(NrfErrorType::Busy.to_error());
+            }
+            let read = PendingRead {
+                remaining: self
+                    .characteristics
+                    .iter()
+                    .filter(|c| c.properties.contains(CharacteristicProperties::READ))
+                    .cloned()
+                    .collect(),
+                info: Default::default(),
+            };
+            if let Some(next) = read.remaining.front() {
+                next.read()?;
+                *pending_read = Some(read);
+                return Ok(waitable);
+            }
+        }
+
+        // Nothing to read
+        self.on_read_complete.dispatch(
+            self.clone(),
+            DeviceInfoReadEvent {
+                result: Ok(DeviceInfo::default()),
+            },
+        );
+        Ok(waitable)
+    }
+}
+
+impl Subscriber<GattcCharacteristic, ReadCompleteEvent> for DeviceInfoClient {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<GattcCharacteristic>,
+        event: ReadCompleteEvent,
+    ) -> SubscriberResult {
+        let result = {
+            let mut pending_read = self.pending_read.lock().unwrap();
+            let read = match pending_read.as_mut() {
+                Some(read) => read,
+                None => return Ok(None),
+            };
+            if !read
+                .remaining
+                .front()
+                .map_or(false, |c| Arc::ptr_eq(c, &sender))
+            {
+                return Ok(None);
+            }
+            read.remaining.pop_front();
+
+            let result = event
+                .result
+                .and_then(|data| {
+                    read.info
+                        .set_value(sender.uuid.as_sig().unwrap_or(0), &data)
+                })
+                .and_then(|_| match read.remaining.front() {
+                    Some(next) => next.read().map(|_| ()).map_err(GattError::from),
+                    None => Ok(()),
+                });
+
+            match result {
+                Ok(_) if !read.remaining.is_empty() => return Ok(None),
+                Ok(_) => Ok(pending_read.take().unwrap().info),
+                Err(e) => {
+                    pending_read.take();
+                    Err(e)
+                }
+            }
+        };
+
+        self.on_read_complete
+            .dispatch(self.clone(), DeviceInfoReadEvent { result });
+        return Ok(None);
+    }
+}
@@ -0,0 +1,484 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+
+use crate::ble_uuid::BleUuid;
+use crate::consts::ATT_VALUE_MAX_SIZE;
+use crate::events::{
+    CharacteristicWrittenEvent, NotificationReceivedEvent, ReadCompleteEvent,
+    SubscriptionWriteCompleteEvent, WriteCompleteEvent,
+};
+use crate::gatt::gattc::{GattcCharacteristic, GattcDatabase};
+use crate::gatt::gatts::{GattsCharacteristic, GattsDatabase, GattsService};
+use crate::gatt::{
+    CharacteristicParams, CharacteristicProperties, GattError, GattResult, GattStatus,
+    NotificationType,
+};
+use crate::peer::Peer;
+
+pub const HEART_RATE_SERVICE_UUID: u16 = 0x180D;
+pub const HEART_RATE_MEASUREMENT_UUID: u16 = 0x2A37;
+pub const BODY_SENSOR_LOCATION_UUID: u16 = 0x2A38;
+pub const HEART_RATE_CONTROL_POINT_UUID: u16 = 0x2A39;
+
+const CONTROL_POINT_RESET_ENERGY_EXPENDED: u8 = 0x01;
+// ATT error for control point values other than reset
+const ATT_ERROR_CONTROL_POINT_NOT_SUPPORTED: u8 = 0x80;
+
+// ATT notification header: opcode (1), handle (2)
+const NOTIFICATION_OVERHEAD: usize = 3;
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct MeasurementFlags: u8 {
+        const VALUE_FORMAT_U16 = 0x01;
+        const SENSOR_CONTACT_DETECTED = 0x02;
+        const SENSOR_CONTACT_SUPPORTED = 0x04;
+        const ENERGY_EXPENDED_PRESENT = 0x08;
+        const RR_INTERVALS_PRESENT = 0x10;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SensorContact {
+    NotSupported,
+    NotDetected,
+    Detected,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartRateMeasurement {
+    // Beats per minute
+    pub heart_rate: u16,
+    pub sensor_contact: SensorContact,
+    // Kilojoules since the last reset
+    pub energy_expended: Option<u16>,
+    // Time between beats in 1/1024 second units, oldest first
+    pub rr_intervals: Vec<u16>,
+}
+
+impl HeartRateMeasurement {
+    pub fn new(heart_rate: u16) -> Self {
+        Self {
+            heart_rate,
+            sensor_contact: SensorContact::NotSupported,
+            energy_expended: None,
+            rr_intervals: vec![],
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut flags = MeasurementFlags::empty();
+        let mut data = vec![0];
+
+        if self.heart_rate > u8::MAX as u16 {
+            flags |= MeasurementFlags::VALUE_FORMAT_U16;
+            data.extend_from_slice(&self.heart_rate.to_le_bytes());
+        } else {
+            data.push(self.heart_rate as u8);
+        }
+
+        match self.sensor_contact {
+            SensorContact::NotSupported => {}
+            SensorContact::NotDetected => flags |= MeasurementFlags::SENSOR_CONTACT_SUPPORTED,
+            SensorContact::Detected => {
+                flags |= MeasurementFlags::SENSOR_CONTACT_SUPPORTED
+                    | MeasurementFlags::SENSOR_CONTACT_DETECTED
+            }
+        }
+
+        if let Some(energy_expended) = self.energy_expended {
+            flags |= MeasurementFlags::ENERGY_EXPENDED_PRESENT;
+            data.extend_from_slice(&energy_expended.to_le_bytes());
+        }
+
+        if !self.rr_intervals.is_empty() {
+            flags |= MeasurementFlags::RR_INTERVALS_PRESENT;
+            for rr_interval in self.rr_intervals.iter() {
+                data.extend_from_slice(&rr_interval.to_le_bytes());
+            }
+        }
+
+        data[0] = flags.bits();
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let flags = MeasurementFlags::from_bits_truncate(*data.get(0)?);
+        let u16_at = |i: usize| -> Option<u16> {
+            Some(u16::from_le_bytes(data.get(i..i + 2)?.try_into().unwrap()))
+        };
+        let mut offset = 1;
+
+        let heart_rate = if flags.contains(MeasurementFlags::VALUE_FORMAT_U16) {
+            offset += 2;
+            u16_at(1)?
+        } else {
+            offset += 1;
+            *data.get(1)? as u16
+        };
+
+        let sensor_contact = if !flags.contains(MeasurementFlags::SENSOR_CONTACT_SUPPORTED) {
+            SensorContact::NotSupported
+        } else if flags.contains(MeasurementFlags::SENSOR_CONTACT_DETECTED) {
+            SensorContact::Detected
+        } else {
+            SensorContact::NotDetected
+        };
+
+        let energy_expended = if flags.contains(MeasurementFlags::ENERGY_EXPENDED_PRESENT) {
+            offset += 2;
+            Some(u16_at(offset - 2)?)
+        } else {
+            None
+        };
+
+        let mut rr_intervals = vec![];
+        if flags.contains(MeasurementFlags::RR_INTERVALS_PRESENT) {
+            while offset + 2 <= data.len() {
+                rr_intervals.push(u16_at(offset)?);
+                offset += 2;
+            }
+        }
+
+        Some(Self {
+            heart_rate,
+            sensor_contact,
+            energy_expended,
+            rr_intervals,
+        })
+    }
+
+    // Drops the oldest RR intervals until the measurement fits, they're sent again next time
+    fn serialize_within(&self, max_len: usize) -> Vec<u8> {
+        let data = self.serialize();
+        if data.len() <= max_len {
+            return data;
+        }
+        let mut measurement = self.clone();
+        let excess = (data.len() - max_len + 1) / 2;
+        measurement
+            .rr_intervals
+            .drain(..excess.min(measurement.rr_intervals.len()));
+        measurement.serialize()
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BodySensorLocation {
+    Other = 0,
+    Chest = 1,
+    Wrist = 2,
+    Finger = 3,
+    Hand = 4,
+    EarLobe = 5,
+    Foot = 6,
+}
+
+impl BodySensorLocation {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(BodySensorLocation::Other),
+            1 => Some(BodySensorLocation::Chest),
+            2 => Some(BodySensorLocation::Wrist),
+            3 => Some(BodySensorLocation::Finger),
+            4 => Some(BodySensorLocation::Hand),
+            5 => Some(BodySensorLocation::EarLobe),
+            6 => Some(BodySensorLocation::Foot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeartRateMeasurementEvent {
+    pub measurement: HeartRateMeasurement,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BodySensorLocationReadEvent {
+    pub result: GattResult<BodySensorLocation>,
+}
+
+// No params
+#[derive(Debug, Copy, Clone)]
+pub struct EnergyExpendedResetEvent {}
+
+pub struct HeartRateServer {
+    pub service: Arc<GattsService>,
+    peer: Arc<Peer>,
+    measurement: Arc<GattsCharacteristic>,
+
+    pub on_energy_expended_reset: Publisher<Self, EnergyExpendedResetEvent>,
+}
+
+impl HeartRateServer {
+    // The control point is only added when energy expended is supported, as the spec requires
+    pub fn add_to_database(
+        database: &Arc<GattsDatabase>,
+        body_sensor_location: Option<BodySensorLocation>,
+        energy_expended_supported: bool,
+    ) -> NrfResult<Arc<Self>> {
+        let service = database.add_service(BleUuid::from_sig(HEART_RATE_SERVICE_UUID))?;
+
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(HEART_RATE_MEASUREMENT_UUID),
+            CharacteristicProperties::NOTIFY,
+            ATT_VALUE_MAX_SIZE as u16,
+        );
+        let measurement = service.add_characteristic(&params)?;
+
+        if let Some(location) = body_sensor_location {
+            let params = CharacteristicParams::new(
+                BleUuid::from_sig(BODY_SENSOR_LOCATION_UUID),
+                CharacteristicProperties::READ,
+                1,
+            )
+            .with_fixed_length()
+            .with_initial_value(&[location as u8]);
+            service.add_characteristic(&params)?;
+        }
+
+        let control_point = if energy_expended_supported {
+            let params = CharacteristicParams::new(
+                BleUuid::from_sig(HEART_RATE_CONTROL_POINT_UUID),
+                CharacteristicProperties::WRITE,
+                1,
+            )
+            .with_fixed_length()
+            .with_write_authorization()
+            .with_initial_value(&[0]);
+            let control_point = service.add_characteristic(&params)?;
+            control_point.set_write_authorizer(|value| match value {
+                [CONTROL_POINT_RESET_ENERGY_EXPENDED] => Ok(()),
+                _ => Err(ATT_ERROR_CONTROL_POINT_NOT_SUPPORTED),
+            });
+            Some(control_point)
+        } else {
+            None
+        };
+
+        let server = Arc::new(Self {
+            service,
+            peer: database.peer().clone(),
+            measurement,
+            on_energy_expended_reset: Publisher::new("On Energy Expended Reset"),
+        });
+
+        if let Some(control_point) = control_point {
+            control_point
+                .on_write
+                .subscribe_with_priority(server.clone(), Priority::Internal);
+        }
+
+        return Ok(server);
+    }
+
+    // Oldest RR intervals are dropped if the measurement doesn't fit in a notification
+    pub fn send_measurement(&self, measurement: &HeartRateMeasurement) -> NrfResult<()> {
+        let max_len = self.peer.mtu_size() as usize - NOTIFICATION_OVERHEAD;
+        self.measurement
+            .notify(&measurement.serialize_within(max_len))
+    }
+
+    pub fn is_client_subscribed(&self) -> bool {
+        self.measurement.subscription().is_some()
+    }
+}
+
+impl Subscriber<GattsCharacteristic, CharacteristicWrittenEvent> for HeartRateServer {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattsCharacteristic>,
+        _event: CharacteristicWrittenEvent,
+    ) -> SubscriberResult {
+        // The write authorizer rejects everything other than a reset
+        self.on_energy_expended_reset
+            .dispatch(self.clone(), EnergyExpendedResetEvent {});
+        return Ok(None);
+    }
+}
+
+pub struct HeartRateClient {
+    measurement: Arc<GattcCharacteristic>,
+    body_sensor_location: Option<Arc<GattcCharacteristic>>,
+    control_point: Option<Arc<GattcCharacteristic>>,
+
+    pub on_measurement: Publisher<Self, HeartRateMeasurementEvent>,
+    pub on_body_sensor_location_read: Publisher<Self, BodySensorLocationReadEvent>,
+}
+
+impl HeartRateClient {
+    // Finds the heart rate service within the peer's discovered database
+    pub fn find(database: &GattcDatabase) -> Option<Arc<Self>> {
+        let service = database.find_service(&BleUuid::from_sig(HEART_RATE_SERVICE_UUID))?;
+        let measurement =
+            service.find_characteristic(&BleUuid::from_sig(HEART_RATE_MEASUREMENT_UUID))?;
+
+        let client = Arc::new(Self {
+            measurement: measurement.clone(),
+            body_sensor_location: service
+                .find_characteristic(&BleUuid::from_sig(BODY_SENSOR_LOCATION_UUID))
+                .cloned(),
+            control_point: service
+                .find_characteristic(&BleUuid::from_sig(HEART_RATE_CONTROL_POINT_UUID))
+                .cloned(),
+            on_measurement: Publisher::new("On Heart Rate Measurement"),
+            on_body_sensor_location_read: Publisher::new("On Body Sensor Location Read"),
+        });
+
+        measurement
+            .on_notification_received
+            .subscribe_with_priority(client.clone(), Priority::Internal);
+        if let Some(location) = &client.body_sensor_location {
+            location
+                .on_read_complete
+                .subscribe_with_priority(client.clone(), Priority::Internal);
+        }
+
+        return Some(client);
+    }
+
+    pub fn subscribe(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.measurement.subscribe(NotificationType::Notification)
+    }
+
+    pub fn unsubscribe(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.measurement.unsubscribe()
+    }
+
+    pub fn read_body_sensor_location(
+        self: &Arc<Self>,
+    ) -> NrfResult<Arc<EventWaitable<Self, BodySensorLocationReadEvent>>> {
+        let location = self
+            .body_sensor_location
+            .as_ref()
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        let waitable = EventWaitable::new(&self.on_body_sensor_location_read);
+
+        location.read().and_then(|_| Ok(waitable))
+    }
+
+    pub fn reset_energy_expended(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, WriteCompleteEvent>>> {
+        let control_point = self
+            .control_point
+            .as_ref()
+            .ok_or_else(|| NrfErrorType::NotSupported.to_error())?;
+
+        control_point.write(&[CONTROL_POINT_RESET_ENERGY_EXPENDED])
+    }
+}
+
+impl Subscriber<GattcCharacteristic, NotificationReceivedEvent> for HeartRateClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: NotificationReceivedEvent,
+    ) -> SubscriberResult {
+        match HeartRateMeasurement::deserialize(&event.data) {
+            Some(measurement) => self
+                .on_measurement
+                .dispatch(self.clone(), HeartRateMeasurementEvent { measurement }),
+            None => warn!("Received invalid heart rate measurement: {:?}", event.data),
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattcCharacteristic, ReadCompleteEvent> for HeartRateClient {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: ReadCompleteEvent,
+    ) -> SubscriberResult {
+        let result = event.result.and_then(|data| {
+            data.get(0)
+                .and_then(|v| BodySensorLocation::from_u8(*v))
+                .ok_or(GattError::Status(GattStatus::InvalidAttributeValueLength))
+        });
+
+        self.on_body_sensor_location_read
+            .dispatch(self.clone(), BodySensorLocationReadEvent { result });
+        return Ok(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heart_rate_uses_a_u16_only_when_needed() {
+        assert_eq!(HeartRateMeasurement::new(72).serialize(), [0x00, 72]);
+        assert_eq!(
+            HeartRateMeasurement::new(300).serialize(),
+            [0x01, 0x2C, 0x01]
+        );
+        assert_eq!(
+            HeartRateMeasurement::deserialize(&[0x01, 0x2C, 0x01]),
+            Some(HeartRateMeasurement::new(300))
+        );
+    }
+
+    #[test]
+    fn round_trips_every_field() {
+        let measurement = HeartRateMeasurement {
+            heart_rate: 300,
+            sensor_contact: SensorContact::Detected,
+            energy_expended: Some(0x1234),
+            rr_intervals: vec![1024, 512],
+        };
+        let data = measurement.serialize();
+
+        assert_eq!(data, [0x1F, 0x2C, 0x01, 0x34, 0x12, 0x00, 0x04, 0x00, 0x02]);
+        assert_eq!(HeartRateMeasurement::deserialize(&data), Some(measurement));
+    }
+
+    #[test]
+    fn deserializes_sensor_contact() {
+        let not_detected = HeartRateMeasurement::deserialize(&[0x04, 60]).unwrap();
+        let detected = HeartRateMeasurement::deserialize(&[0x06, 60]).unwrap();
+
+        assert_eq!(not_detected.sensor_contact, SensorContact::NotDetected);
+        assert_eq!(detected.sensor_contact, SensorContact::Detected);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert_eq!(HeartRateMeasurement::deserialize(&[]), None);
+        assert_eq!(HeartRateMeasurement::deserialize(&[0x01, 0x2C]), None);
+        assert_eq!(HeartRateMeasurement::deserialize(&[0x08, 60, 0x01]), None);
+    }
+
+    #[test]
+    fn oldest_rr_intervals_are_dropped_to_fit() {
+        let mut measurement = HeartRateMeasurement::new(60);
+        measurement.rr_intervals = (1..=10).collect();
+
+        let fits = measurement.serialize_within(22);
+        let even = measurement.serialize_within(10);
+        let odd = measurement.serialize_within(9);
+
+        assert_eq!(fits, measurement.serialize());
+        let rr_intervals = |data: &[u8]| {
+            HeartRateMeasurement::deserialize(data)
+                .unwrap()
+                .rr_intervals
+        };
+        assert_eq!(rr_intervals(&even), [7, 8, 9, 10]);
+        assert_eq!(odd.len(), 8);
+        assert_eq!(rr_intervals(&odd), [8, 9, 10]);
+    }
+}
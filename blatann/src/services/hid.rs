@@ -0,0 +1,783 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use blatann_event::{
+    EventWaitable, Priority, Publisher, Subscribable, Subscriber, SubscriberResult, Waitable,
+};
+
+use nrf_driver::error::{NrfErrorType, NrfResult};
+use nrf_driver::gap::enums::BleGapSecurityMode;
+
+use crate::advertise_data::{AdvData, AdvertisingFlags};
+use crate::ble_uuid::BleUuid;
+use crate::device::Appearance;
+use crate::events::{CharacteristicWrittenEvent, ConnectionEvent};
+use crate::gatt::gatts::{GattsCharacteristic, GattsDatabase, GattsService};
+use crate::gatt::{CharacteristicParams, CharacteristicProperties};
+use crate::peer::Peer;
+
+pub const HID_SERVICE_UUID: u16 = 0x1812;
+pub const HID_INFORMATION_UUID: u16 = 0x2A4A;
+pub const REPORT_MAP_UUID: u16 = 0x2A4B;
+pub const HID_CONTROL_POINT_UUID: u16 = 0x2A4C;
+pub const REPORT_UUID: u16 = 0x2A4D;
+pub const PROTOCOL_MODE_UUID: u16 = 0x2A4E;
+pub const BOOT_KEYBOARD_INPUT_UUID: u16 = 0x2A22;
+pub const BOOT_KEYBOARD_OUTPUT_UUID: u16 = 0x2A32;
+pub const BOOT_MOUSE_INPUT_UUID: u16 = 0x2A33;
+pub const REPORT_REFERENCE_UUID: u16 = 0x2908;
+
+// HID attributes must only be accessible over an encrypted link
+const PERMISSION: BleGapSecurityMode = BleGapSecurityMode::EncryptedNoMitm;
+
+// How long to wait for room in the notification queue before giving up
+const NOTIFICATION_QUEUE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const BOOT_KEYBOARD_INPUT_LEN: u16 = 8;
+const BOOT_MOUSE_INPUT_LEN: u16 = 3;
+const REPORT_MAP_MAX_LEN: usize = 512;
+
+const CONTROL_POINT_SUSPEND: u8 = 0x00;
+const CONTROL_POINT_EXIT_SUSPEND: u8 = 0x01;
+
+// Boot-compatible keyboard: modifiers, reserved, 6 keys in, 5 LEDs out
+const KEYBOARD_REPORT_MAP: &[u8] = &[
+    0x05, 0x01, 0x09, 0x06, 0xA1, 0x01, 0x05, 0x07, 0x19, 0xE0, 0x29, 0xE7, 0x15, 0x00, 0x25, 0x01,
+    0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0x95, 0x01, 0x75, 0x08, 0x81, 0x01, 0x95, 0x05, 0x75, 0x01,
+    0x05, 0x08, 0x19, 0x01, 0x29, 0x05, 0x91, 0x02, 0x95, 0x01, 0x75, 0x03, 0x91, 0x01, 0x95, 0x06,
+    0x75, 0x08, 0x15, 0x00, 0x25, 0x65, 0x05, 0x07, 0x19, 0x00, 0x29, 0x65, 0x81, 0x00, 0xC0,
+];
+
+// Boot-compatible mouse: 3 buttons, x, y, followed by a wheel which boot hosts ignore
+const MOUSE_REPORT_MAP: &[u8] = &[
+    0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00, 0x05, 0x09, 0x19, 0x01, 0x29, 0x03,
+    0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01, 0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x01,
+    0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x09, 0x38, 0x15, 0x81, 0x25, 0x7F, 0x75, 0x08, 0x95, 0x03,
+    0x81, 0x06, 0xC0, 0xC0,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HidDeviceType {
+    Keyboard,
+    Mouse,
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProtocolMode {
+    Boot = 0,
+    Report = 1,
+}
+
+impl ProtocolMode {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ProtocolMode::Boot),
+            1 => Some(ProtocolMode::Report),
+            _ => None,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReportType {
+    Input = 1,
+    Output = 2,
+    Feature = 3,
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct HidInfoFlags: u8 {
+        const REMOTE_WAKE = 0x01;
+        const NORMALLY_CONNECTABLE = 0x02;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HidInformation {
+    // HID spec version in BCD, e.g. 0x0111 for 1.11
+    pub bcd_hid: u16,
+    pub country_code: u8,
+    pub flags: HidInfoFlags,
+}
+
+impl Default for HidInformation {
+    fn default() -> Self {
+        Self {
+            bcd_hid: 0x0111,
+            country_code: 0,
+            flags: HidInfoFlags::NORMALLY_CONNECTABLE,
+        }
+    }
+}
+
+impl HidInformation {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.bcd_hid.to_le_bytes().to_vec();
+        data.push(self.country_code);
+        data.push(self.flags.bits());
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 {
+            return None;
+        }
+        Some(Self {
+            bcd_hid: u16::from_le_bytes([data[0], data[1]]),
+            country_code: data[2],
+            flags: HidInfoFlags::from_bits_truncate(data[3]),
+        })
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct KeyModifiers: u8 {
+        const LEFT_CTRL = 0x01;
+        const LEFT_SHIFT = 0x02;
+        const LEFT_ALT = 0x04;
+        const LEFT_GUI = 0x08;
+        const RIGHT_CTRL = 0x10;
+        const RIGHT_SHIFT = 0x20;
+        const RIGHT_ALT = 0x40;
+        const RIGHT_GUI = 0x80;
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct KeyboardReport {
+    pub modifiers: KeyModifiers,
+    // Usage IDs from the keyboard usage page, 0 for no key
+    pub keys: [u8; 6],
+}
+
+impl KeyboardReport {
+    pub fn new(modifiers: KeyModifiers, key: u8) -> Self {
+        Self {
+            modifiers,
+            keys: [key, 0, 0, 0, 0, 0],
+        }
+    }
+
+    // Maps an ASCII character to the key press that types it on a US layout
+    pub fn from_char(c: char) -> Option<Self> {
+        let (shift, key) = match c {
+            'a'..='z' => (false, 0x04 + (c as u8 - b'a')),
+            'A'..='Z' => (true, 0x04 + (c as u8 - b'A')),
+            '1'..='9' => (false, 0x1E + (c as u8 - b'1')),
+            '0' => (false, 0x27),
+            '\n' => (false, 0x28),
+            '\t' => (false, 0x2B),
+            ' ' => (false, 0x2C),
+            '-' => (false, 0x2D),
+            '=' => (false, 0x2E),
+            '[' => (false, 0x2F),
+            ']' => (false, 0x30),
+            '\\' => (false, 0x31),
+            ';' => (false, 0x33),
+            '\'' => (false, 0x34),
+            '`' => (false, 0x35),
+            ',' => (false, 0x36),
+            '.' => (false, 0x37),
+            '/' => (false, 0x38),
+            '!' => (true, 0x1E),
+            '@' => (true, 0x1F),
+            '#' => (true, 0x20),
+            '$' => (true, 0x21),
+            '%' => (true, 0x22),
+            '^' => (true, 0x23),
+            '&' => (true, 0x24),
+            '*' => (true, 0x25),
+            '(' => (true, 0x26),
+            ')' => (true, 0x27),
+            '_' => (true, 0x2D),
+            '+' => (true, 0x2E),
+            '{' => (true, 0x2F),
+            '}' => (true, 0x30),
+            '|' => (true, 0x31),
+            ':' => (true, 0x33),
+            '"' => (true, 0x34),
+            '~' => (true, 0x35),
+            '<' => (true, 0x36),
+            '>' => (true, 0x37),
+            '?' => (true, 0x38),
+            _ => return None,
+        };
+        let modifiers = if shift {
+            KeyModifiers::LEFT_SHIFT
+        } else {
+            KeyModifiers::empty()
+        };
+        Some(Self::new(modifiers, key))
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = vec![self.modifiers.bits(), 0];
+        data.extend_from_slice(&self.keys);
+        data
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct MouseButtons: u8 {
+        const LEFT = 0x01;
+        const RIGHT = 0x02;
+        const MIDDLE = 0x04;
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MouseReport {
+    pub buttons: MouseButtons,
+    pub x: i8,
+    pub y: i8,
+    pub wheel: i8,
+}
+
+impl MouseReport {
+    pub fn serialize(&self) -> Vec<u8> {
+        vec![
+            self.buttons.bits(),
+            self.x as u8,
+            self.y as u8,
+            self.wheel as u8,
+        ]
+    }
+
+    // The boot protocol report has no wheel
+    pub fn serialize_boot(&self) -> Vec<u8> {
+        self.serialize()[..BOOT_MOUSE_INPUT_LEN as usize].to_vec()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReportDefinition {
+    // 0 if the report map doesn't use report IDs
+    pub report_id: u8,
+    pub report_type: ReportType,
+    pub length: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct HidConfig {
+    pub report_map: Vec<u8>,
+    pub reports: Vec<ReportDefinition>,
+    // Adds the boot protocol characteristics for the device type
+    pub boot_device: Option<HidDeviceType>,
+    pub information: HidInformation,
+}
+
+impl HidConfig {
+    pub fn keyboard() -> Self {
+        Self {
+            report_map: KEYBOARD_REPORT_MAP.to_vec(),
+            reports: vec![
+                ReportDefinition {
+                    report_id: 0,
+                    report_type: ReportType::Input,
+                    length: BOOT_KEYBOARD_INPUT_LEN,
+                },
+                ReportDefinition {
+                    report_id: 0,
+                    report_type: ReportType::Output,
+                    length: 1,
+                },
+            ],
+            boot_device: Some(HidDeviceType::Keyboard),
+            information: Default::default(),
+        }
+    }
+
+    pub fn mouse() -> Self {
+        Self {
+            report_map: MOUSE_REPORT_MAP.to_vec(),
+            reports: vec![ReportDefinition {
+                report_id: 0,
+                report_type: ReportType::Input,
+                length: 4,
+            }],
+            boot_device: Some(HidDeviceType::Mouse),
+            information: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputReportEvent {
+    pub report_id: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureReportWrittenEvent {
+    pub report_id: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ProtocolModeChangeEvent {
+    pub protocol_mode: ProtocolMode,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SuspendStateChangeEvent {
+    pub suspended: bool,
+}
+
+struct Report {
+    definition: ReportDefinition,
+    characteristic: Arc<GattsCharacteristic>,
+}
+
+pub struct HidServer {
+    pub service: Arc<GattsService>,
+    database: Arc<GattsDatabase>,
+    boot_device: Option<HidDeviceType>,
+    reports: Vec<Report>,
+    protocol_mode_characteristic: Option<Arc<GattsCharacteristic>>,
+    control_point: Arc<GattsCharacteristic>,
+    boot_input: Option<Arc<GattsCharacteristic>>,
+    boot_output: Option<Arc<GattsCharacteristic>>,
+    protocol_mode: Mutex<ProtocolMode>,
+
+    pub on_output_report: Publisher<Self, OutputReportEvent>,
+    pub on_feature_report_written: Publisher<Self, FeatureReportWrittenEvent>,
+    pub on_protocol_mode_change: Publisher<Self, ProtocolModeChangeEvent>,
+    pub on_suspend_change: Publisher<Self, SuspendStateChangeEvent>,
+}
+
+impl HidServer {
+    pub fn add_to_database(
+        database: &Arc<GattsDatabase>,
+        config: &HidConfig,
+    ) -> NrfResult<Arc<Self>> {
+        if config.report_map.is_empty() || config.report_map.len() > REPORT_MAP_MAX_LEN {
+            return Err(NrfErrorType::InvalidParam.to_error());
+        }
+
+        let service = database.add_service(BleUuid::from_sig(HID_SERVICE_UUID))?;
+
+        // Protocol mode is only needed by boot devices
+        let protocol_mode_characteristic = match config.boot_device {
+            Some(_) => {
+                let params = CharacteristicParams::new(
+                    BleUuid::from_sig(PROTOCOL_MODE_UUID),
+                    CharacteristicProperties::READ
+                        | CharacteristicProperties::WRITE_WITHOUT_RESPONSE,
+                    1,
+                )
+                .with_permissions(PERMISSION, PERMISSION)
+                .with_fixed_length()
+                .with_initial_value(&[ProtocolMode::Report as u8]);
+                Some(service.add_characteristic(&params)?)
+            }
+            None => None,
+        };
+
+        let mut reports = vec![];
+        for definition in config.reports.iter() {
+            let properties = match definition.report_type {
+                ReportType::Input => {
+                    CharacteristicProperties::READ | CharacteristicProperties::NOTIFY
+                }
+                ReportType::Output => {
+                    CharacteristicProperties::READ
+                        | CharacteristicProperties::WRITE
+                        | CharacteristicProperties::WRITE_WITHOUT_RESPONSE
+                }
+                ReportType::Feature => {
+                    CharacteristicProperties::READ | CharacteristicProperties::WRITE
+                }
+            };
+            let params = CharacteristicParams::new(
+                BleUuid::from_sig(REPORT_UUID),
+                properties,
+                definition.length,
+            )
+            .with_permissions(PERMISSION, PERMISSION)
+            .with_fixed_length()
+            .with_initial_value(&vec![0; definition.length as usize]);
+            let characteristic = service.add_characteristic(&params)?;
+            characteristic.add_descriptor(
+                &BleUuid::from_sig(REPORT_REFERENCE_UUID),
+                &[definition.report_id, definition.report_type as u8],
+                PERMISSION,
+            )?;
+
+            reports.push(Report {
+                definition: *definition,
+                characteristic,
+            });
+        }
+
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(REPORT_MAP_UUID),
+            CharacteristicProperties::READ,
+            config.report_map.len() as u16,
+        )
+        .with_permissions(PERMISSION, BleGapSecurityMode::NoAccess)
+        .with_fixed_length()
+        .with_initial_value(&config.report_map);
+        service.add_characteristic(&params)?;
+
+        let (boot_input, boot_output) = match config.boot_device {
+            Some(HidDeviceType::Keyboard) => {
+                let params = CharacteristicParams::new(
+                    BleUuid::from_sig(BOOT_KEYBOARD_INPUT_UUID),
+                    CharacteristicProperties::READ | CharacteristicProperties::NOTIFY,
+                    BOOT_KEYBOARD_INPUT_LEN,
+                )
+                .with_permissions(PERMISSION, PERMISSION)
+                .with_fixed_length()
+                .with_initial_value(&[0; BOOT_KEYBOARD_INPUT_LEN as usize]);
+                let input = service.add_characteristic(&params)?;
+
+                let params = CharacteristicParams::new(
+                    BleUuid::from_sig(BOOT_KEYBOARD_OUTPUT_UUID),
+                    CharacteristicProperties::READ
+                        | CharacteristicProperties::WRITE
+                        | CharacteristicProperties::WRITE_WITHOUT_RESPONSE,
+                    1,
+                )
+                .with_permissions(PERMISSION, PERMISSION)
+                .with_fixed_length()
+                .with_initial_value(&[0]);
+                let output = service.add_characteristic(&params)?;
+                (Some(input), Some(output))
+            }
+            Some(HidDeviceType::Mouse) => {
+                let params = CharacteristicParams::new(
+                    BleUuid::from_sig(BOOT_MOUSE_INPUT_UUID),
+                    CharacteristicProperties::READ | CharacteristicProperties::NOTIFY,
+                    BOOT_MOUSE_INPUT_LEN,
+                )
+                .with_permissions(PERMISSION, PERMISSION)
+                .with_fixed_length()
+                .with_initial_value(&[0; BOOT_MOUSE_INPUT_LEN as usize]);
+                (Some(service.add_characteristic(&params)?), None)
+            }
+            None => (None, None),
+        };
+
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(HID_INFORMATION_UUID),
+            CharacteristicProperties::READ,
+            4,
+        )
+        .with_permissions(PERMISSION, BleGapSecurityMode::NoAccess)
+        .with_fixed_length()
+        .with_initial_value(&config.information.serialize());
+        service.add_characteristic(&params)?;
+
+        let params = CharacteristicParams::new(
+            BleUuid::from_sig(HID_CONTROL_POINT_UUID),
+            CharacteristicProperties::WRITE_WITHOUT_RESPONSE,
+            1,
+        )
+        .with_permissions(PERMISSION, PERMISSION)
+        .with_fixed_length()
+        .with_initial_value(&[CONTROL_POINT_EXIT_SUSPEND]);
+        let control_point = service.add_characteristic(&params)?;
+
+        let server = Arc::new(Self {
+            service,
+            database: database.clone(),
+            boot_device: config.boot_device,
+            reports,
+            protocol_mode_characteristic,
+            control_point,
+            boot_input,
+            boot_output,
+            protocol_mode: Mutex::new(ProtocolMode::Report),
+            on_output_report: Publisher::new("On HID Output Report"),
+            on_feature_report_written: Publisher::new("On HID Feature Report Written"),
+            on_protocol_mode_change: Publisher::new("On HID Protocol Mode Change"),
+            on_suspend_change: Publisher::new("On HID Suspend Change"),
+        });
+
+        let writable = server
+            .reports
+            .iter()
+            .filter(|r| r.definition.report_type != ReportType::Input)
+            .map(|r| &r.characteristic)
+            .chain(server.protocol_mode_characteristic.iter())
+            .chain(server.boot_output.iter())
+            .chain(Some(&server.control_point));
+        for characteristic in writable {
+            characteristic
+                .on_write
+                .subscribe_with_priority(server.clone(), Priority::Internal);
+        }
+        database
+            .peer()
+            .on_connect
+            .subscribe_with_priority(server.clone(), Priority::Internal);
+
+        return Ok(server);
+    }
+
+    pub fn appearance(&self) -> Appearance {
+        match self.boot_device {
+            Some(HidDeviceType::Keyboard) => Appearance::HidKeyboard,
+            Some(HidDeviceType::Mouse) => Appearance::HidMouse,
+            None => Appearance::GenericHid,
+        }
+    }
+
+    // Advertising payload hosts expect from a HID device
+    pub fn advertising_data(&self, name: &str) -> AdvData {
+        let mut adv_data = AdvData::default();
+        adv_data.set_flags(
+            AdvertisingFlags::GENERAL_DISCOVERY_MODE | AdvertisingFlags::BR_EDR_NOT_SUPPORTED,
+        );
+        adv_data.set_appearance(self.appearance());
+        adv_data.set_service_uuid16s(&[HID_SERVICE_UUID], true);
+        adv_data.set_name(name, true);
+        adv_data
+    }
+
+    pub fn protocol_mode(&self) -> ProtocolMode {
+        *self.protocol_mode.lock().unwrap()
+    }
+
+    // Sending blocks while the notification queue is full, so it must not be called from
+    // an event handler
+    pub fn send_input_report(&self, report_id: u8, data: &[u8]) -> NrfResult<()> {
+        let report = self
+            .find_report(report_id, ReportType::Input)
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        self.send(&report.characteristic, data)
+    }
+
+    pub fn set_feature_report(&self, report_id: u8, data: &[u8]) -> NrfResult<()> {
+        let report = self
+            .find_report(report_id, ReportType::Feature)
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        report.characteristic.set_value(data)
+    }
+
+    pub fn send_keyboard_report(&self, report: &KeyboardReport) -> NrfResult<()> {
+        let data = report.serialize();
+        match (self.protocol_mode(), &self.boot_input) {
+            (ProtocolMode::Boot, Some(boot_input)) => self.send(boot_input, &data),
+            _ => self.send_first_input(&data),
+        }
+    }
+
+    // Presses and releases the keys for each character. Fails without sending anything if
+    // the text has characters that can't be typed
+    pub fn type_string(&self, text: &str) -> NrfResult<()> {
+        let reports = text
+            .chars()
+            .map(KeyboardReport::from_char)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| NrfErrorType::InvalidParam.to_error())?;
+
+        let release = KeyboardReport::default();
+        for report in reports.iter() {
+            self.send_keyboard_report(report)?;
+            self.send_keyboard_report(&release)?;
+        }
+        Ok(())
+    }
+
+    pub fn send_mouse_report(&self, report: &MouseReport) -> NrfResult<()> {
+        match (self.protocol_mode(), &self.boot_input) {
+            (ProtocolMode::Boot, Some(boot_input)) => {
+                self.send(boot_input, &report.serialize_boot())
+            }
+            _ => self.send_first_input(&report.serialize()),
+        }
+    }
+
+    // Splits movements larger than a single report into several
+    pub fn move_pointer(&self, dx: i32, dy: i32, buttons: MouseButtons) -> NrfResult<()> {
+        let (mut dx, mut dy) = (dx, dy);
+        loop {
+            let x = dx.max(i8::MIN as i32 + 1).min(i8::MAX as i32);
+            let y = dy.max(i8::MIN as i32 + 1).min(i8::MAX as i32);
+            self.send_mouse_report(&MouseReport {
+                buttons,
+                x: x as i8,
+                y: y as i8,
+                wheel: 0,
+            })?;
+            dx -= x;
+            dy -= y;
+            if dx == 0 && dy == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn click(&self, buttons: MouseButtons) -> NrfResult<()> {
+        self.send_mouse_report(&MouseReport {
+            buttons,
+            ..Default::default()
+        })?;
+        self.send_mouse_report(&Default::default())
+    }
+
+    fn find_report(&self, report_id: u8, report_type: ReportType) -> Option<&Report> {
+        self.reports.iter().find(|r| {
+            r.definition.report_id == report_id && r.definition.report_type == report_type
+        })
+    }
+
+    fn send_first_input(&self, data: &[u8]) -> NrfResult<()> {
+        let report = self
+            .reports
+            .iter()
+            .find(|r| r.definition.report_type == ReportType::Input)
+            .ok_or_else(|| NrfErrorType::NotFound.to_error())?;
+        self.send(&report.characteristic, data)
+    }
+
+    fn send(&self, characteristic: &GattsCharacteristic, data: &[u8]) -> NrfResult<()> {
+        loop {
+            let waitable = EventWaitable::new(&self.database.on_notifications_sent);
+            match characteristic.notify(data) {
+                Err(e) if matches!(e.error_type, NrfErrorType::Resources) => {
+                    // Queue is full, wait for a notification to go out
+                    if waitable.wait_timeout(NOTIFICATION_QUEUE_TIMEOUT).is_err() {
+                        return Err(e);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn set_protocol_mode(self: &Arc<Self>, protocol_mode: ProtocolMode) {
+        let changed = {
+            let mut current = self.protocol_mode.lock().unwrap();
+            let changed = *current != protocol_mode;
+            *current = protocol_mode;
+            changed
+        };
+
+        if changed {
+            self.on_protocol_mode_change
+                .dispatch(self.clone(), ProtocolModeChangeEvent { protocol_mode });
+        }
+    }
+}
+
+impl Subscriber<GattsCharacteristic, CharacteristicWrittenEvent> for HidServer {
+    fn handle(
+        self: Arc<Self>,
+        sender: Arc<GattsCharacteristic>,
+        event: CharacteristicWrittenEvent,
+    ) -> SubscriberResult {
+        let handle = sender.value_handle;
+
+        if handle == self.control_point.value_handle {
+            let suspended = match event.value.get(0).copied() {
+                Some(CONTROL_POINT_SUSPEND) => true,
+                Some(CONTROL_POINT_EXIT_SUSPEND) => false,
+                _ => return Ok(None),
+            };
+            self.on_suspend_change
+                .dispatch(self.clone(), SuspendStateChangeEvent { suspended });
+        } else if Some(handle)
+            == self
+                .protocol_mode_characteristic
+                .as_ref()
+                .map(|c| c.value_handle)
+        {
+            match event.value.get(0).copied().and_then(ProtocolMode::from_u8) {
+                Some(mode) => self.set_protocol_mode(mode),
+                None => warn!("Invalid HID protocol mode written: {:?}", event.value),
+            }
+        } else if Some(handle) == self.boot_output.as_ref().map(|c| c.value_handle) {
+            self.on_output_report.dispatch(
+                self.clone(),
+                OutputReportEvent {
+                    report_id: 0,
+                    data: event.value,
+                },
+            );
+        } else if let Some(report) = self
+            .reports
+            .iter()
+            .find(|r| r.characteristic.value_handle == handle)
+        {
+            let report_id = report.definition.report_id;
+            match report.definition.report_type {
+                ReportType::Output => self.on_output_report.dispatch(
+                    self.clone(),
+                    OutputReportEvent {
+                        report_id,
+                        data: event.value,
+                    },
+                ),
+                ReportType::Feature => self.on_feature_report_written.dispatch(
+                    self.clone(),
+                    FeatureReportWrittenEvent {
+                        report_id,
+                        data: event.value,
+                    },
+                ),
+                ReportType::Input => {}
+            }
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<Peer, ConnectionEvent> for HidServer {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: ConnectionEvent) -> SubscriberResult {
+        // Hosts expect report protocol on every new connection
+        if let Some(characteristic) = &self.protocol_mode_characteristic {
+            if let Err(e) = characteristic.set_value(&[ProtocolMode::Report as u8]) {
+                error!("Failed to reset HID protocol mode: {:?}", e);
+            }
+        }
+        self.set_protocol_mode(ProtocolMode::Report);
+        return Ok(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_char_maps_letters_digits_and_shifted_symbols() {
+        let a = KeyboardReport::from_char('a').unwrap();
+        let upper_a = KeyboardReport::from_char('A').unwrap();
+        let zero = KeyboardReport::from_char('0').unwrap();
+        let bang = KeyboardReport::from_char('!').unwrap();
+        let question = KeyboardReport::from_char('?').unwrap();
+
+        assert_eq!(a, KeyboardReport::new(KeyModifiers::empty(), 0x04));
+        assert_eq!(upper_a, KeyboardReport::new(KeyModifiers::LEFT_SHIFT, 0x04));
+        assert_eq!(zero, KeyboardReport::new(KeyModifiers::empty(), 0x27));
+        assert_eq!(bang, KeyboardReport::new(KeyModifiers::LEFT_SHIFT, 0x1E));
+        assert_eq!(
+            question,
+            KeyboardReport::new(KeyModifiers::LEFT_SHIFT, 0x38)
+        );
+        assert_eq!(KeyboardReport::from_char('é'), None);
+    }
+
+    #[test]
+    fn keyboard_report_serializes_with_the_reserved_byte() {
+        let report = KeyboardReport::from_char('Z').unwrap();
+
+        assert_eq!(report.serialize(), [0x02, 0x00, 0x1D, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn hid_information_round_trips() {
+        let info = HidInformation::default();
+
+        assert_eq!(info.serialize(), [0x11, 0x01, 0x00, 0x02]);
+        assert_eq!(HidInformation::deserialize(&info.serialize()), Some(info));
+        assert_eq!(HidInformation::deserialize(&[0x11, 0x01, 0x00]), None);
+    }
+}
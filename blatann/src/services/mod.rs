@@ -0,0 +1,6 @@
+pub mod battery;
+pub mod current_time;
+pub mod device_info;
+pub mod heart_rate;
+pub mod hid;
+pub mod nordic_uart;
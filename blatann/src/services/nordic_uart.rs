@@ -0,0 +1,372 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use blatann_event::{
+    EventWaitable, Priority, Subscribable, Subscriber, SubscriberResult, Waitable,
+};
+
+use nrf_driver::error::{NrfError, NrfErrorType, NrfResult};
+
+use crate::ble_uuid::BleUuid;
+use crate::consts::ATT_VALUE_MAX_SIZE;
+use crate::events::{
+    CharacteristicWrittenEvent, ConnectionEvent, DisconnectionEvent, NotificationReceivedEvent,
+    NotificationsSentEvent, SubscriptionWriteCompleteEvent,
+};
+use crate::gatt::gattc::GattcCharacteristic;
+use crate::gatt::gatts::{GattsCharacteristic, GattsDatabase, GattsService};
+use crate::gatt::stream_writer::StreamWriter;
+use crate::gatt::{CharacteristicParams, CharacteristicProperties, NotificationType};
+use crate::peer::Peer;
+
+pub const NUS_BASE_UUID: uuid::Uuid = uuid::Uuid::from_u128(0x6E400000_B5A3_F393_E0A9_E50E24DCCA9E);
+pub const NUS_SERVICE_UUID: BleUuid = BleUuid::Vendor {
+    base: NUS_BASE_UUID,
+    offset: 0x0001,
+};
+// Client to server
+pub const NUS_RX_UUID: BleUuid = BleUuid::Vendor {
+    base: NUS_BASE_UUID,
+    offset: 0x0002,
+};
+// Server to client
+pub const NUS_TX_UUID: BleUuid = BleUuid::Vendor {
+    base: NUS_BASE_UUID,
+    offset: 0x0003,
+};
+
+// ATT notification header: opcode (1), handle (2)
+const NOTIFICATION_OVERHEAD: u16 = 3;
+
+struct RxBuffer {
+    data: VecDeque<u8>,
+    closed: bool,
+}
+
+enum Transport {
+    Server {
+        tx: Arc<GattsCharacteristic>,
+        peer: Arc<Peer>,
+        // Bumped on every notification tx complete, lets writers wait for room in the queue
+        tx_completions: Mutex<u64>,
+        tx_ready: Condvar,
+    },
+    Client {
+        writer: Arc<StreamWriter>,
+    },
+}
+
+// Byte stream over the NUS characteristics. Reads and writes block, so they must not be
+// called from an event handler
+pub struct NusStream {
+    transport: Transport,
+    rx: Mutex<RxBuffer>,
+    rx_ready: Condvar,
+    read_timeout: Mutex<Option<Duration>>,
+}
+
+impl NusStream {
+    fn new(transport: Transport) -> Arc<Self> {
+        Arc::new(Self {
+            transport,
+            rx: Mutex::new(RxBuffer {
+                data: VecDeque::new(),
+                closed: false,
+            }),
+            rx_ready: Condvar::new(),
+            read_timeout: Mutex::new(None),
+        })
+    }
+
+    // Reads block until data is received, the peer disconnects or the timeout expires
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) {
+        *self.read_timeout.lock().unwrap() = timeout;
+    }
+
+    pub fn bytes_available(&self) -> usize {
+        self.rx.lock().unwrap().data.len()
+    }
+
+    fn received(&self, data: &[u8]) {
+        let mut rx = self.rx.lock().unwrap();
+        rx.data.extend(data);
+        self.rx_ready.notify_all();
+    }
+
+    fn set_closed(&self, closed: bool) {
+        let mut rx = self.rx.lock().unwrap();
+        rx.closed = closed;
+        if !closed {
+            rx.data.clear();
+        }
+        self.rx_ready.notify_all();
+
+        if let Transport::Server { tx_ready, .. } = &self.transport {
+            tx_ready.notify_all();
+        }
+    }
+
+    fn notify_chunk(
+        &self,
+        tx: &GattsCharacteristic,
+        peer: &Peer,
+        tx_completions: &Mutex<u64>,
+        tx_ready: &Condvar,
+        buf: &[u8],
+    ) -> io::Result<usize> {
+        let chunk_size = (peer.mtu_size() - NOTIFICATION_OVERHEAD) as usize;
+        let chunk = &buf[..buf.len().min(chunk_size)];
+
+        loop {
+            if self.rx.lock().unwrap().closed || tx.subscription().is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "Client is not subscribed to the TX characteristic",
+                ));
+            }
+
+            let completions = *tx_completions.lock().unwrap();
+            match tx.notify(chunk) {
+                Ok(_) => return Ok(chunk.len()),
+                Err(e) if matches!(e.error_type, NrfErrorType::Resources) => {
+                    // Queue is full, wait for a notification to go out
+                    let guard = tx_completions.lock().unwrap();
+                    let _ = tx_ready
+                        .wait_timeout_while(guard, Duration::from_secs(1), |c| *c == completions)
+                        .unwrap();
+                }
+                Err(e) => return Err(to_io_error(e)),
+            }
+        }
+    }
+}
+
+fn to_io_error(err: NrfError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}", err))
+}
+
+impl io::Read for &NusStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = *self.read_timeout.lock().unwrap();
+        let mut rx = self.rx.lock().unwrap();
+
+        loop {
+            if !rx.data.is_empty() {
+                let len = buf.len().min(rx.data.len());
+                for (dst, src) in buf.iter_mut().zip(rx.data.drain(..len)) {
+                    *dst = src;
+                }
+                return Ok(len);
+            }
+            if rx.closed {
+                return Ok(0);
+            }
+
+            rx = match timeout {
+                Some(timeout) => {
+                    let (rx, result) = self.rx_ready.wait_timeout(rx, timeout).unwrap();
+                    if result.timed_out() && rx.data.is_empty() && !rx.closed {
+                        return Err(io::ErrorKind::TimedOut.into());
+                    }
+                    rx
+                }
+                None => self.rx_ready.wait(rx).unwrap(),
+            };
+        }
+    }
+}
+
+impl io::Write for &NusStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        match &self.transport {
+            Transport::Server {
+                tx,
+                peer,
+                tx_completions,
+                tx_ready,
+            } => self.notify_chunk(tx, peer, tx_completions, tx_ready, buf),
+            Transport::Client { writer } => {
+                // The stream writer chunks to the MTU and keeps the write command queue full
+                let (_, event) = writer
+                    .write(buf)
+                    .map_err(to_io_error)?
+                    .wait()
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                event
+                    .result
+                    .map(|_| buf.len())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Read for NusStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl io::Write for NusStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl Subscriber<GattsCharacteristic, CharacteristicWrittenEvent> for NusStream {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattsCharacteristic>,
+        event: CharacteristicWrittenEvent,
+    ) -> SubscriberResult {
+        self.received(&event.value);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattcCharacteristic, NotificationReceivedEvent> for NusStream {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattcCharacteristic>,
+        event: NotificationReceivedEvent,
+    ) -> SubscriberResult {
+        self.received(&event.data);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<GattsDatabase, NotificationsSentEvent> for NusStream {
+    fn handle(
+        self: Arc<Self>,
+        _sender: Arc<GattsDatabase>,
+        _event: NotificationsSentEvent,
+    ) -> SubscriberResult {
+        if let Transport::Server {
+            tx_completions,
+            tx_ready,
+            ..
+        } = &self.transport
+        {
+            *tx_completions.lock().unwrap() += 1;
+            tx_ready.notify_all();
+        }
+        return Ok(None);
+    }
+}
+
+impl Subscriber<Peer, ConnectionEvent> for NusStream {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: ConnectionEvent) -> SubscriberResult {
+        self.set_closed(false);
+        return Ok(None);
+    }
+}
+
+impl Subscriber<Peer, DisconnectionEvent> for NusStream {
+    fn handle(self: Arc<Self>, _sender: Arc<Peer>, _event: DisconnectionEvent) -> SubscriberResult {
+        self.set_closed(true);
+        return Ok(None);
+    }
+}
+
+pub struct NusServer {
+    pub service: Arc<GattsService>,
+    stream: Arc<NusStream>,
+}
+
+impl NusServer {
+    pub fn add_to_database(database: &Arc<GattsDatabase>) -> NrfResult<Arc<Self>> {
+        let service = database.add_service(NUS_SERVICE_UUID)?;
+
+        let rx_params = CharacteristicParams::new(
+            NUS_RX_UUID,
+            CharacteristicProperties::WRITE | CharacteristicProperties::WRITE_WITHOUT_RESPONSE,
+            ATT_VALUE_MAX_SIZE as u16,
+        );
+        let rx = service.add_characteristic(&rx_params)?;
+
+        let tx_params = CharacteristicParams::new(
+            NUS_TX_UUID,
+            CharacteristicProperties::NOTIFY,
+            ATT_VALUE_MAX_SIZE as u16,
+        );
+        let tx = service.add_characteristic(&tx_params)?;
+
+        let peer = database.peer().clone();
+        let stream = NusStream::new(Transport::Server {
+            tx,
+            peer: peer.clone(),
+            tx_completions: Mutex::new(0),
+            tx_ready: Condvar::new(),
+        });
+
+        rx.on_write
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+        database
+            .on_notifications_sent
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+        peer.on_connect
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+        peer.on_disconnect
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+
+        return Ok(Arc::new(Self { service, stream }));
+    }
+
+    pub fn stream(&self) -> &Arc<NusStream> {
+        &self.stream
+    }
+}
+
+pub struct NusClient {
+    tx: Arc<GattcCharacteristic>,
+    stream: Arc<NusStream>,
+}
+
+impl NusClient {
+    // Finds the NUS within the peer's discovered database
+    pub fn find(peer: &Arc<Peer>) -> Option<Arc<Self>> {
+        let database = peer.database();
+        let service = database.find_service(&NUS_SERVICE_UUID)?;
+        let rx = service.find_characteristic(&NUS_RX_UUID)?;
+        let tx = service.find_characteristic(&NUS_TX_UUID)?;
+
+        let stream = NusStream::new(Transport::Client {
+            writer: StreamWriter::new(rx),
+        });
+
+        tx.on_notification_received
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+        peer.on_disconnect
+            .subscribe_with_priority(stream.clone(), Priority::Internal);
+
+        return Some(Arc::new(Self {
+            tx: tx.clone(),
+            stream,
+        }));
+    }
+
+    // Received data is only buffered once the TX notifications are enabled
+    pub fn subscribe(
+        &self,
+    ) -> NrfResult<Arc<EventWaitable<GattcCharacteristic, SubscriptionWriteCompleteEvent>>> {
+        self.tx.subscribe(NotificationType::Notification)
+    }
+
+    pub fn stream(&self) -> &Arc<NusStream> {
+        &self.stream
+    }
+}
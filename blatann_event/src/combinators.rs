@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::completion::Completion;
+use crate::{executor, AsyncEventHandler, TimeoutError, Waitable};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+// Completes with whichever of the two waitables finishes first
+pub fn select<A, B, TA, TB>(a: &A, b: &B) -> Arc<Completion<Either<TA, TB>>>
+where
+    A: AsyncEventHandler<TA> + ?Sized,
+    B: AsyncEventHandler<TB> + ?Sized,
+    TA: Clone + Send + 'static,
+    TB: Clone + Send + 'static,
+{
+    let completion = Completion::new();
+    let c = completion.clone();
+    a.then(move |result| {
+        c.complete(Either::Left(result));
+    });
+    let c = completion.clone();
+    b.then(move |result| {
+        c.complete(Either::Right(result));
+    });
+    completion
+}
+
+// Completes with the index and result of the first waitable to finish
+pub fn any<W, T>(waitables: &[Arc<W>]) -> Arc<Completion<(usize, T)>>
+where
+    W: AsyncEventHandler<T> + ?Sized,
+    T: Clone + Send + 'static,
+{
+    let completion = Completion::new();
+    for (i, waitable) in waitables.iter().enumerate() {
+        let c = completion.clone();
+        waitable.then(move |result| {
+            c.complete((i, result));
+        });
+    }
+    completion
+}
+
+// Completes once every waitable has finished, results are in the same order as the input
+pub fn all<W, T>(waitables: &[Arc<W>]) -> Arc<Completion<Vec<T>>>
+where
+    W: AsyncEventHandler<T> + ?Sized,
+    T: Clone + Send + 'static,
+{
+    let completion = Completion::new();
+    if waitables.is_empty() {
+        completion.complete(vec![]);
+        return completion;
+    }
+
+    let results: Arc<Mutex<Vec<Option<T>>>> = Arc::new(Mutex::new(vec![None; waitables.len()]));
+    for (i, waitable) in waitables.iter().enumerate() {
+        let c = completion.clone();
+        let results = results.clone();
+        waitable.then(move |result| {
+            let mut results = results.lock().unwrap();
+            results[i] = Some(result);
+            if results.iter().all(|r| r.is_some()) {
+                let results = results.drain(..).map(|r| r.unwrap()).collect();
+                c.complete(results);
+            }
+        });
+    }
+    completion
+}
+
+pub trait WaitableExt<T: Clone + Send + 'static>: AsyncEventHandler<T> {
+    fn map<U, F>(&self, f: F) -> Arc<Completion<U>>
+    where
+        U: Clone + Send + 'static,
+        F: 'static + Send + FnOnce(T) -> U,
+    {
+        let completion = Completion::new();
+        let c = completion.clone();
+        self.then(move |result| {
+            c.complete(f(result));
+        });
+        completion
+    }
+
+    // Starts the next operation once this one completes, e.g. waiting for a connection and
+    // then for the peer to disconnect
+    fn and_then<U, W, F>(&self, f: F) -> Arc<Completion<U>>
+    where
+        U: Clone + Send + 'static,
+        W: AsyncEventHandler<U> + ?Sized,
+        F: 'static + Send + FnOnce(T) -> Arc<W>,
+    {
+        let completion = Completion::new();
+        let c = completion.clone();
+        self.then(move |result| {
+            f(result).then(move |result| {
+                c.complete(result);
+            });
+        });
+        completion
+    }
+
+    // Completes with Err if the waitable hasn't finished within the timeout
+    fn with_timeout(&self, timeout: Duration) -> Arc<Completion<Result<T, TimeoutError>>> {
+        let completion = Completion::new();
+        let c = completion.clone();
+        self.then(move |result| {
+            c.complete(Ok(result));
+        });
+
+        if !completion.is_complete() {
+            let c = completion.clone();
+            executor::spawn(move || {
+                if c.wait_timeout(timeout).is_err() {
+                    c.complete(Err(TimeoutError { timeout }));
+                }
+            });
+        }
+        completion
+    }
+}
+
+impl<T: Clone + Send + 'static, W: AsyncEventHandler<T> + ?Sized> WaitableExt<T> for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_completes_with_the_first() {
+        let a: Arc<Completion<u32>> = Completion::new();
+        let b: Arc<Completion<&str>> = Completion::new();
+        let selected = select(&*a, &*b);
+
+        b.complete("b");
+        a.complete(1);
+
+        assert_eq!(selected.result(), Some(Either::Right("b")));
+    }
+
+    #[test]
+    fn any_completes_with_the_index_of_the_first() {
+        let waitables: Vec<Arc<Completion<u32>>> = (0..3).map(|_| Completion::new()).collect();
+        let first = any(&waitables);
+
+        waitables[2].complete(20);
+        waitables[0].complete(0);
+
+        assert_eq!(first.result(), Some((2, 20)));
+    }
+
+    #[test]
+    fn all_keeps_the_input_order() {
+        let waitables: Vec<Arc<Completion<u32>>> = (0..3).map(|_| Completion::new()).collect();
+        let every = all(&waitables);
+
+        waitables[2].complete(2);
+        waitables[0].complete(0);
+        assert!(!every.is_complete());
+        waitables[1].complete(1);
+
+        assert_eq!(every.result(), Some(vec![0, 1, 2]));
+        let none: Vec<Arc<Completion<u32>>> = vec![];
+        assert_eq!(all(&none).result(), Some(vec![]));
+    }
+
+    #[test]
+    fn map_and_and_then_chain_results() {
+        let first: Arc<Completion<u32>> = Completion::new();
+        let second: Arc<Completion<u32>> = Completion::new();
+        let next = second.clone();
+        let chained = first.map(|r| r + 1).and_then(move |r| {
+            next.complete(r * 10);
+            next
+        });
+
+        first.complete(1);
+
+        assert_eq!(chained.result(), Some(20));
+    }
+
+    #[test]
+    fn with_timeout_fails_if_not_completed_in_time() {
+        let late: Arc<Completion<u32>> = Completion::new();
+        let timeout = Duration::from_millis(10);
+        let timed = late.with_timeout(timeout);
+
+        assert_eq!(timed.wait(), Ok(Err(TimeoutError { timeout })));
+        late.complete(1);
+        assert_eq!(timed.result(), Some(Err(TimeoutError { timeout })));
+
+        let done: Arc<Completion<u32>> = Completion::new();
+        done.complete(1);
+        assert_eq!(done.with_timeout(timeout).result(), Some(Ok(1)));
+    }
+}
@@ -0,0 +1,210 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::RecvError;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::{AsyncEventHandler, TimeoutError, Waitable};
+
+struct State<T> {
+    result: Option<T>,
+    callbacks: Vec<Box<dyn FnOnce(T) + Send>>,
+    wakers: Vec<Waker>,
+}
+
+// One-shot result cell backing the waitables. The first result is kept, so waiting or
+// registering a callback after completion returns immediately
+pub struct Completion<T> {
+    state: Mutex<State<T>>,
+    completed: Condvar,
+}
+
+impl<T: Clone + Send + 'static> Completion<T> {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(State {
+                result: None,
+                callbacks: vec![],
+                wakers: vec![],
+            }),
+            completed: Condvar::new(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap()
+    }
+
+    // Returns false if the completion already had a result, the new one is discarded
+    pub fn complete(&self, result: T) -> bool {
+        let mut state = self.lock();
+        if state.result.is_some() {
+            return false;
+        }
+        state.result = Some(result.clone());
+        let callbacks: Vec<_> = state.callbacks.drain(..).collect();
+        let wakers: Vec<_> = state.wakers.drain(..).collect();
+        drop(state);
+
+        self.completed.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+        for cb in callbacks {
+            (cb)(result.clone());
+        }
+        return true;
+    }
+
+    pub fn result(&self) -> Option<T> {
+        self.lock().result.clone()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.lock().result.is_some()
+    }
+
+    pub fn poll_result(&self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.lock();
+        match &state.result {
+            Some(result) => Poll::Ready(result.clone()),
+            None => {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Waitable<T> for Completion<T> {
+    fn wait_timeout(&self, timeout: Duration) -> Result<T, TimeoutError> {
+        let state = self.lock();
+        let (state, _) = self
+            .completed
+            .wait_timeout_while(state, timeout, |s| s.result.is_none())
+            .unwrap();
+        state.result.clone().ok_or(TimeoutError { timeout })
+    }
+
+    fn wait(&self) -> Result<T, RecvError> {
+        let state = self.lock();
+        let state = self
+            .completed
+            .wait_while(state, |s| s.result.is_none())
+            .unwrap();
+        state.result.clone().ok_or(RecvError)
+    }
+}
+
+impl<T: Clone + Send + 'static> AsyncEventHandler<T> for Completion<T> {
+    fn then<F>(&self, f: F)
+    where
+        F: 'static + Send + FnOnce(T),
+    {
+        let mut state = self.lock();
+        match state.result.clone() {
+            Some(result) => {
+                // Run outside the lock so the callback can use the completion
+                drop(state);
+                f(result);
+            }
+            None => state.callbacks.push(Box::new(f)),
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Future for Completion<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_result(cx)
+    }
+}
+
+// Completions are handed out in an Arc, so they're awaited by reference
+impl<T: Clone + Send + 'static> Future for &Completion<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_result(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn keeps_the_first_result() {
+        let completion = Completion::new();
+
+        assert!(completion.complete(1));
+        assert!(!completion.complete(2));
+        assert_eq!(completion.result(), Some(1));
+    }
+
+    #[test]
+    fn callbacks_run_whether_registered_before_or_after_completion() {
+        let completion = Completion::new();
+        let results = Arc::new(Mutex::new(vec![]));
+        let before = results.clone();
+        completion.then(move |r| before.lock().unwrap().push(r));
+
+        completion.complete(1);
+        let after = results.clone();
+        completion.then(move |r| after.lock().unwrap().push(r + 1));
+
+        assert_eq!(*results.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn wait_returns_the_result_from_another_thread() {
+        let completion = Completion::new();
+        let c = completion.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            c.complete(1);
+        });
+
+        assert_eq!(completion.wait(), Ok(1));
+    }
+
+    #[test]
+    fn wait_timeout_times_out_without_a_result() {
+        let completion: Arc<Completion<u32>> = Completion::new();
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(
+            completion.wait_timeout(timeout),
+            Err(TimeoutError { timeout })
+        );
+    }
+
+    #[test]
+    fn polling_wakes_on_completion() {
+        let completion = Completion::new();
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = flag.clone().into();
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(completion.poll_result(&mut cx).is_pending());
+        completion.complete(1);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert_eq!(completion.poll_result(&mut cx), Poll::Ready(1));
+    }
+}
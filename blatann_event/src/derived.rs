@@ -0,0 +1,361 @@
+use std::any::Any;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::publisher::Publisher;
+use crate::{
+    executor, Priority, Subscribable, Subscriber, SubscriberAction, SubscriberResult,
+    Unsubscribable,
+};
+
+struct Operator<S, U: Clone, F> {
+    publisher: Arc<Publisher<S, U>>,
+    op: F,
+    // Keeps a chain of derived publishers alive while the last one is in use
+    _upstream: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl<S, E, U, F> Subscriber<S, E> for Operator<S, U, F>
+where
+    S: Send + Sync,
+    U: Clone + Send,
+    F: Fn(&Arc<Publisher<S, U>>, Arc<S>, E) -> Option<SubscriberAction> + Send + Sync,
+{
+    fn handle(self: Arc<Self>, sender: Arc<S>, event: E) -> SubscriberResult {
+        Ok((self.op)(&self.publisher, sender, event))
+    }
+}
+
+// Publisher fed from another publisher through an operator. The upstream only holds a weak
+// reference, so dropping every clone of the derived publisher unsubscribes it
+pub struct DerivedPublisher<S, E: Clone> {
+    publisher: Arc<Publisher<S, E>>,
+    node: Arc<dyn Any + Send + Sync>,
+}
+
+impl<S, E: Clone> Clone for DerivedPublisher<S, E> {
+    fn clone(&self) -> Self {
+        Self {
+            publisher: self.publisher.clone(),
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<S, E: Clone> Deref for DerivedPublisher<S, E> {
+    type Target = Publisher<S, E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.publisher
+    }
+}
+
+impl<S, E: Clone> Subscribable<S, E> for DerivedPublisher<S, E> {
+    fn name(&self) -> &str {
+        self.publisher.name()
+    }
+
+    fn subscribe(&self, subscriber: Arc<dyn Subscriber<S, E>>) -> Uuid {
+        self.publisher.subscribe(subscriber)
+    }
+
+    fn subscribe_once(&self, subscriber: Arc<dyn Subscriber<S, E>>) -> Uuid {
+        self.publisher.subscribe_once(subscriber)
+    }
+
+    fn subscribe_with_priority(
+        &self,
+        subscriber: Arc<dyn Subscriber<S, E>>,
+        priority: Priority,
+    ) -> Uuid {
+        self.publisher.subscribe_with_priority(subscriber, priority)
+    }
+
+    fn keep_alive(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        Some(self.node.clone())
+    }
+}
+
+impl<S, E: Clone> Unsubscribable for DerivedPublisher<S, E> {
+    fn unsubscribe(&self, id: Uuid) {
+        self.publisher.unsubscribe(id)
+    }
+}
+
+fn derive<S, E, U, T, F>(source: &T, name: &str, op: F) -> DerivedPublisher<S, U>
+where
+    S: Send + Sync + 'static,
+    E: Clone + 'static,
+    U: Clone + Send + 'static,
+    T: Subscribable<S, E> + ?Sized,
+    F: Fn(&Arc<Publisher<S, U>>, Arc<S>, E) -> Option<SubscriberAction> + Send + Sync + 'static,
+{
+    derive_with_priority(source, name, Priority::Normal, op)
+}
+
+// The operator subscribes upstream at the given priority, which decides when it runs relative to
+// the source's other subscribers
+fn derive_with_priority<S, E, U, T, F>(
+    source: &T,
+    name: &str,
+    priority: Priority,
+    op: F,
+) -> DerivedPublisher<S, U>
+where
+    S: Send + Sync + 'static,
+    E: Clone + 'static,
+    U: Clone + Send + 'static,
+    T: Subscribable<S, E> + ?Sized,
+    F: Fn(&Arc<Publisher<S, U>>, Arc<S>, E) -> Option<SubscriberAction> + Send + Sync + 'static,
+{
+    let publisher = Arc::new(Publisher::new(&format!("{} ({})", source.name(), name)));
+    let node = Arc::new(Operator {
+        publisher: publisher.clone(),
+        op,
+        _upstream: source.keep_alive(),
+    });
+    source.subscribe_with_priority(node.clone(), priority);
+
+    DerivedPublisher { publisher, node }
+}
+
+struct Debounce<S, E> {
+    pending: Option<(Arc<S>, E)>,
+    deadline: Instant,
+    timer_running: bool,
+}
+
+fn run_debounce_timer<S: 'static, E: Clone + 'static>(
+    state: Arc<Mutex<Debounce<S, E>>>,
+    publisher: Weak<Publisher<S, E>>,
+) {
+    loop {
+        let mut debounce = state.lock().unwrap();
+        let now = Instant::now();
+        if now < debounce.deadline {
+            let remaining = debounce.deadline - now;
+            drop(debounce);
+            thread::sleep(remaining);
+            continue;
+        }
+
+        debounce.timer_running = false;
+        let pending = debounce.pending.take();
+        drop(debounce);
+
+        if let (Some((sender, event)), Some(publisher)) = (pending, publisher.upgrade()) {
+            publisher.dispatch(sender, event);
+        }
+        return;
+    }
+}
+
+pub trait SubscribableExt<S: Send + Sync + 'static, E: Clone + Send + 'static>:
+    Subscribable<S, E>
+{
+    fn filter<P>(&self, predicate: P) -> DerivedPublisher<S, E>
+    where
+        P: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.filter_with_priority(predicate, Priority::Normal)
+    }
+
+    // Library objects filter at Priority::Internal so their handlers still run ahead of the user's
+    fn filter_with_priority<P>(&self, predicate: P, priority: Priority) -> DerivedPublisher<S, E>
+    where
+        P: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        derive_with_priority(self, "filter", priority, move |publisher, sender, event| {
+            if predicate(&event) {
+                publisher.dispatch(sender, event);
+            }
+            return None;
+        })
+    }
+
+    fn map<U, F>(&self, f: F) -> DerivedPublisher<S, U>
+    where
+        U: Clone + Send + 'static,
+        F: Fn(E) -> U + Send + Sync + 'static,
+    {
+        derive(self, "map", move |publisher, sender, event| {
+            publisher.dispatch(sender, f(event));
+            return None;
+        })
+    }
+
+    // Forwards an event once no newer one has arrived for the duration, from a timer thread
+    fn debounce(&self, duration: Duration) -> DerivedPublisher<S, E> {
+        let state = Arc::new(Mutex::new(Debounce {
+            pending: None,
+            deadline: Instant::now(),
+            timer_running: false,
+        }));
+        derive(self, "debounce", move |publisher, sender, event| {
+            let mut debounce = state.lock().unwrap();
+            debounce.pending = Some((sender, event));
+            debounce.deadline = Instant::now() + duration;
+            if !debounce.timer_running {
+                debounce.timer_running = true;
+                let state = state.clone();
+                let publisher = Arc::downgrade(publisher);
+                executor::spawn(move || run_debounce_timer(state, publisher));
+            }
+            return None;
+        })
+    }
+
+    // Forwards at most one event per duration, events in between are dropped
+    fn throttle(&self, duration: Duration) -> DerivedPublisher<S, E> {
+        let last_dispatch: Mutex<Option<Instant>> = Mutex::new(None);
+        derive(self, "throttle", move |publisher, sender, event| {
+            let now = Instant::now();
+            {
+                let mut last_dispatch = last_dispatch.lock().unwrap();
+                if let Some(last) = *last_dispatch {
+                    if now.duration_since(last) < duration {
+                        return None;
+                    }
+                }
+                *last_dispatch = Some(now);
+            }
+            publisher.dispatch(sender, event);
+            return None;
+        })
+    }
+
+    // Forwards the first n events, then unsubscribes from the upstream publisher
+    fn take(&self, n: usize) -> DerivedPublisher<S, E> {
+        let remaining = Mutex::new(n);
+        derive(self, "take", move |publisher, sender, event| {
+            let last = {
+                let mut remaining = remaining.lock().unwrap();
+                if *remaining == 0 {
+                    return Some(SubscriberAction::Unsubscribe);
+                }
+                *remaining -= 1;
+                *remaining == 0
+            };
+            publisher.dispatch(sender, event);
+
+            if last {
+                return Some(SubscriberAction::Unsubscribe);
+            }
+            return None;
+        })
+    }
+}
+
+impl<S, E, T> SubscribableExt<S, E> for T
+where
+    S: Send + Sync + 'static,
+    E: Clone + Send + 'static,
+    T: Subscribable<S, E> + ?Sized,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(derived: &DerivedPublisher<(), u32>) -> (crate::Subscription, Arc<Mutex<Vec<u32>>>) {
+        let events = Arc::new(Mutex::new(vec![]));
+        let handler_events = events.clone();
+        let subscription = derived.subscribe_fn(move |_, event| {
+            handler_events.lock().unwrap().push(event);
+        });
+        (subscription, events)
+    }
+
+    fn dispatch_all(publisher: &Publisher<(), u32>, events: &[u32]) {
+        for event in events {
+            publisher.dispatch(Arc::new(()), *event);
+        }
+    }
+
+    #[test]
+    fn filter_and_map_chain_through_dropped_intermediates() {
+        let publisher = Publisher::new("test");
+        let doubled_evens = publisher.filter(|e| e % 2 == 0).map(|e| e * 2);
+        let (_subscription, events) = collect(&doubled_evens);
+
+        dispatch_all(&publisher, &[1, 2, 3, 4]);
+
+        assert_eq!(*events.lock().unwrap(), [4, 8]);
+    }
+
+    #[test]
+    fn dropping_the_derived_publisher_unsubscribes_it() {
+        let publisher = Publisher::new("test");
+        let calls = Arc::new(Mutex::new(0));
+        let predicate_calls = calls.clone();
+        let filtered = publisher.filter(move |_| {
+            *predicate_calls.lock().unwrap() += 1;
+            true
+        });
+        dispatch_all(&publisher, &[1]);
+
+        drop(filtered);
+        dispatch_all(&publisher, &[2]);
+
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn take_forwards_n_events_then_stops() {
+        let publisher = Publisher::new("test");
+        let first_two = publisher.take(2);
+        let (_subscription, events) = collect(&first_two);
+
+        dispatch_all(&publisher, &[1, 2, 3]);
+
+        assert_eq!(*events.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn filter_with_priority_runs_ahead_of_normal_subscribers() {
+        let publisher = Publisher::new("test");
+        let order = Arc::new(Mutex::new(vec![]));
+        let normal_order = order.clone();
+        let _normal =
+            publisher.subscribe_fn(move |_, _| normal_order.lock().unwrap().push("normal"));
+        let internal = publisher.filter_with_priority(|_| true, Priority::Internal);
+        let internal_order = order.clone();
+        let _internal =
+            internal.subscribe_fn(move |_, _| internal_order.lock().unwrap().push("internal"));
+
+        dispatch_all(&publisher, &[1]);
+
+        assert_eq!(*order.lock().unwrap(), ["internal", "normal"]);
+    }
+
+    #[test]
+    fn debounce_forwards_the_last_event_of_a_burst() {
+        let publisher = Publisher::new("test");
+        let debounced = publisher.debounce(Duration::from_millis(30));
+        let (_subscription, events) = collect(&debounced);
+
+        dispatch_all(&publisher, &[1, 2, 3]);
+        assert!(events.lock().unwrap().is_empty());
+
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*events.lock().unwrap(), [3]);
+    }
+
+    #[test]
+    fn throttle_drops_events_within_the_duration() {
+        let publisher = Publisher::new("test");
+        let throttled = publisher.throttle(Duration::from_millis(30));
+        let (_subscription, events) = collect(&throttled);
+
+        dispatch_all(&publisher, &[1, 2]);
+        thread::sleep(Duration::from_millis(50));
+        dispatch_all(&publisher, &[3]);
+
+        assert_eq!(*events.lock().unwrap(), [1, 3]);
+    }
+}
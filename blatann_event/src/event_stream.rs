@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::subscription::Subscription;
+use crate::{EventArgs, Subscriber, SubscriberAction, SubscriberResult};
+
+// What to do with a new event when the consumer has fallen behind and the buffer is full
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OverflowPolicy {
+    DropOldest,
+    DropNewest,
+    // Blocks the dispatching thread until there's room, use with care on driver events
+    Block,
+}
+
+struct Queue<S, E> {
+    events: VecDeque<EventArgs<Arc<S>, E>>,
+    // The stream was dropped
+    closed: bool,
+    // The publisher was dropped, no more events will arrive
+    ended: bool,
+    dropped: u64,
+    waker: Option<Waker>,
+}
+
+pub(crate) struct StreamSubscriber<S, E> {
+    queue: Mutex<Queue<S, E>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl<S, E> StreamSubscriber<S, E> {
+    pub(crate) fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Arc<Self> {
+        Arc::new(Self {
+            queue: Mutex::new(Queue {
+                events: VecDeque::new(),
+                closed: false,
+                ended: false,
+                dropped: 0,
+                waker: None,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: capacity.max(1),
+            overflow_policy,
+        })
+    }
+
+    // Called once the publisher is gone, the stream finishes after the buffered events
+    pub(crate) fn end(&self) {
+        let mut queue = self.lock();
+        queue.ended = true;
+        self.not_empty.notify_all();
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue<S, E>> {
+        self.queue.lock().unwrap()
+    }
+
+    fn pop(&self, queue: &mut Queue<S, E>) -> Option<EventArgs<Arc<S>, E>> {
+        let args = queue.events.pop_front();
+        if args.is_some() {
+            self.not_full.notify_one();
+        }
+        args
+    }
+}
+
+impl<S: Send + Sync, E: Clone + Send> Subscriber<S, E> for StreamSubscriber<S, E> {
+    fn handle(self: Arc<Self>, sender: Arc<S>, event: E) -> SubscriberResult {
+        let mut queue = self.lock();
+        if queue.closed {
+            return Ok(Some(SubscriberAction::Unsubscribe));
+        }
+
+        if queue.events.len() >= self.capacity {
+            match self.overflow_policy {
+                OverflowPolicy::DropOldest => {
+                    queue.events.pop_front();
+                    queue.dropped += 1;
+                }
+                OverflowPolicy::DropNewest => {
+                    queue.dropped += 1;
+                    return Ok(None);
+                }
+                OverflowPolicy::Block => {
+                    queue = self
+                        .not_full
+                        .wait_while(queue, |q| q.events.len() >= self.capacity && !q.closed)
+                        .unwrap();
+                    if queue.closed {
+                        return Ok(Some(SubscriberAction::Unsubscribe));
+                    }
+                }
+            }
+        }
+
+        queue.events.push_back((sender, event));
+        self.not_empty.notify_one();
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+        return Ok(None);
+    }
+}
+
+// Buffered subscription to a publisher. Events are yielded in order through Iterator (blocking)
+// or Stream, which both end once the publisher is dropped. Dropping the stream unsubscribes it
+pub struct EventStream<S, E> {
+    subscriber: Arc<StreamSubscriber<S, E>>,
+    // Dropped after the queue is closed
+    _subscription: Subscription,
+}
+
+impl<S, E> EventStream<S, E> {
+    pub(crate) fn new(subscriber: Arc<StreamSubscriber<S, E>>, subscription: Subscription) -> Self {
+        Self {
+            subscriber,
+            _subscription: subscription,
+        }
+    }
+
+    pub fn try_next(&self) -> Option<EventArgs<Arc<S>, E>> {
+        let mut queue = self.subscriber.lock();
+        self.subscriber.pop(&mut queue)
+    }
+
+    pub fn next_timeout(&self, timeout: Duration) -> Option<EventArgs<Arc<S>, E>> {
+        let queue = self.subscriber.lock();
+        let (mut queue, _) = self
+            .subscriber
+            .not_empty
+            .wait_timeout_while(queue, timeout, |q| q.events.is_empty() && !q.ended)
+            .unwrap();
+        self.subscriber.pop(&mut queue)
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriber.lock().events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Number of events discarded by the overflow policy
+    pub fn dropped(&self) -> u64 {
+        self.subscriber.lock().dropped
+    }
+}
+
+// Blocks until the next event, so must not be iterated from an event handler. Returns None once
+// the publisher has been dropped and the buffered events are consumed
+impl<S, E> Iterator for EventStream<S, E> {
+    type Item = EventArgs<Arc<S>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let queue = self.subscriber.lock();
+        let mut queue = self
+            .subscriber
+            .not_empty
+            .wait_while(queue, |q| q.events.is_empty() && !q.ended)
+            .unwrap();
+        self.subscriber.pop(&mut queue)
+    }
+}
+
+impl<S, E> futures_core::Stream for EventStream<S, E> {
+    type Item = EventArgs<Arc<S>, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut queue = self.subscriber.lock();
+        match self.subscriber.pop(&mut queue) {
+            Some(args) => Poll::Ready(Some(args)),
+            None if queue.ended => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<S, E> Drop for EventStream<S, E> {
+    fn drop(&mut self) {
+        // The closed flag releases a blocked dispatch, then the subscription is dropped
+        let mut queue = self.subscriber.lock();
+        queue.closed = true;
+        queue.events.clear();
+        self.subscriber.not_full.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    use futures_core::Stream;
+
+    use crate::publisher::Publisher;
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn dispatch_all(publisher: &Publisher<(), u32>, events: &[u32]) {
+        for event in events {
+            publisher.dispatch(Arc::new(()), *event);
+        }
+    }
+
+    fn drain(stream: &EventStream<(), u32>) -> Vec<u32> {
+        std::iter::from_fn(|| stream.try_next().map(|(_, e)| e)).collect()
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest_events() {
+        let publisher = Publisher::new("test");
+        let stream = publisher.stream(2, OverflowPolicy::DropOldest);
+        dispatch_all(&publisher, &[1, 2, 3, 4]);
+
+        assert_eq!(stream.dropped(), 2);
+        assert_eq!(drain(&stream), [3, 4]);
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_oldest_events() {
+        let publisher = Publisher::new("test");
+        let stream = publisher.stream(2, OverflowPolicy::DropNewest);
+        dispatch_all(&publisher, &[1, 2, 3, 4]);
+
+        assert_eq!(stream.dropped(), 2);
+        assert_eq!(drain(&stream), [1, 2]);
+    }
+
+    #[test]
+    fn block_waits_for_the_consumer() {
+        let publisher = Arc::new(Publisher::new("test"));
+        let stream = publisher.stream(1, OverflowPolicy::Block);
+        let dispatcher = {
+            let publisher = publisher.clone();
+            thread::spawn(move || dispatch_all(&publisher, &[1, 2, 3]))
+        };
+
+        let events: Vec<_> = stream.take(3).map(|(_, e)| e).collect();
+        dispatcher.join().unwrap();
+        assert_eq!(events, [1, 2, 3]);
+    }
+
+    #[test]
+    fn dropping_a_stream_releases_a_blocked_dispatch() {
+        let publisher = Arc::new(Publisher::new("test"));
+        let stream = publisher.stream(1, OverflowPolicy::Block);
+        let dispatcher = {
+            let publisher = publisher.clone();
+            thread::spawn(move || dispatch_all(&publisher, &[1, 2]))
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        drop(stream);
+        dispatcher.join().unwrap();
+        dispatch_all(&publisher, &[3]);
+    }
+
+    #[test]
+    fn next_timeout_returns_none_without_events() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let stream = publisher.stream(1, OverflowPolicy::DropOldest);
+
+        assert!(stream.next_timeout(Duration::from_millis(10)).is_none());
+        dispatch_all(&publisher, &[1]);
+        assert_eq!(
+            stream
+                .next_timeout(Duration::from_millis(10))
+                .map(|(_, e)| e),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn polling_wakes_on_events_and_ends_with_the_publisher() {
+        let publisher = Publisher::new("test");
+        let mut stream = publisher.stream(4, OverflowPolicy::DropOldest);
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = flag.clone().into();
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(Pin::new(&mut stream).poll_next(&mut cx).is_pending());
+        dispatch_all(&publisher, &[1]);
+        assert!(flag.0.load(Ordering::SeqCst));
+        match Pin::new(&mut stream).poll_next(&mut cx) {
+            Poll::Ready(Some((_, event))) => assert_eq!(event, 1),
+            _ => panic!("expected an event"),
+        }
+
+        drop(publisher);
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+}
@@ -1,65 +1,140 @@
-use std::sync::mpsc::{RecvError, RecvTimeoutError};
-use std::sync::{mpsc, Arc, Mutex};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::RecvError;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 
-use crate::{AsyncEventHandler, EventArgs, Subscribable, Subscriber, SubscriberAction, Waitable};
+use crate::completion::Completion;
+use crate::{
+    AsyncEventHandler, EventArgs, Subscribable, Subscriber, SubscriberAction, SubscriberResult,
+    TimeoutError, Waitable,
+};
 
-pub struct EventWaitable<S: 'static, E: Clone + 'static> {
-    sender: mpsc::Sender<EventArgs<Arc<S>, E>>,
-    receiver: mpsc::Receiver<EventArgs<Arc<S>, E>>,
-    callbacks: Mutex<Vec<Box<dyn FnOnce(EventArgs<Arc<S>, E>)>>>,
+pub struct EventWaitable<S: Send + Sync + 'static, E: Clone + Send + 'static> {
+    completion: Arc<Completion<EventArgs<Arc<S>, E>>>,
 }
 
-impl<S: 'static, E: Clone + 'static> EventWaitable<S, E> {
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> EventWaitable<S, E> {
     pub fn new(event: &dyn Subscribable<S, E>) -> Arc<Self> {
-        let (sender, receiver) = mpsc::channel();
         let waitable = Arc::new(Self {
-            sender,
-            receiver,
-            callbacks: Mutex::new(vec![]),
+            completion: Completion::new(),
         });
 
         event.subscribe(waitable.clone());
 
         return waitable;
     }
+
+    // The event, if it has already fired
+    pub fn result(&self) -> Option<EventArgs<Arc<S>, E>> {
+        self.completion.result()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completion.is_complete()
+    }
 }
 
-impl<S: 'static, E: Clone + 'static> Waitable<EventArgs<Arc<S>, E>> for EventWaitable<S, E> {
-    fn wait_timeout(&self, timeout: Duration) -> Result<EventArgs<Arc<S>, E>, RecvTimeoutError> {
-        self.receiver.recv_timeout(timeout)
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> Waitable<EventArgs<Arc<S>, E>>
+    for EventWaitable<S, E>
+{
+    fn wait_timeout(&self, timeout: Duration) -> Result<EventArgs<Arc<S>, E>, TimeoutError> {
+        self.completion.wait_timeout(timeout)
     }
 
     fn wait(&self) -> Result<EventArgs<Arc<S>, E>, RecvError> {
-        self.receiver.recv()
+        self.completion.wait()
     }
 }
 
-impl<S: 'static, E: Clone + 'static> AsyncEventHandler<EventArgs<Arc<S>, E>>
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> Future for EventWaitable<S, E> {
+    type Output = EventArgs<Arc<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.completion.poll_result(cx)
+    }
+}
+
+// Waitables are handed out in an Arc, so they're awaited by reference: `waitable.as_ref().await`
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> Future for &EventWaitable<S, E> {
+    type Output = EventArgs<Arc<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.completion.poll_result(cx)
+    }
+}
+
+// Waitables are awaited from spawned tasks, so they must stay Send
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<EventWaitable<(), ()>>();
+    assert_send::<&EventWaitable<(), ()>>();
+};
+
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> AsyncEventHandler<EventArgs<Arc<S>, E>>
     for EventWaitable<S, E>
 {
     fn then<F>(&self, f: F)
     where
-        F: 'static + FnOnce(EventArgs<Arc<S>, E>),
+        F: 'static + Send + FnOnce(EventArgs<Arc<S>, E>),
     {
-        let mut callbacks = self.callbacks.lock().unwrap();
-        callbacks.push(Box::new(f));
+        self.completion.then(f);
     }
 }
 
-impl<S: 'static, E: Clone + 'static> Subscriber<S, E> for EventWaitable<S, E> {
-    fn handle(self: Arc<Self>, sender: Arc<S>, event: E) -> Option<SubscriberAction> {
-        self.sender
-            .send((sender.clone(), event.clone()))
-            .unwrap_or_else(|e| {
-                error!("Failed to send waitable: {:?}", e);
-            });
+impl<S: Send + Sync + 'static, E: Clone + Send + 'static> Subscriber<S, E> for EventWaitable<S, E> {
+    fn handle(self: Arc<Self>, sender: Arc<S>, event: E) -> SubscriberResult {
+        self.completion.complete((sender, event));
+
         // Handled the event, unsubscribe
-        let mut callbacks = self.callbacks.lock().unwrap();
-        for cb in callbacks.drain(..) {
-            (cb)((sender.clone(), event.clone()));
-        }
+        return Ok(Some(SubscriberAction::Unsubscribe));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use crate::publisher::Publisher;
+
+    #[test]
+    fn completes_with_the_first_event_only() {
+        let publisher = Publisher::new("test");
+        let waitable = EventWaitable::new(&publisher);
+
+        publisher.dispatch(Arc::new(()), 1);
+        publisher.dispatch(Arc::new(()), 2);
+
+        assert_eq!(waitable.result().map(|(_, e)| e), Some(1));
+    }
+
+    #[test]
+    fn wait_returns_an_event_dispatched_from_another_thread() {
+        let publisher = Arc::new(Publisher::new("test"));
+        let waitable = EventWaitable::new(&*publisher);
+        let dispatcher = publisher.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            dispatcher.dispatch(Arc::new(()), 1);
+        });
+
+        assert_eq!(
+            waitable
+                .wait_timeout(Duration::from_secs(1))
+                .map(|(_, e)| e),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn dropping_the_waitable_unsubscribes_it() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let waitable = EventWaitable::new(&publisher);
 
-        return Some(SubscriberAction::Unsubscribe);
+        drop(waitable);
+        publisher.dispatch(Arc::new(()), 1);
     }
 }
@@ -0,0 +1,128 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub(crate) fn spawn<F: FnOnce() + Send + 'static>(f: F) {
+    thread::spawn(f);
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// Worker threads shared between publishers. The threads exit once the pool is dropped
+pub struct DispatchPool {
+    sender: Mutex<Sender<Job>>,
+}
+
+impl DispatchPool {
+    pub fn new(name: &str, threads: usize) -> Arc<Self> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for i in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("{} {}", name, i))
+                .spawn(move || run_worker(receiver))
+                .expect("Failed to spawn dispatch thread");
+        }
+
+        Arc::new(Self {
+            sender: Mutex::new(sender),
+        })
+    }
+
+    pub(crate) fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        let job: Job = Box::new(f);
+        if self.sender.lock().unwrap().send(job).is_err() {
+            error!("Dispatch pool has shut down, dropping job");
+        }
+    }
+}
+
+fn run_worker(receiver: Arc<Mutex<Receiver<Job>>>) {
+    loop {
+        let job = receiver.lock().unwrap().recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+// Where a publisher runs its subscribers. Whichever is used, a publisher delivers events in the
+// order they were dispatched and never runs two events' handlers at the same time
+#[derive(Clone, Default)]
+pub enum Executor {
+    // On the dispatching thread, before dispatch returns. A dispatch from within one of the
+    // publisher's own handlers is queued and delivered once the current event is done, still
+    // before the outer dispatch returns. Dispatches from other threads wait their turn
+    #[default]
+    Inline,
+    // On a thread owned by the publisher, dispatch returns immediately
+    Thread,
+    // On a shared pool, dispatch returns immediately. Different publishers run in parallel
+    Pool(Arc<DispatchPool>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::publisher::Publisher;
+
+    #[test]
+    fn thread_executor_delivers_in_order_off_the_dispatching_thread() {
+        let publisher: Publisher<(), u32> = Publisher::with_executor("test", Executor::Thread);
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let _subscription = publisher.subscribe_fn(move |_, event| {
+            let this_thread = thread::current().id();
+            sender.lock().unwrap().send((event, this_thread)).unwrap();
+        });
+
+        for i in 0..100 {
+            publisher.dispatch(Arc::new(()), i);
+        }
+
+        for i in 0..100 {
+            let (event, thread_id) = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert_eq!(event, i);
+            assert_ne!(thread_id, thread::current().id());
+        }
+    }
+
+    #[test]
+    fn pool_never_runs_a_publishers_handlers_concurrently() {
+        let pool = DispatchPool::new("test", 4);
+        let publisher: Publisher<(), u32> = Publisher::with_executor("test", Executor::Pool(pool));
+        let running = Arc::new(AtomicUsize::new(0));
+        let overlapped = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let _subscription = {
+            let running = running.clone();
+            let overlapped = overlapped.clone();
+            publisher.subscribe_fn(move |_, event| {
+                if running.fetch_add(1, Ordering::SeqCst) > 0 {
+                    overlapped.fetch_add(1, Ordering::SeqCst);
+                }
+                thread::sleep(Duration::from_micros(100));
+                running.fetch_sub(1, Ordering::SeqCst);
+                sender.lock().unwrap().send(event).unwrap();
+            })
+        };
+
+        for i in 0..50 {
+            publisher.dispatch(Arc::new(()), i);
+        }
+
+        let events: Vec<_> = (0..50)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+            .collect();
+        assert_eq!(events, (0..50).collect::<Vec<_>>());
+        assert_eq!(overlapped.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,30 +1,94 @@
 #[macro_use]
 extern crate log;
 
-use std::sync::mpsc::{RecvError, RecvTimeoutError};
+use std::any::Any;
+use std::fmt;
+use std::sync::mpsc::RecvError;
 use std::sync::Arc;
 use std::time::Duration;
 
 use uuid::Uuid;
 
+pub mod combinators;
+pub mod completion;
+pub mod derived;
+pub mod event_stream;
 pub mod event_waitable;
+pub mod executor;
 pub mod publisher;
+pub mod state_publisher;
+pub mod subscription;
+#[cfg(feature = "tokio")]
+pub mod tokio_timeout;
 
+pub use combinators::{all, any, select, Either, WaitableExt};
+pub use completion::Completion;
+pub use derived::{DerivedPublisher, SubscribableExt};
+pub use event_stream::{EventStream, OverflowPolicy};
 pub use event_waitable::EventWaitable;
+pub use executor::{DispatchPool, Executor};
 pub use publisher::Publisher;
+pub use state_publisher::StatePublisher;
+pub use subscription::Subscription;
 
 pub enum SubscriberAction {
     Unsubscribe,
 }
 
-pub trait Subscriber<TSender, TEvent> {
-    fn handle(self: Arc<Self>, sender: Arc<TSender>, event: TEvent) -> Option<SubscriberAction>;
+// Subscribers run in priority order, then in the order they subscribed. Internal is reserved for
+// library objects that update their state before user handlers see the event
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Internal,
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+pub type SubscriberError = Box<dyn std::error::Error>;
+pub type SubscriberResult = Result<Option<SubscriberAction>, SubscriberError>;
+
+// A subscriber returned an error or panicked. It stays subscribed and dispatch carries on with
+// the next subscriber
+#[derive(Debug, Clone)]
+pub struct SubscriberFailure {
+    pub publisher: String,
+    pub subscription_id: Uuid,
+    pub message: String,
+    pub panicked: bool,
+}
+
+impl fmt::Display for SubscriberFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.panicked { "panicked" } else { "failed" };
+        write!(
+            f,
+            "[{}] Subscriber {} {}: {}",
+            self.publisher, self.subscription_id, kind, self.message
+        )
+    }
+}
+
+// Subscribers are called from the driver's event thread or an executor's threads
+pub trait Subscriber<TSender, TEvent>: Send + Sync {
+    fn handle(self: Arc<Self>, sender: Arc<TSender>, event: TEvent) -> SubscriberResult;
 }
 
 pub trait Subscribable<TSender, TEvent: Clone> {
     fn name(&self) -> &str;
     fn subscribe(&self, subscriber: Arc<dyn Subscriber<TSender, TEvent>>) -> Uuid;
     fn subscribe_once(&self, subscriber: Arc<dyn Subscriber<TSender, TEvent>>) -> Uuid;
+    fn subscribe_with_priority(
+        &self,
+        subscriber: Arc<dyn Subscriber<TSender, TEvent>>,
+        priority: Priority,
+    ) -> Uuid;
+
+    // Derived publishers hand out their upstream chain so operators built on them keep it alive
+    fn keep_alive(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        None
+    }
 }
 
 pub trait Unsubscribable {
@@ -33,13 +97,26 @@ pub trait Unsubscribable {
 
 pub type EventArgs<S, E> = (S, E);
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TimeoutError {
+    pub timeout: Duration,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Timed out after {:?}", self.timeout)
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
 pub trait Waitable<T> {
-    fn wait_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError>;
+    fn wait_timeout(&self, timeout: Duration) -> Result<T, TimeoutError>;
     fn wait(&self) -> Result<T, RecvError>;
 }
 
 pub trait AsyncEventHandler<T> {
     fn then<F>(&self, f: F)
     where
-        F: 'static + FnOnce(T);
+        F: 'static + Send + FnOnce(T);
 }
@@ -1,8 +1,18 @@
-use std::sync::{Arc, Mutex, Weak};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread::{self, ThreadId};
 
 use uuid::Uuid;
 
-use crate::{Subscribable, Subscriber, SubscriberAction, Unsubscribable};
+use crate::event_stream::{EventStream, OverflowPolicy, StreamSubscriber};
+use crate::executor::{DispatchPool, Executor};
+use crate::subscription::Subscription;
+use crate::{
+    Priority, Subscribable, Subscriber, SubscriberAction, SubscriberFailure, SubscriberResult,
+    Unsubscribable,
+};
 
 #[derive(Debug, Copy, Clone)]
 enum SubscriptionMode {
@@ -10,30 +20,100 @@ enum SubscriptionMode {
     All,
 }
 
+enum Handler<S, E> {
+    // Subscribers are owned by the caller, the publisher doesn't keep them alive
+    Weak(Weak<dyn Subscriber<S, E>>),
+    // Closure subscribers are owned by the publisher until unsubscribed
+    Owned(Arc<dyn Subscriber<S, E>>),
+}
+
+impl<S, E> Handler<S, E> {
+    fn upgrade(&self) -> Option<Arc<dyn Subscriber<S, E>>> {
+        match self {
+            Handler::Weak(handler) => handler.upgrade(),
+            Handler::Owned(handler) => Some(handler.clone()),
+        }
+    }
+}
+
+impl<S, E> Clone for Handler<S, E> {
+    fn clone(&self) -> Self {
+        match self {
+            Handler::Weak(handler) => Handler::Weak(handler.clone()),
+            Handler::Owned(handler) => Handler::Owned(handler.clone()),
+        }
+    }
+}
+
+struct FnSubscriber<F> {
+    f: F,
+}
+
+impl<S, E, F> Subscriber<S, E> for FnSubscriber<F>
+where
+    F: Fn(Arc<S>, E) + Send + Sync,
+{
+    fn handle(self: Arc<Self>, sender: Arc<S>, event: E) -> SubscriberResult {
+        (self.f)(sender, event);
+        return Ok(None);
+    }
+}
+
 struct EventSubscription<S, E: Clone> {
     id: Uuid,
-    handler: Weak<dyn Subscriber<S, E>>,
+    handler: Handler<S, E>,
     mode: SubscriptionMode,
+    priority: Priority,
 }
 
 impl<S, E: Clone> EventSubscription<S, E> {
-    fn process_event(&self, sender: Arc<S>, event: E) -> bool {
-        // Check if the handler is still a valid reference
-        let action = match self.handler.upgrade() {
-            // Valid, emit the event and return the action
-            Some(handler) => handler.handle(sender.clone(), event),
-            // Invalid, needs to be unsubscribed
-            None => Some(SubscriberAction::Unsubscribe),
+    // Returns whether to unsubscribe, along with the failure if the handler errored or panicked
+    fn process_event(
+        &self,
+        publisher: &str,
+        sender: Arc<S>,
+        event: E,
+    ) -> (bool, Option<SubscriberFailure>) {
+        // Check if the handler is still a valid reference, unsubscribe if not
+        let handler = match self.handler.upgrade() {
+            Some(handler) => handler,
+            None => return (true, None),
         };
 
-        if let Some(SubscriberAction::Unsubscribe) = action {
-            return true;
-        }
-        if let SubscriptionMode::Once = self.mode {
-            return true;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| handler.handle(sender, event)));
+        let (action, failure) = match result {
+            Ok(Ok(action)) => (action, None),
+            Ok(Err(e)) => (None, Some(self.failure(publisher, e.to_string(), false))),
+            Err(payload) => (
+                None,
+                Some(self.failure(publisher, panic_message(&payload), true)),
+            ),
+        };
+
+        let remove = matches!(
+            (action, self.mode),
+            (Some(SubscriberAction::Unsubscribe), _) | (_, SubscriptionMode::Once)
+        );
+        return (remove, failure);
+    }
+
+    fn failure(&self, publisher: &str, message: String, panicked: bool) -> SubscriberFailure {
+        SubscriberFailure {
+            publisher: publisher.to_string(),
+            subscription_id: self.id,
+            message,
+            panicked,
         }
+    }
+}
 
-        return false;
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
     }
 }
 
@@ -43,6 +123,7 @@ impl<S, E: Clone> Clone for EventSubscription<S, E> {
             id: self.id.clone(),
             handler: self.handler.clone(),
             mode: self.mode.clone(),
+            priority: self.priority,
         }
     }
 
@@ -50,27 +131,108 @@ impl<S, E: Clone> Clone for EventSubscription<S, E> {
         self.id = source.id.clone();
         self.handler = source.handler.clone();
         self.mode = source.mode.clone();
+        self.priority = source.priority;
     }
 }
 
-pub struct Publisher<TSender, TEvent: Clone> {
+struct QueuedEvent<S, E> {
+    // Only delivered to this subscription when set, e.g. to replay a value as it subscribes
+    target: Option<Uuid>,
+    sender: Arc<S>,
+    event: E,
+}
+
+struct DispatchQueue<S, E> {
+    events: VecDeque<QueuedEvent<S, E>>,
+    // Thread delivering inline, reentrant dispatches from it are queued rather than deadlocking
+    inline_owner: Option<ThreadId>,
+    // A job draining the queue has been handed to the executor
+    scheduled: bool,
+}
+
+// Owns the queue while draining it. Dropping it gives the queue up, also when a panic unwinds out
+// of the drain, so later dispatches can never be left waiting on an owner that's gone
+struct QueueOwner<'a, S, E: Clone> {
+    shared: &'a Shared<S, E>,
+    // Held between events, so ownership is released under the same lock that saw the queue empty
+    queue: Option<MutexGuard<'a, DispatchQueue<S, E>>>,
+}
+
+impl<S, E: Clone> QueueOwner<'_, S, E> {
+    fn pop(&mut self) -> Option<QueuedEvent<S, E>> {
+        let mut queue = match self.queue.take() {
+            Some(queue) => queue,
+            None => self.shared.lock_queue(),
+        };
+        let next = queue.events.pop_front();
+        if next.is_none() {
+            self.queue = Some(queue);
+        }
+        next
+    }
+}
+
+impl<S, E: Clone> Drop for QueueOwner<'_, S, E> {
+    fn drop(&mut self) {
+        let mut queue = match self.queue.take() {
+            Some(queue) => queue,
+            None => self
+                .shared
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+        queue.inline_owner = None;
+        queue.scheduled = false;
+        drop(queue);
+        self.shared.idle.notify_all();
+    }
+}
+
+// Streams by subscription id, held weakly so dropping a stream unsubscribes it
+type Streams<S, E> = Vec<(Uuid, Weak<StreamSubscriber<S, E>>)>;
+
+type ErrorHandler<S> = Arc<dyn Fn(Arc<S>, SubscriberFailure) + Send + Sync>;
+
+// Hands a drain of the queue to the publisher's executor. Created by set_executor, which is the
+// only place the sender and event types are known to be Send
+type Scheduler = Arc<dyn Fn() + Send + Sync>;
+
+struct Shared<S, E: Clone> {
     name: String,
-    subscribers: Mutex<Vec<EventSubscription<TSender, TEvent>>>,
-    dispatch_lock: Mutex<()>,
+    error_handler: Mutex<Option<ErrorHandler<S>>>,
+    subscribers: Mutex<Vec<EventSubscription<S, E>>>,
+    queue: Mutex<DispatchQueue<S, E>>,
+    idle: Condvar,
+    // Ended once the publisher and any in-flight dispatch are gone
+    streams: Mutex<Streams<S, E>>,
 }
 
-impl<TSender, TEvent: Clone> Publisher<TSender, TEvent> {
-    pub fn new(name: &str) -> Self {
-        Self {
-            name: name.to_string(),
-            subscribers: Mutex::new(vec![]),
-            dispatch_lock: Mutex::new(()),
+impl<S, E: Clone> Shared<S, E> {
+    fn lock_queue(&self) -> MutexGuard<'_, DispatchQueue<S, E>> {
+        self.queue.lock().unwrap()
+    }
+
+    // Delivers queued events until the queue is empty, then gives up ownership of it
+    fn drain<'a>(&'a self, queue: MutexGuard<'a, DispatchQueue<S, E>>) {
+        let mut owner = QueueOwner {
+            shared: self,
+            queue: Some(queue),
+        };
+        while let Some(queued) = owner.pop() {
+            match queued.target {
+                Some(id) => self.deliver_to(id, queued.sender, queued.event),
+                None => self.deliver(queued.sender, queued.event),
+            }
         }
     }
 
-    pub fn dispatch(&self, sender: Arc<TSender>, event: TEvent) {
-        let _dispatch_lock = self.dispatch_lock.lock().unwrap();
+    // Executor job, delivers the queue then lets inline dispatches through again
+    fn run_scheduled(&self) {
+        self.drain(self.lock_queue());
+    }
 
+    fn deliver(&self, sender: Arc<S>, event: E) {
         // Get a clone of the list to iterate mutex-free
         let temp_subs: Vec<_> = {
             let subs = self.subscribers.lock().unwrap();
@@ -78,12 +240,15 @@ impl<TSender, TEvent: Clone> Publisher<TSender, TEvent> {
         };
 
         let mut subs_to_remove = vec![];
+        let mut failures = vec![];
 
         for sub in temp_subs.iter() {
-            let should_remove = sub.process_event(sender.clone(), event.to_owned());
+            let (should_remove, failure) =
+                sub.process_event(&self.name, sender.clone(), event.to_owned());
             if should_remove {
                 subs_to_remove.push(sub)
             }
+            failures.extend(failure);
         }
 
         // For each of the subs that need cleanup, remove from the mutex-locked list
@@ -93,43 +258,495 @@ impl<TSender, TEvent: Clone> Publisher<TSender, TEvent> {
                 subscribers.retain(|s| s.id != sub.id)
             }
         }
+
+        self.report(&sender, failures);
+    }
+
+    fn deliver_to(&self, id: Uuid, sender: Arc<S>, event: E) {
+        let sub = {
+            let subs = self.subscribers.lock().unwrap();
+            subs.iter().find(|s| s.id == id).cloned()
+        };
+        let sub = match sub {
+            Some(sub) => sub,
+            None => return,
+        };
+
+        let (should_remove, failure) = sub.process_event(&self.name, sender.clone(), event);
+        if should_remove {
+            self.subscribers.lock().unwrap().retain(|s| s.id != id);
+        }
+        self.report(&sender, failure.into_iter().collect());
+    }
+
+    fn report(&self, sender: &Arc<S>, failures: Vec<SubscriberFailure>) {
+        if failures.is_empty() {
+            return;
+        }
+        let error_handler = self.error_handler.lock().unwrap().clone();
+        for failure in failures {
+            error!("{}", failure);
+            if let Some(error_handler) = &error_handler {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    error_handler(sender.clone(), failure)
+                }));
+                if let Err(payload) = result {
+                    error!(
+                        "[{}] Error handler panicked: {}",
+                        self.name,
+                        panic_message(&payload)
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<S, E: Clone> Drop for Shared<S, E> {
+    fn drop(&mut self) {
+        let streams = self.streams.get_mut().unwrap();
+        for stream in streams.drain(..).filter_map(|(_, s)| s.upgrade()) {
+            stream.end();
+        }
+    }
+}
+
+pub struct Publisher<TSender, TEvent: Clone> {
+    name: String,
+    // Shared with Subscription guards and executor threads
+    shared: Arc<Shared<TSender, TEvent>>,
+    // None when dispatching inline
+    scheduler: Mutex<Option<Scheduler>>,
+}
+
+impl<TSender, TEvent: Clone> Publisher<TSender, TEvent> {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            shared: Arc::new(Shared {
+                name: name.to_string(),
+                error_handler: Mutex::new(None),
+                subscribers: Mutex::new(vec![]),
+                queue: Mutex::new(DispatchQueue {
+                    events: VecDeque::new(),
+                    inline_owner: None,
+                    scheduled: false,
+                }),
+                idle: Condvar::new(),
+                streams: Mutex::new(vec![]),
+            }),
+            scheduler: Mutex::new(None),
+        }
+    }
+
+    pub fn with_executor(name: &str, executor: Executor) -> Self
+    where
+        TSender: Send + Sync + 'static,
+        TEvent: Send + 'static,
+    {
+        let publisher = Self::new(name);
+        publisher.set_executor(executor);
+        publisher
+    }
+
+    // Events already queued are delivered by the previous executor. Subscribers run on another
+    // thread, so the sender and events have to be sendable
+    pub fn set_executor(&self, executor: Executor)
+    where
+        TSender: Send + Sync + 'static,
+        TEvent: Send + 'static,
+    {
+        let pool = match executor {
+            Executor::Inline => None,
+            Executor::Thread => Some(DispatchPool::new(&self.name, 1)),
+            Executor::Pool(pool) => Some(pool),
+        };
+        let scheduler = pool.map(|pool| {
+            let shared = self.shared.clone();
+            let scheduler: Scheduler = Arc::new(move || {
+                let shared = shared.clone();
+                pool.execute(move || shared.run_scheduled());
+            });
+            scheduler
+        });
+        *self.scheduler.lock().unwrap() = scheduler;
+    }
+
+    // Called with each subscriber error or panic after it's been logged
+    pub fn set_error_handler<F>(&self, f: F)
+    where
+        F: Fn(Arc<TSender>, SubscriberFailure) + Send + Sync + 'static,
+    {
+        *self.shared.error_handler.lock().unwrap() = Some(Arc::new(f));
+    }
+
+    // Queues an event without delivering it, so callers can order it with their own state under
+    // their own lock. Followed by flush() once that lock is released
+    pub(crate) fn enqueue(&self, target: Option<Uuid>, sender: Arc<TSender>, event: TEvent) {
+        self.shared.lock_queue().events.push_back(QueuedEvent {
+            target,
+            sender,
+            event,
+        });
+    }
+
+    fn flush_inline(&self) {
+        let this_thread = thread::current().id();
+        let queue = self.shared.lock_queue();
+        if queue.inline_owner == Some(this_thread) {
+            // Dispatched from one of our own handlers, the outer dispatch delivers it
+            return;
+        }
+
+        // Another owner delivers anything queued before it gives the queue up
+        let mut queue = self
+            .shared
+            .idle
+            .wait_while(queue, |q| q.inline_owner.is_some() || q.scheduled)
+            .unwrap();
+        if queue.events.is_empty() {
+            return;
+        }
+        queue.inline_owner = Some(this_thread);
+        self.shared.drain(queue);
     }
 
     fn subscribe_impl(
         &self,
-        handler: Arc<dyn Subscriber<TSender, TEvent>>,
+        handler: Handler<TSender, TEvent>,
         mode: SubscriptionMode,
+        priority: Priority,
     ) -> Uuid {
         let id = Uuid::new_v4();
         let sub = EventSubscription {
             id: id.clone(),
-            handler: Arc::downgrade(&handler),
+            handler,
             mode,
+            priority,
         };
-        let mut subscribers = self.subscribers.lock().unwrap();
-        subscribers.push(sub);
+        // Keep the list sorted by priority, after any existing subscribers of the same priority
+        let mut subscribers = self.shared.subscribers.lock().unwrap();
+        let index = subscribers
+            .iter()
+            .position(|s| s.priority > priority)
+            .unwrap_or(subscribers.len());
+        subscribers.insert(index, sub);
 
         return id;
     }
 }
 
+impl<TSender: 'static, TEvent: Clone + 'static> Publisher<TSender, TEvent> {
+    pub fn dispatch(&self, sender: Arc<TSender>, event: TEvent) {
+        self.enqueue(None, sender, event);
+        self.flush();
+    }
+
+    // Delivers queued events, inline or on the executor
+    pub(crate) fn flush(&self) {
+        let scheduler = self.scheduler.lock().unwrap().clone();
+        let scheduler = match scheduler {
+            Some(scheduler) => scheduler,
+            None => return self.flush_inline(),
+        };
+
+        let mut queue = self.shared.lock_queue();
+        if queue.scheduled || queue.inline_owner.is_some() || queue.events.is_empty() {
+            // Whoever is draining the queue delivers it, keeping events in order
+            return;
+        }
+        queue.scheduled = true;
+        drop(queue);
+
+        scheduler();
+    }
+
+    // The publisher keeps the closure alive until the returned guard is dropped
+    pub fn subscribe_fn<F>(&self, f: F) -> Subscription
+    where
+        TSender: Send + Sync,
+        TEvent: Send,
+        F: Fn(Arc<TSender>, TEvent) + Send + Sync + 'static,
+    {
+        let handler: Arc<dyn Subscriber<TSender, TEvent>> = Arc::new(FnSubscriber { f });
+        let id = self.subscribe_impl(
+            Handler::Owned(handler),
+            SubscriptionMode::All,
+            Priority::Normal,
+        );
+        self.subscription(id)
+    }
+
+    // Buffers up to `capacity` events for the returned stream, which unsubscribes when dropped
+    pub fn stream(
+        &self,
+        capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> EventStream<TSender, TEvent>
+    where
+        TSender: Send + Sync,
+        TEvent: Send,
+    {
+        let subscriber = StreamSubscriber::new(capacity, overflow_policy);
+        let id = self.subscribe(subscriber.clone());
+        self.shared
+            .streams
+            .lock()
+            .unwrap()
+            .push((id, Arc::downgrade(&subscriber)));
+        EventStream::new(subscriber, self.subscription(id))
+    }
+
+    fn subscription(&self, id: Uuid) -> Subscription
+    where
+        TSender: Send + Sync,
+        TEvent: Send,
+    {
+        let shared = Arc::downgrade(&self.shared);
+        Subscription::new(
+            id,
+            Box::new(move || {
+                if let Some(shared) = shared.upgrade() {
+                    shared.subscribers.lock().unwrap().retain(|s| s.id != id);
+                    shared.streams.lock().unwrap().retain(|(s, _)| *s != id);
+                }
+            }),
+        )
+    }
+}
+
 impl<TSender, TEvent: Clone> Subscribable<TSender, TEvent> for Publisher<TSender, TEvent> {
     fn name(&self) -> &str {
         return &self.name;
     }
 
     fn subscribe(&self, handler: Arc<dyn Subscriber<TSender, TEvent>>) -> Uuid {
-        return self.subscribe_impl(handler, SubscriptionMode::All);
+        return self.subscribe_impl(
+            Handler::Weak(Arc::downgrade(&handler)),
+            SubscriptionMode::All,
+            Priority::Normal,
+        );
     }
 
     fn subscribe_once(&self, handler: Arc<dyn Subscriber<TSender, TEvent>>) -> Uuid {
-        return self.subscribe_impl(handler, SubscriptionMode::Once);
+        return self.subscribe_impl(
+            Handler::Weak(Arc::downgrade(&handler)),
+            SubscriptionMode::Once,
+            Priority::Normal,
+        );
+    }
+
+    fn subscribe_with_priority(
+        &self,
+        handler: Arc<dyn Subscriber<TSender, TEvent>>,
+        priority: Priority,
+    ) -> Uuid {
+        return self.subscribe_impl(
+            Handler::Weak(Arc::downgrade(&handler)),
+            SubscriptionMode::All,
+            priority,
+        );
     }
 }
 
 impl<TSender, TEvent: Clone> Unsubscribable for Publisher<TSender, TEvent> {
     fn unsubscribe(&self, id: Uuid) {
-        let mut subscribers = self.subscribers.lock().unwrap();
+        let mut subscribers = self.shared.subscribers.lock().unwrap();
         subscribers.retain(|s| s.id != id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                log: log.clone(),
+            })
+        }
+    }
+
+    impl Subscriber<(), u32> for Recorder {
+        fn handle(self: Arc<Self>, _sender: Arc<()>, event: u32) -> SubscriberResult {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}{}", self.name, event));
+            return Ok(None);
+        }
+    }
+
+    struct Unsubscriber;
+
+    impl Subscriber<(), u32> for Unsubscriber {
+        fn handle(self: Arc<Self>, _sender: Arc<()>, _event: u32) -> SubscriberResult {
+            return Ok(Some(SubscriberAction::Unsubscribe));
+        }
+    }
+
+    struct Failing;
+
+    impl Subscriber<(), u32> for Failing {
+        fn handle(self: Arc<Self>, _sender: Arc<()>, _event: u32) -> SubscriberResult {
+            return Err("failed".into());
+        }
+    }
+
+    fn log() -> Arc<Mutex<Vec<String>>> {
+        Arc::new(Mutex::new(vec![]))
+    }
+
+    fn entries(log: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        log.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn subscribers_run_in_priority_then_subscription_order() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let log = log();
+        let low = Recorder::new("low", &log);
+        let normal_a = Recorder::new("a", &log);
+        let normal_b = Recorder::new("b", &log);
+        let internal = Recorder::new("internal", &log);
+        publisher.subscribe_with_priority(low.clone(), Priority::Low);
+        publisher.subscribe(normal_a.clone());
+        publisher.subscribe(normal_b.clone());
+        publisher.subscribe_with_priority(internal.clone(), Priority::Internal);
+
+        publisher.dispatch(Arc::new(()), 1);
+
+        assert_eq!(entries(&log), ["internal1", "a1", "b1", "low1"]);
+    }
+
+    #[test]
+    fn reentrant_dispatch_is_delivered_after_the_current_event() {
+        let publisher: Arc<Publisher<(), u32>> = Arc::new(Publisher::new("test"));
+        let log = log();
+        let weak = Arc::downgrade(&publisher);
+        let first_log = log.clone();
+        let _first = publisher.subscribe_fn(move |sender, event| {
+            first_log.lock().unwrap().push(format!("a{}", event));
+            if event == 1 {
+                weak.upgrade().unwrap().dispatch(sender, 2);
+            }
+        });
+        let second = Recorder::new("b", &log);
+        publisher.subscribe(second.clone());
+
+        publisher.dispatch(Arc::new(()), 1);
+
+        assert_eq!(entries(&log), ["a1", "b1", "a2", "b2"]);
+    }
+
+    #[test]
+    fn panicking_subscriber_does_not_stop_later_dispatches() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let failures = Arc::new(Mutex::new(vec![]));
+        let handler_failures = failures.clone();
+        publisher.set_error_handler(move |_, failure: SubscriberFailure| {
+            handler_failures.lock().unwrap().push(failure.panicked);
+            panic!("error handler");
+        });
+        let log = log();
+        let _panicking = publisher.subscribe_fn(|_, event| {
+            if event == 1 {
+                panic!("subscriber");
+            }
+        });
+        let recorder = Recorder::new("r", &log);
+        publisher.subscribe(recorder.clone());
+        let failing = Arc::new(Failing);
+        publisher.subscribe(failing.clone());
+
+        publisher.dispatch(Arc::new(()), 1);
+        publisher.dispatch(Arc::new(()), 2);
+
+        assert_eq!(entries(&log), ["r1", "r2"]);
+        assert_eq!(*failures.lock().unwrap(), [true, false, false]);
+    }
+
+    #[test]
+    fn once_and_unsubscribe_action_remove_the_subscriber() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let log = log();
+        let once = Recorder::new("once", &log);
+        publisher.subscribe_once(once.clone());
+        let unsubscriber = Arc::new(Unsubscriber);
+        publisher.subscribe(unsubscriber.clone());
+
+        publisher.dispatch(Arc::new(()), 1);
+        publisher.dispatch(Arc::new(()), 2);
+
+        assert_eq!(entries(&log), ["once1"]);
+        assert_eq!(publisher.shared.subscribers.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let log = log();
+        let recorder = Recorder::new("r", &log);
+        publisher.subscribe(recorder.clone());
+        let count = Arc::new(AtomicUsize::new(0));
+        let fn_count = count.clone();
+        let subscription = publisher.subscribe_fn(move |_, _| {
+            fn_count.fetch_add(1, Ordering::SeqCst);
+        });
+
+        drop(recorder);
+        drop(subscription);
+        publisher.dispatch(Arc::new(()), 1);
+
+        assert!(entries(&log).is_empty());
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert_eq!(publisher.shared.subscribers.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn stream_ends_when_the_publisher_is_dropped() {
+        let publisher: Publisher<(), u32> = Publisher::new("test");
+        let stream = publisher.stream(8, OverflowPolicy::DropOldest);
+        publisher.dispatch(Arc::new(()), 1);
+        publisher.dispatch(Arc::new(()), 2);
+        drop(publisher);
+
+        let events: Vec<_> = stream.map(|(_, e)| e).collect();
+        assert_eq!(events, [1, 2]);
+    }
+
+    #[test]
+    fn dispatches_from_other_threads_are_all_delivered() {
+        let publisher: Arc<Publisher<(), u32>> = Arc::new(Publisher::new("test"));
+        let count = Arc::new(AtomicUsize::new(0));
+        let fn_count = count.clone();
+        let _subscription = publisher.subscribe_fn(move |_, _| {
+            fn_count.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let publisher = publisher.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        publisher.dispatch(Arc::new(()), i);
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert_eq!(count.load(Ordering::SeqCst), 400);
+    }
+}
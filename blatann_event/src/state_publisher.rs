@@ -0,0 +1,242 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use uuid::Uuid;
+
+use crate::publisher::Publisher;
+use crate::subscription::Subscription;
+use crate::{Priority, Subscribable, Subscriber, SubscriberFailure, Unsubscribable};
+
+struct State<S, T> {
+    value: T,
+    // Sender of the last set, needed to replay the value
+    sender: Weak<S>,
+}
+
+// Publisher for a current value. Subscribers hear about changes and, with replay enabled, get
+// the current value as soon as they subscribe. Changes and replays are queued in the order the
+// value was read or written, so the last value a subscriber hears is always the current one
+pub struct StatePublisher<S, T: Clone + PartialEq> {
+    publisher: Publisher<S, T>,
+    state: Mutex<State<S, T>>,
+    replay: bool,
+}
+
+impl<S: 'static, T: Clone + PartialEq + 'static> StatePublisher<S, T> {
+    pub fn new(name: &str, initial: T, replay: bool) -> Self {
+        Self {
+            publisher: Publisher::new(name),
+            state: Mutex::new(State {
+                value: initial,
+                sender: Weak::new(),
+            }),
+            replay,
+        }
+    }
+
+    // Called with each subscriber error or panic, including on replay
+    pub fn set_error_handler<F>(&self, f: F)
+    where
+        F: Fn(Arc<S>, SubscriberFailure) + Send + Sync + 'static,
+    {
+        self.publisher.set_error_handler(f)
+    }
+
+    pub fn get(&self) -> T {
+        self.state.lock().unwrap().value.clone()
+    }
+
+    // Dispatches only if the value changed, returns whether it did. The sender is stored either
+    // way so the value can be replayed
+    pub fn set(&self, sender: &Arc<S>, value: T) -> bool {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.sender = Arc::downgrade(sender);
+            if state.value == value {
+                return false;
+            }
+            state.value = value.clone();
+            self.publisher.enqueue(None, sender.clone(), value);
+        }
+        self.publisher.flush();
+        return true;
+    }
+
+    pub fn subscribe_fn<F>(&self, f: F) -> Subscription
+    where
+        S: Send + Sync,
+        T: Send,
+        F: Fn(Arc<S>, T) + Send + Sync + 'static,
+    {
+        let subscription = self.publisher.subscribe_fn(f);
+        self.replay_to(subscription.id());
+        subscription
+    }
+
+    // Delivered like any other event, so failures reach the error handler and an unsubscribe or a
+    // once subscription removes the subscriber
+    fn replay_to(&self, id: Uuid) {
+        if !self.replay {
+            return;
+        }
+        {
+            let state = self.state.lock().unwrap();
+            if let Some(sender) = state.sender.upgrade() {
+                self.publisher
+                    .enqueue(Some(id), sender, state.value.clone());
+            }
+        }
+        self.publisher.flush();
+    }
+}
+
+impl<S: 'static, T: Clone + PartialEq + 'static> Subscribable<S, T> for StatePublisher<S, T> {
+    fn name(&self) -> &str {
+        self.publisher.name()
+    }
+
+    fn subscribe(&self, subscriber: Arc<dyn Subscriber<S, T>>) -> Uuid {
+        self.subscribe_with_priority(subscriber, Priority::Normal)
+    }
+
+    fn subscribe_once(&self, subscriber: Arc<dyn Subscriber<S, T>>) -> Uuid {
+        let id = self.publisher.subscribe_once(subscriber);
+        self.replay_to(id);
+        id
+    }
+
+    fn subscribe_with_priority(
+        &self,
+        subscriber: Arc<dyn Subscriber<S, T>>,
+        priority: Priority,
+    ) -> Uuid {
+        let id = self.publisher.subscribe_with_priority(subscriber, priority);
+        self.replay_to(id);
+        id
+    }
+}
+
+impl<S, T: Clone + PartialEq> Unsubscribable for StatePublisher<S, T> {
+    fn unsubscribe(&self, id: Uuid) {
+        self.publisher.unsubscribe(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::SubscriberResult;
+
+    struct Recorder {
+        values: Mutex<Vec<u32>>,
+    }
+
+    impl Subscriber<(), u32> for Recorder {
+        fn handle(self: Arc<Self>, _sender: Arc<()>, event: u32) -> SubscriberResult {
+            self.values.lock().unwrap().push(event);
+            return Ok(None);
+        }
+    }
+
+    fn recorder() -> Arc<Recorder> {
+        Arc::new(Recorder {
+            values: Mutex::new(vec![]),
+        })
+    }
+
+    #[test]
+    fn replays_the_current_value_on_subscribe() {
+        let sender = Arc::new(());
+        let state = StatePublisher::new("test", 0, true);
+        state.set(&sender, 1);
+
+        let subscriber = recorder();
+        state.subscribe(subscriber.clone());
+        state.set(&sender, 2);
+
+        assert_eq!(*subscriber.values.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn only_changes_are_dispatched_without_replay() {
+        let sender = Arc::new(());
+        let state = StatePublisher::new("test", 0, false);
+        state.set(&sender, 1);
+
+        let subscriber = recorder();
+        state.subscribe(subscriber.clone());
+
+        assert!(!state.set(&sender, 1));
+        assert!(state.set(&sender, 2));
+        assert_eq!(*subscriber.values.lock().unwrap(), [2]);
+        assert_eq!(state.get(), 2);
+    }
+
+    #[test]
+    fn replay_completes_a_once_subscription() {
+        let sender = Arc::new(());
+        let state = StatePublisher::new("test", 0, true);
+        state.set(&sender, 1);
+
+        let subscriber = recorder();
+        state.subscribe_once(subscriber.clone());
+        state.set(&sender, 2);
+
+        assert_eq!(*subscriber.values.lock().unwrap(), [1]);
+    }
+
+    #[test]
+    fn replay_failures_reach_the_error_handler() {
+        let sender = Arc::new(());
+        let state = StatePublisher::new("test", 0, true);
+        state.set(&sender, 1);
+        let failures = Arc::new(Mutex::new(0));
+        let handler_failures = failures.clone();
+        state.set_error_handler(move |_, _| *handler_failures.lock().unwrap() += 1);
+
+        let _subscription = state.subscribe_fn(|_, _| panic!("subscriber"));
+
+        assert_eq!(*failures.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn last_value_seen_is_the_current_one_when_subscribing_during_sets() {
+        let sender = Arc::new(());
+        let state = Arc::new(StatePublisher::new("test", 0, true));
+        state.set(&sender, 1);
+
+        let setter = {
+            let state = state.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 2..=50 {
+                    state.set(&sender, i);
+                    thread::sleep(Duration::from_micros(200));
+                }
+            })
+        };
+        // Slow handlers widen the window between reading the value and delivering it
+        let subscribers: Vec<_> = (0..50)
+            .map(|_| {
+                let values = Arc::new(Mutex::new(vec![]));
+                let handler_values = values.clone();
+                let subscription = state.subscribe_fn(move |_, value| {
+                    thread::sleep(Duration::from_micros(50));
+                    handler_values.lock().unwrap().push(value);
+                });
+                (subscription, values)
+            })
+            .collect();
+        setter.join().unwrap();
+
+        // A replay may repeat a change queued as it subscribed, but never follows a newer value
+        for (_subscription, values) in subscribers {
+            let values = values.lock().unwrap();
+            assert!(values.windows(2).all(|w| w[0] <= w[1]), "{:?}", values);
+            assert_eq!(values.last(), Some(&state.get()));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use uuid::Uuid;
+
+// Unsubscribes from the publisher when dropped
+#[must_use = "dropping a Subscription unsubscribes it, call detach() to keep it"]
+pub struct Subscription {
+    id: Uuid,
+    unsubscribe: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Subscription {
+    pub(crate) fn new(id: Uuid, unsubscribe: Box<dyn FnOnce() + Send>) -> Self {
+        Self {
+            id,
+            unsubscribe: Some(unsubscribe),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    // Keeps the handler subscribed for as long as the publisher lives
+    pub fn detach(mut self) {
+        self.unsubscribe.take();
+    }
+
+    pub fn unsubscribe(self) {}
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.unsubscribe.take() {
+            unsubscribe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::publisher::Publisher;
+
+    fn counting(publisher: &Publisher<(), u32>, count: &Arc<AtomicUsize>) -> super::Subscription {
+        let count = count.clone();
+        publisher.subscribe_fn(move |_, _| {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    #[test]
+    fn dropping_unsubscribes() {
+        let publisher = Publisher::new("test");
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscription = counting(&publisher, &count);
+
+        publisher.dispatch(Arc::new(()), 1);
+        subscription.unsubscribe();
+        publisher.dispatch(Arc::new(()), 2);
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn detached_subscriptions_stay_subscribed() {
+        let publisher = Publisher::new("test");
+        let count = Arc::new(AtomicUsize::new(0));
+        counting(&publisher, &count).detach();
+
+        publisher.dispatch(Arc::new(()), 1);
+        publisher.dispatch(Arc::new(()), 2);
+
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn outliving_the_publisher_is_harmless() {
+        let publisher = Publisher::new("test");
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscription = counting(&publisher, &count);
+
+        drop(publisher);
+        drop(subscription);
+    }
+}
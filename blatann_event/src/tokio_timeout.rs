@@ -0,0 +1,18 @@
+use std::future::Future;
+use std::time::Duration;
+
+pub use tokio::time::error::Elapsed;
+use tokio::time::Timeout;
+
+// Async counterpart of Waitable::wait_timeout, must be awaited within a tokio runtime
+pub trait TimeoutExt: Future + Sized {
+    fn timeout(self, duration: Duration) -> Timeout<Self> {
+        tokio::time::timeout(duration, self)
+    }
+}
+
+impl<F: Future> TimeoutExt for F {}
+
+pub async fn wait_timeout<F: Future>(future: F, duration: Duration) -> Result<F::Output, Elapsed> {
+    tokio::time::timeout(duration, future).await
+}
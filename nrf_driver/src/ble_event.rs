@@ -4,11 +4,16 @@ use crate::common::events::*;
 use crate::ffi;
 use crate::ffi::{ble_common_evt_t, ble_evt_t};
 use crate::gap::events::*;
+use crate::gatt::enums::BleGattStatusCode;
+use crate::gattc::events::*;
+use crate::gatts::events::*;
 
 #[derive(Copy, Clone, Debug)]
 pub enum BleEventId {
     Common(CommonEventId),
     Gap(GapEventId),
+    Gattc(GattcEventId),
+    Gatts(GattsEventId),
 }
 
 impl BleEventId {
@@ -17,6 +22,10 @@ impl BleEventId {
             Some(Self::Common(id))
         } else if let Some(id) = GapEventId::try_from(id) {
             Some(Self::Gap(id))
+        } else if let Some(id) = GattcEventId::try_from(id) {
+            Some(Self::Gattc(id))
+        } else if let Some(id) = GattsEventId::try_from(id) {
+            Some(Self::Gatts(id))
         } else {
             None
         }
@@ -28,6 +37,8 @@ impl Into<u16> for BleEventId {
         match self {
             BleEventId::Common(x) => x as u16,
             BleEventId::Gap(x) => x as u16,
+            BleEventId::Gattc(x) => x as u16,
+            BleEventId::Gatts(x) => x as u16,
         }
     }
 }
@@ -77,14 +88,14 @@ pub enum GapEventId {
     Connected = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_CONNECTED as u16,
     Disconnected = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_DISCONNECTED as u16,
     // ConnParamUpdate = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_CONN_PARAM_UPDATE as u16,
-    // SecParamsRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_SEC_PARAMS_REQUEST as u16,
-    // SecInfoRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_SEC_INFO_REQUEST as u16,
+    SecParamsRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_SEC_PARAMS_REQUEST as u16,
+    SecInfoRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_SEC_INFO_REQUEST as u16,
     // PasskeyDisplay = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_PASSKEY_DISPLAY as u16,
     // KeyPressed = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_KEY_PRESSED as u16,
     // AuthKeyRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_AUTH_KEY_REQUEST as u16,
     // LescDhkeyRequest = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_LESC_DHKEY_REQUEST as u16,
-    // AuthStatus = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_AUTH_STATUS as u16,
-    // ConnSecUpdate = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_CONN_SEC_UPDATE as u16,
+    AuthStatus = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_AUTH_STATUS as u16,
+    ConnSecUpdate = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_CONN_SEC_UPDATE as u16,
     Timeout = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_TIMEOUT as u16,
     // RssiChanged = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_RSSI_CHANGED as u16,
     // AdvReport = ffi::BLE_GAP_EVTS_BLE_GAP_EVT_ADV_REPORT as u16,
@@ -113,6 +124,10 @@ impl Into<BleEventId> for GapEventId {
 pub enum GapEvent {
     Connected(GapEventConnected),
     Disconnected(GapEventDisconnected),
+    SecParamsRequest(GapEventSecParamsRequest),
+    SecInfoRequest(GapEventSecInfoRequest),
+    AuthStatus(GapEventAuthStatus),
+    ConnSecUpdate(GapEventConnSecUpdate),
     Timeout(GapEventTimeout),
     PhyUpdateRequest(GapEventPhyUpdateRequest),
     PhyUpdate(GapEventPhyUpdate),
@@ -133,14 +148,24 @@ impl GapEvent {
                 &params.disconnected,
             )),
             // GapEventId::ConnParamUpdate => unimplemented!(),
-            // GapEventId::SecParamsRequest => unimplemented!(),
-            // GapEventId::SecInfoRequest => unimplemented!(),
+            GapEventId::SecParamsRequest => GapEvent::SecParamsRequest(
+                GapEventSecParamsRequest::from_c(conn_handle, &params.sec_params_request),
+            ),
+            GapEventId::SecInfoRequest => GapEvent::SecInfoRequest(
+                GapEventSecInfoRequest::from_c(conn_handle, &params.sec_info_request),
+            ),
             // GapEventId::PasskeyDisplay => unimplemented!(),
             // GapEventId::KeyPressed => unimplemented!(),
             // GapEventId::AuthKeyRequest => unimplemented!(),
             // GapEventId::LescDhkeyRequest => unimplemented!(),
-            // GapEventId::AuthStatus => unimplemented!(),
-            // GapEventId::ConnSecUpdate => unimplemented!(),
+            GapEventId::AuthStatus => GapEvent::AuthStatus(GapEventAuthStatus::from_c(
+                conn_handle,
+                &params.auth_status,
+            )),
+            GapEventId::ConnSecUpdate => GapEvent::ConnSecUpdate(GapEventConnSecUpdate::from_c(
+                conn_handle,
+                &params.conn_sec_update,
+            )),
             GapEventId::Timeout => {
                 GapEvent::Timeout(GapEventTimeout::from_c(conn_handle, &params.timeout))
             }
@@ -168,10 +193,181 @@ impl GapEvent {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[repr(u16)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug)]
+pub enum GattcEventId {
+    PrimaryServiceDiscoveryResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_PRIM_SRVC_DISC_RSP as u16,
+    // RelationshipDiscoveryResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_REL_DISC_RSP as u16,
+    CharacteristicDiscoveryResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_CHAR_DISC_RSP as u16,
+    DescriptorDiscoveryResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_DESC_DISC_RSP as u16,
+    // AttrInfoDiscoveryResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_ATTR_INFO_DISC_RSP as u16,
+    // CharValueByUuidReadResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_CHAR_VAL_BY_UUID_READ_RSP as u16,
+    ReadResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_READ_RSP as u16,
+    // CharValuesReadResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_CHAR_VALS_READ_RSP as u16,
+    WriteResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_WRITE_RSP as u16,
+    Hvx = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_HVX as u16,
+    ExchangeMtuResponse = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_EXCHANGE_MTU_RSP as u16,
+    // Timeout = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_TIMEOUT as u16,
+    WriteCmdTxComplete = ffi::BLE_GATTC_EVTS_BLE_GATTC_EVT_WRITE_CMD_TX_COMPLETE as u16,
+}
+
+impl GattcEventId {
+    pub fn try_from(id: u16) -> Option<Self> {
+        FromPrimitive::from_u16(id)
+    }
+}
+
+impl Into<BleEventId> for GattcEventId {
+    fn into(self) -> BleEventId {
+        BleEventId::Gattc(self)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum GattcEvent {
+    PrimaryServiceDiscoveryResponse(GattcEventPrimaryServiceDiscoveryResponse),
+    CharacteristicDiscoveryResponse(GattcEventCharacteristicDiscoveryResponse),
+    DescriptorDiscoveryResponse(GattcEventDescriptorDiscoveryResponse),
+    ReadResponse(GattcEventReadResponse),
+    WriteResponse(GattcEventWriteResponse),
+    Hvx(GattcEventHvx),
+    ExchangeMtuResponse(GattcEventExchangeMtuResponse),
+    WriteCmdTxComplete(GattcEventWriteCmdTxComplete),
+}
+
+impl GattcEvent {
+    pub(crate) unsafe fn from_c(id: GattcEventId, e: *const ffi::ble_gattc_evt_t) -> Self {
+        let conn_handle = (*e).conn_handle;
+        let status = BleGattStatusCode::from_u16_or_unknown((*e).gatt_status);
+        let params = &(*e).params;
+        match id {
+            GattcEventId::PrimaryServiceDiscoveryResponse => {
+                GattcEvent::PrimaryServiceDiscoveryResponse(
+                    GattcEventPrimaryServiceDiscoveryResponse::from_c(
+                        conn_handle,
+                        status,
+                        &params.prim_srvc_disc_rsp,
+                    ),
+                )
+            }
+            // GattcEventId::RelationshipDiscoveryResponse => unimplemented!(),
+            GattcEventId::CharacteristicDiscoveryResponse => {
+                GattcEvent::CharacteristicDiscoveryResponse(
+                    GattcEventCharacteristicDiscoveryResponse::from_c(
+                        conn_handle,
+                        status,
+                        &params.char_disc_rsp,
+                    ),
+                )
+            }
+            GattcEventId::DescriptorDiscoveryResponse => GattcEvent::DescriptorDiscoveryResponse(
+                GattcEventDescriptorDiscoveryResponse::from_c(
+                    conn_handle,
+                    status,
+                    &params.desc_disc_rsp,
+                ),
+            ),
+            // GattcEventId::AttrInfoDiscoveryResponse => unimplemented!(),
+            // GattcEventId::CharValueByUuidReadResponse => unimplemented!(),
+            GattcEventId::ReadResponse => GattcEvent::ReadResponse(GattcEventReadResponse::from_c(
+                conn_handle,
+                status,
+                (*e).error_handle,
+                &params.read_rsp,
+            )),
+            // GattcEventId::CharValuesReadResponse => unimplemented!(),
+            GattcEventId::WriteResponse => {
+                GattcEvent::WriteResponse(GattcEventWriteResponse::from_c(
+                    conn_handle,
+                    status,
+                    (*e).error_handle,
+                    &params.write_rsp,
+                ))
+            }
+            GattcEventId::Hvx => GattcEvent::Hvx(GattcEventHvx::from_c(conn_handle, &params.hvx)),
+            GattcEventId::ExchangeMtuResponse => {
+                GattcEvent::ExchangeMtuResponse(GattcEventExchangeMtuResponse::from_c(
+                    conn_handle,
+                    status,
+                    &params.exchange_mtu_rsp,
+                ))
+            }
+            // GattcEventId::Timeout => unimplemented!(),
+            GattcEventId::WriteCmdTxComplete => GattcEvent::WriteCmdTxComplete(
+                GattcEventWriteCmdTxComplete::from_c(conn_handle, &params.write_cmd_tx_complete),
+            ),
+        }
+    }
+}
+
+#[repr(u16)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug)]
+pub enum GattsEventId {
+    Write = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_WRITE as u16,
+    RwAuthorizeRequest = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_RW_AUTHORIZE_REQUEST as u16,
+    SysAttrMissing = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_SYS_ATTR_MISSING as u16,
+    // Hvc = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_HVC as u16,
+    ScConfirm = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_SC_CONFIRM as u16,
+    ExchangeMtuRequest = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_EXCHANGE_MTU_REQUEST as u16,
+    // Timeout = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_TIMEOUT as u16,
+    HvnTxComplete = ffi::BLE_GATTS_EVTS_BLE_GATTS_EVT_HVN_TX_COMPLETE as u16,
+}
+
+impl GattsEventId {
+    pub fn try_from(id: u16) -> Option<Self> {
+        FromPrimitive::from_u16(id)
+    }
+}
+
+impl Into<BleEventId> for GattsEventId {
+    fn into(self) -> BleEventId {
+        BleEventId::Gatts(self)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum GattsEvent {
+    Write(GattsEventWrite),
+    RwAuthorizeRequest(GattsEventRwAuthorizeRequest),
+    SysAttrMissing(GattsEventSysAttrMissing),
+    ScConfirm(GattsEventScConfirm),
+    ExchangeMtuRequest(GattsEventExchangeMtuRequest),
+    HvnTxComplete(GattsEventHvnTxComplete),
+}
+
+impl GattsEvent {
+    pub(crate) unsafe fn from_c(id: GattsEventId, e: *const ffi::ble_gatts_evt_t) -> Self {
+        let conn_handle = (*e).conn_handle;
+        let params = &(*e).params;
+        match id {
+            GattsEventId::Write => {
+                GattsEvent::Write(GattsEventWrite::from_c(conn_handle, &params.write))
+            }
+            GattsEventId::RwAuthorizeRequest => GattsEvent::RwAuthorizeRequest(
+                GattsEventRwAuthorizeRequest::from_c(conn_handle, &params.authorize_request),
+            ),
+            GattsEventId::SysAttrMissing => GattsEvent::SysAttrMissing(
+                GattsEventSysAttrMissing::from_c(conn_handle, &params.sys_attr_missing),
+            ),
+            // GattsEventId::Hvc => unimplemented!(),
+            GattsEventId::ScConfirm => {
+                GattsEvent::ScConfirm(GattsEventScConfirm::from_c(conn_handle))
+            }
+            GattsEventId::ExchangeMtuRequest => GattsEvent::ExchangeMtuRequest(
+                GattsEventExchangeMtuRequest::from_c(conn_handle, &params.exchange_mtu_request),
+            ),
+            // GattsEventId::Timeout => unimplemented!(),
+            GattsEventId::HvnTxComplete => GattsEvent::HvnTxComplete(
+                GattsEventHvnTxComplete::from_c(conn_handle, &params.hvn_tx_complete),
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct BleEvent {
     pub id: u16,
-    pub data: Option<BleEventData>,
+    pub data: BleEventData,
 }
 
 impl BleEvent {
@@ -183,28 +379,36 @@ impl BleEvent {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum BleEventData {
     Common(CommonEvent),
     Gap(GapEvent),
+    Gattc(GattcEvent),
+    Gatts(GattsEvent),
+    // Event id the driver doesn't know how to decode
+    UnknownEvent { id: u16 },
 }
 
 impl BleEventData {
-    pub unsafe fn from_c(e: *const ble_evt_t) -> Option<Self> {
+    pub unsafe fn from_c(e: *const ble_evt_t) -> Self {
         let id = (*e).header.evt_id;
 
         let event = if let Some(id) = CommonEventId::try_from(id) {
-            Some(Self::Common(CommonEvent::from_c(id, &(*e).evt.common_evt)))
+            Self::Common(CommonEvent::from_c(id, &(*e).evt.common_evt))
         } else if let Some(id) = GapEventId::try_from(id) {
-            Some(Self::Gap(GapEvent::from_c(id, &(*e).evt.gap_evt)))
+            Self::Gap(GapEvent::from_c(id, &(*e).evt.gap_evt))
+        } else if let Some(id) = GattcEventId::try_from(id) {
+            Self::Gattc(GattcEvent::from_c(id, &(*e).evt.gattc_evt))
+        } else if let Some(id) = GattsEventId::try_from(id) {
+            Self::Gatts(GattsEvent::from_c(id, &(*e).evt.gatts_evt))
         } else {
-            None
+            Self::UnknownEvent { id }
         };
 
         return event;
     }
 }
 
-pub trait BleEventDataType: Clone {
+pub trait BleEventDataType: Clone + Send {
     fn id() -> BleEventId;
 }
@@ -0,0 +1,185 @@
+use crate::ffi;
+
+pub const CONN_CFG_TAG: u8 = 1;
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleConfig {
+    pub max_peripheral_connections: u8,
+    pub max_central_connections: u8,
+    pub max_secured_central_connections: u8,
+    pub att_mtu: u16,
+    pub event_length: u16,
+    pub hvn_tx_queue_size: u8,
+    pub write_cmd_tx_queue_size: u8,
+    pub vendor_uuid_count: u8,
+    pub attr_table_size: u32,
+    pub service_changed: bool,
+}
+
+impl Default for BleConfig {
+    fn default() -> Self {
+        Self {
+            max_peripheral_connections: ffi::BLE_GAP_ROLE_COUNT_PERIPH_DEFAULT as u8,
+            max_central_connections: ffi::BLE_GAP_ROLE_COUNT_CENTRAL_DEFAULT as u8,
+            max_secured_central_connections: ffi::BLE_GAP_ROLE_COUNT_CENTRAL_SEC_DEFAULT as u8,
+            att_mtu: 247,
+            event_length: 6,
+            hvn_tx_queue_size: ffi::BLE_GATTS_HVN_TX_QUEUE_SIZE_DEFAULT as u8,
+            write_cmd_tx_queue_size: ffi::BLE_GATTC_WRITE_CMD_TX_QUEUE_SIZE_DEFAULT as u8,
+            vendor_uuid_count: 10,
+            attr_table_size: ffi::BLE_GATTS_ATTR_TAB_SIZE_DEFAULT,
+            service_changed: true,
+        }
+    }
+}
+
+impl BleConfig {
+    pub fn with_max_connections(mut self, peripheral: u8, central: u8) -> Self {
+        self.max_peripheral_connections = peripheral;
+        self.max_central_connections = central;
+        self.max_secured_central_connections = self.max_secured_central_connections.min(central);
+        self
+    }
+
+    pub fn with_max_secured_central_connections(mut self, count: u8) -> Self {
+        self.max_secured_central_connections = count;
+        self
+    }
+
+    pub fn with_att_mtu(mut self, att_mtu: u16) -> Self {
+        self.att_mtu = att_mtu;
+        self
+    }
+
+    pub fn with_event_length(mut self, event_length: u16) -> Self {
+        self.event_length = event_length;
+        self
+    }
+
+    pub fn with_hvn_tx_queue_size(mut self, queue_size: u8) -> Self {
+        self.hvn_tx_queue_size = queue_size;
+        self
+    }
+
+    pub fn with_write_cmd_tx_queue_size(mut self, queue_size: u8) -> Self {
+        self.write_cmd_tx_queue_size = queue_size;
+        self
+    }
+
+    pub fn with_vendor_uuid_count(mut self, count: u8) -> Self {
+        self.vendor_uuid_count = count;
+        self
+    }
+
+    pub fn with_attr_table_size(mut self, size: u32) -> Self {
+        self.attr_table_size = size;
+        self
+    }
+
+    pub fn with_service_changed(mut self, enabled: bool) -> Self {
+        self.service_changed = enabled;
+        self
+    }
+
+    pub fn max_connections(&self) -> u8 {
+        self.max_peripheral_connections + self.max_central_connections
+    }
+
+    // List of (cfg_id, config) pairs in the order they're passed to sd_ble_cfg_set
+    pub(crate) fn to_c_configs(&self) -> Vec<(u32, ffi::ble_cfg_t)> {
+        let gap_conn_cfg = ffi::ble_conn_cfg_t__bindgen_ty_1 {
+            gap_conn_cfg: ffi::ble_gap_conn_cfg_t {
+                conn_count: self.max_connections(),
+                event_length: self.event_length,
+            },
+        };
+        let gatt_conn_cfg = ffi::ble_conn_cfg_t__bindgen_ty_1 {
+            gatt_conn_cfg: ffi::ble_gatt_conn_cfg_t {
+                att_mtu: self.att_mtu,
+            },
+        };
+        let gattc_conn_cfg = ffi::ble_conn_cfg_t__bindgen_ty_1 {
+            gattc_conn_cfg: ffi::ble_gattc_conn_cfg_t {
+                write_cmd_tx_queue_size: self.write_cmd_tx_queue_size,
+            },
+        };
+        let gatts_conn_cfg = ffi::ble_conn_cfg_t__bindgen_ty_1 {
+            gatts_conn_cfg: ffi::ble_gatts_conn_cfg_t {
+                hvn_tx_queue_size: self.hvn_tx_queue_size,
+            },
+        };
+
+        vec![
+            (
+                ffi::BLE_CONN_CFGS_BLE_CONN_CFG_GAP as u32,
+                conn_cfg(gap_conn_cfg),
+            ),
+            (
+                ffi::BLE_CONN_CFGS_BLE_CONN_CFG_GATT as u32,
+                conn_cfg(gatt_conn_cfg),
+            ),
+            (
+                ffi::BLE_CONN_CFGS_BLE_CONN_CFG_GATTC as u32,
+                conn_cfg(gattc_conn_cfg),
+            ),
+            (
+                ffi::BLE_CONN_CFGS_BLE_CONN_CFG_GATTS as u32,
+                conn_cfg(gatts_conn_cfg),
+            ),
+            (
+                ffi::BLE_COMMON_CFGS_BLE_COMMON_CFG_VS_UUID as u32,
+                ffi::ble_cfg_t {
+                    common_cfg: ffi::ble_common_cfg_t {
+                        vs_uuid_cfg: ffi::ble_common_cfg_vs_uuid_t {
+                            vs_uuid_count: self.vendor_uuid_count,
+                        },
+                    },
+                },
+            ),
+            (
+                ffi::BLE_GAP_CFGS_BLE_GAP_CFG_ROLE_COUNT as u32,
+                ffi::ble_cfg_t {
+                    gap_cfg: ffi::ble_gap_cfg_t {
+                        role_count_cfg: ffi::ble_gap_cfg_role_count_t {
+                            periph_role_count: self.max_peripheral_connections,
+                            central_role_count: self.max_central_connections,
+                            central_sec_count: self.max_secured_central_connections,
+                        },
+                    },
+                },
+            ),
+            (
+                ffi::BLE_GATTS_CFGS_BLE_GATTS_CFG_SERVICE_CHANGED as u32,
+                ffi::ble_cfg_t {
+                    gatts_cfg: ffi::ble_gatts_cfg_t {
+                        service_changed: ffi::ble_gatts_cfg_service_changed_t {
+                            _bitfield_1: ffi::ble_gatts_cfg_service_changed_t::new_bitfield_1(
+                                self.service_changed as u8,
+                            ),
+                            _bitfield_align_1: [],
+                        },
+                    },
+                },
+            ),
+            (
+                ffi::BLE_GATTS_CFGS_BLE_GATTS_CFG_ATTR_TAB_SIZE as u32,
+                ffi::ble_cfg_t {
+                    gatts_cfg: ffi::ble_gatts_cfg_t {
+                        attr_tab_size: ffi::ble_gatts_cfg_attr_tab_size_t {
+                            attr_tab_size: self.attr_table_size,
+                        },
+                    },
+                },
+            ),
+        ]
+    }
+}
+
+fn conn_cfg(params: ffi::ble_conn_cfg_t__bindgen_ty_1) -> ffi::ble_cfg_t {
+    ffi::ble_cfg_t {
+        conn_cfg: ffi::ble_conn_cfg_t {
+            conn_cfg_tag: CONN_CFG_TAG,
+            params,
+        },
+    }
+}
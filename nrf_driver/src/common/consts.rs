@@ -2,3 +2,8 @@ use crate::common::types::ConnHandle;
 use crate::ffi;
 
 pub const CONN_HANDLE_INVALID: ConnHandle = ffi::BLE_CONN_HANDLE_INVALID as ConnHandle;
+pub const GATT_HANDLE_INVALID: u16 = ffi::BLE_GATT_HANDLE_INVALID as u16;
+
+pub const UUID_GAP_DEVICE_NAME: u16 = ffi::BLE_UUID_GAP_CHARACTERISTIC_DEVICE_NAME as u16;
+pub const UUID_DESCRIPTOR_CCCD: u16 = ffi::BLE_UUID_DESCRIPTOR_CLIENT_CHAR_CONFIG as u16;
+pub const UUID_GATT_SERVICE_CHANGED: u16 = ffi::BLE_UUID_GATT_CHARACTERISTIC_SERVICE_CHANGED as u16;
@@ -1,3 +1,4 @@
+pub mod config;
 pub mod consts;
 pub mod enums;
 pub mod events;
@@ -1 +1,46 @@
+use crate::ffi;
+
 pub type ConnHandle = u16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BleUuid {
+    pub value: u16,
+    pub uuid_type: u8,
+}
+
+impl BleUuid {
+    pub fn new(value: u16, uuid_type: u8) -> Self {
+        Self { value, uuid_type }
+    }
+
+    pub fn from_sig(value: u16) -> Self {
+        Self::new(value, ffi::BLE_UUID_TYPE_BLE as u8)
+    }
+
+    pub fn is_sig(&self) -> bool {
+        self.uuid_type == ffi::BLE_UUID_TYPE_BLE as u8
+    }
+
+    // Vendor-specific UUIDs that haven't been registered with the SoftDevice decode as unknown
+    pub fn is_unknown(&self) -> bool {
+        self.uuid_type == ffi::BLE_UUID_TYPE_UNKNOWN as u8
+    }
+}
+
+impl From<ffi::ble_uuid_t> for BleUuid {
+    fn from(uuid: ffi::ble_uuid_t) -> Self {
+        Self {
+            value: uuid.uuid,
+            uuid_type: uuid.type_,
+        }
+    }
+}
+
+impl Into<ffi::ble_uuid_t> for &BleUuid {
+    fn into(self) -> ffi::ble_uuid_t {
+        ffi::ble_uuid_t {
+            uuid: self.value,
+            type_: self.uuid_type,
+        }
+    }
+}
@@ -1,30 +1,49 @@
+use std::collections::HashMap;
 use std::ffi::CString;
 use std::ptr::{null, null_mut};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 
+use blatann_event::{Publisher, SubscriberFailure};
 use uuid::Uuid;
 
-use crate::ble_event::{BleEvent, BleEventId};
+use crate::ble_event::{BleEvent, BleEventData, BleEventId};
+use crate::common::config::{BleConfig, CONN_CFG_TAG};
+use crate::common::consts::CONN_HANDLE_INVALID;
 use crate::common::enums::BleHciStatus;
-use crate::common::types::ConnHandle;
+use crate::common::types::{BleUuid, ConnHandle};
 use crate::driver_events::NrfDriverEvents;
 use crate::error::{NrfError, NrfResult};
 use crate::ffi;
-use crate::gap::enums::BleGapPhy;
+use crate::gap::enums::{BleGapAppearance, BleGapPhy, BleGapSecStatus, BleGapSecurityMode};
 use crate::gap::types::*;
+use crate::gatt::enums::{BleGattCharProperties, BleGattHvxType, BleGattWriteOperation};
+use crate::gattc::types::BleGattcHandleRange;
+use crate::gatts::enums::BleGattsServiceType;
+use crate::gatts::types::{BleGattsCharacteristicHandles, BleGattsCharacteristicParams};
 use crate::manager::{event_handler, log_handler, status_handler};
 
+struct SecKeys {
+    enc_key: Box<ffi::ble_gap_enc_key_t>,
+    id_key: Box<ffi::ble_gap_id_key_t>,
+}
+
 #[allow(dead_code)]
 pub struct NrfDriver {
     pub port: String,
     pub id: usize,
     pub events: NrfDriverEvents,
+    // Errors and panics from subscribers of any of the driver's events
+    pub on_subscriber_error: Publisher<NrfDriver, SubscriberFailure>,
     adapter: Mutex<*mut ffi::adapter_t>,
     link_layer: Mutex<*mut ffi::data_link_layer_t>,
     transport_layer: Mutex<*mut ffi::transport_layer_t>,
     log_driver_comms: bool,
     is_open: AtomicBool,
+    conn_cfg_tag: AtomicU8,
+    att_mtu: AtomicU16,
+    // Key storage handed to the SoftDevice during pairing, filled in by the time auth status comes
+    sec_keys: Mutex<HashMap<ConnHandle, SecKeys>>,
 }
 
 impl NrfDriver {
@@ -49,7 +68,11 @@ impl NrfDriver {
                 transport_layer: Mutex::new(transport_layer),
                 log_driver_comms,
                 is_open: AtomicBool::new(false),
+                conn_cfg_tag: AtomicU8::new(ffi::BLE_CONN_CFG_TAG_DEFAULT as u8),
+                att_mtu: AtomicU16::new(ffi::BLE_GATT_ATT_MTU_DEFAULT as u16),
+                sec_keys: Mutex::new(HashMap::new()),
                 events: NrfDriverEvents::new(),
+                on_subscriber_error: Publisher::new("On Subscriber Error"),
             }
         }
     }
@@ -97,14 +120,46 @@ impl NrfDriver {
         }
     }
 
+    pub fn ble_cfg_set(&self, config: &BleConfig) -> NrfResult<()> {
+        for (cfg_id, cfg) in config.to_c_configs() {
+            let err = unsafe {
+                let adapter = self.adapter.lock().unwrap();
+                ffi::sd_ble_cfg_set(*adapter, cfg_id, &cfg, 0)
+            };
+            if err != ffi::NRF_SUCCESS {
+                error!("Failed to set config 0x{:02X}", cfg_id);
+                return Err(NrfError::new(err));
+            }
+        }
+        // Connections need to be started with the tag the connection configs were set with
+        self.conn_cfg_tag.store(CONN_CFG_TAG, Ordering::Relaxed);
+        self.att_mtu.store(config.att_mtu, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    // The largest ATT MTU connections can negotiate, as configured with ble_cfg_set()
+    pub fn att_mtu_max(&self) -> u16 {
+        self.att_mtu.load(Ordering::Relaxed)
+    }
+
     pub fn ble_enable(&self) -> NrfResult<()> {
         let mut ram_base = 0u32;
-        let _ram_base_ptr: *mut u32 = &mut ram_base;
         let err = unsafe {
             let adapter = self.adapter.lock().unwrap();
-            ffi::sd_ble_enable(*adapter, _ram_base_ptr)
+            ffi::sd_ble_enable(*adapter, &mut ram_base)
         };
 
+        if err == ffi::NRF_ERROR_NO_MEM {
+            error!(
+                "Insufficient RAM for the BLE configuration, required app RAM base: 0x{:08X}",
+                ram_base
+            );
+            let mut e = NrfError::new(err);
+            e.required_ram_base = Some(ram_base);
+            return Err(e);
+        }
+
         NrfError::make_result(err)
     }
 
@@ -117,6 +172,21 @@ impl NrfDriver {
         NrfError::make_result(err)
     }
 
+    // Base is the 128-bit UUID in little-endian byte order, returns the assigned uuid type
+    pub fn ble_uuid_vs_add(&self, uuid_base: &[u8; 16]) -> NrfResult<u8> {
+        let base = ffi::ble_uuid128_t {
+            uuid128: *uuid_base,
+        };
+        let mut uuid_type = 0u8;
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_uuid_vs_add(*adapter, &base, &mut uuid_type)
+        };
+
+        NrfError::make_result_typed(err, || uuid_type)
+    }
+
     pub fn ble_gap_disconnect(&self, conn_handle: ConnHandle) -> NrfResult<()> {
         let err = unsafe {
             let adapter = self.adapter.lock().unwrap();
@@ -156,6 +226,83 @@ impl NrfDriver {
         NrfError::make_result(err)
     }
 
+    pub fn ble_gap_device_name_set(
+        &self,
+        name: &str,
+        write_permission: BleGapSecurityMode,
+    ) -> NrfResult<()> {
+        let write_perm = write_permission.into();
+        let name = name.as_bytes();
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_device_name_set(*adapter, &write_perm, name.as_ptr(), name.len() as u16)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gap_device_name_get(&self) -> NrfResult<String> {
+        let mut name = [0u8; ffi::BLE_GAP_DEVNAME_MAX_LEN as usize];
+        let mut len = name.len() as u16;
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_device_name_get(*adapter, name.as_mut_ptr(), &mut len)
+        };
+
+        NrfError::make_result_typed(err, || {
+            String::from_utf8_lossy(&name[..len as usize]).into_owned()
+        })
+    }
+
+    pub fn ble_gap_appearance_set(&self, appearance: BleGapAppearance) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_appearance_set(*adapter, appearance.into())
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gap_appearance_get(&self) -> NrfResult<BleGapAppearance> {
+        let mut appearance = 0u16;
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_appearance_get(*adapter, &mut appearance)
+        };
+
+        NrfError::make_result_typed(err, || BleGapAppearance::from_u16_or_unknown(appearance))
+    }
+
+    pub fn ble_gap_ppcp_set(&self, conn_params: &BleGapConnParams) -> NrfResult<()> {
+        let params = conn_params.into();
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_ppcp_set(*adapter, &params)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gap_ppcp_get(&self) -> NrfResult<BleGapConnParams> {
+        let mut params = ffi::ble_gap_conn_params_t {
+            min_conn_interval: 0,
+            max_conn_interval: 0,
+            slave_latency: 0,
+            conn_sup_timeout: 0,
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_ppcp_get(*adapter, &mut params)
+        };
+
+        NrfError::make_result_typed(err, || params.into())
+    }
+
     pub fn ble_gap_adv_data_set(
         &self,
         adv_data: &Option<Vec<u8>>,
@@ -189,7 +336,8 @@ impl NrfDriver {
 
         let err = unsafe {
             let adapter = self.adapter.lock().unwrap();
-            ffi::sd_ble_gap_adv_start(*adapter, &params, 0)
+            let conn_cfg_tag = self.conn_cfg_tag.load(Ordering::Relaxed);
+            ffi::sd_ble_gap_adv_start(*adapter, &params, conn_cfg_tag)
         };
 
         NrfError::make_result(err)
@@ -237,18 +385,543 @@ impl NrfDriver {
         NrfError::make_result(err)
     }
 
+    // Keys are exchanged for our encryption key and the peer's identity key, retrieve them with ble_gap_sec_keys_take()
+    pub fn ble_gap_sec_params_reply(
+        &self,
+        conn_handle: ConnHandle,
+        sec_status: BleGapSecStatus,
+        sec_params: Option<&BleGapSecParams>,
+    ) -> NrfResult<()> {
+        let params: Option<ffi::ble_gap_sec_params_t> = sec_params.map(|p| p.into());
+        let mut enc_key = Box::new(ffi::ble_gap_enc_key_t {
+            enc_info: (&BleGapEncInfo::default()).into(),
+            master_id: ffi::ble_gap_master_id_t {
+                ediv: 0,
+                rand: [0; 8],
+            },
+        });
+        let mut id_key = Box::new(ffi::ble_gap_id_key_t {
+            id_info: ffi::ble_gap_irk_t {
+                irk: [0; ffi::BLE_GAP_SEC_KEY_LEN as usize],
+            },
+            id_addr_info: ffi::ble_gap_addr_t {
+                _bitfield_1: ffi::ble_gap_addr_t::new_bitfield_1(0, 0),
+                addr: [0; 6],
+                _bitfield_align_1: [],
+            },
+        });
+        let keyset = ffi::ble_gap_sec_keyset_t {
+            keys_own: ffi::ble_gap_sec_keys_t {
+                p_enc_key: &mut *enc_key,
+                p_id_key: null_mut(),
+                p_sign_key: null_mut(),
+                p_pk: null_mut(),
+            },
+            keys_peer: ffi::ble_gap_sec_keys_t {
+                p_enc_key: null_mut(),
+                p_id_key: &mut *id_key,
+                p_sign_key: null_mut(),
+                p_pk: null_mut(),
+            },
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_sec_params_reply(
+                *adapter,
+                conn_handle,
+                sec_status as u8,
+                params.as_ref().map_or(null(), |p| p),
+                if params.is_some() { &keyset } else { null() },
+            )
+        };
+
+        if err == ffi::NRF_SUCCESS && params.is_some() {
+            // The boxes keep the keys' addresses stable until the procedure completes
+            let mut sec_keys = self.sec_keys.lock().unwrap();
+            sec_keys.insert(conn_handle, SecKeys { enc_key, id_key });
+        }
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gap_sec_keys_take(&self, conn_handle: ConnHandle) -> Option<BleGapSecKeys> {
+        let mut sec_keys = self.sec_keys.lock().unwrap();
+        sec_keys.remove(&conn_handle).map(|keys| BleGapSecKeys {
+            own_enc_key: (*keys.enc_key).into(),
+            peer_id_key: (*keys.id_key).into(),
+        })
+    }
+
+    // Replies with no key if the peer isn't bonded
+    pub fn ble_gap_sec_info_reply(
+        &self,
+        conn_handle: ConnHandle,
+        enc_info: Option<&BleGapEncInfo>,
+    ) -> NrfResult<()> {
+        let enc_info: Option<ffi::ble_gap_enc_info_t> = enc_info.map(|e| e.into());
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_sec_info_reply(
+                *adapter,
+                conn_handle,
+                enc_info.as_ref().map_or(null(), |e| e),
+                null(),
+                null(),
+            )
+        };
+
+        NrfError::make_result(err)
+    }
+
+    // As a peripheral this sends a security request, the central starts the pairing
+    pub fn ble_gap_authenticate(
+        &self,
+        conn_handle: ConnHandle,
+        sec_params: &BleGapSecParams,
+    ) -> NrfResult<()> {
+        let params = sec_params.into();
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gap_authenticate(*adapter, conn_handle, &params)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_primary_services_discover(
+        &self,
+        conn_handle: ConnHandle,
+        start_handle: u16,
+        service_uuid: Option<&BleUuid>,
+    ) -> NrfResult<()> {
+        let uuid: Option<ffi::ble_uuid_t> = service_uuid.map(|u| u.into());
+        let uuid_ptr = match &uuid {
+            None => null(),
+            Some(u) => u as *const ffi::ble_uuid_t,
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_primary_services_discover(
+                *adapter,
+                conn_handle,
+                start_handle,
+                uuid_ptr,
+            )
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_characteristics_discover(
+        &self,
+        conn_handle: ConnHandle,
+        handle_range: &BleGattcHandleRange,
+    ) -> NrfResult<()> {
+        let range = handle_range.into();
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_characteristics_discover(*adapter, conn_handle, &range)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_descriptors_discover(
+        &self,
+        conn_handle: ConnHandle,
+        handle_range: &BleGattcHandleRange,
+    ) -> NrfResult<()> {
+        let range = handle_range.into();
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_descriptors_discover(*adapter, conn_handle, &range)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_read(
+        &self,
+        conn_handle: ConnHandle,
+        handle: u16,
+        offset: u16,
+    ) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_read(*adapter, conn_handle, handle, offset)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_write(
+        &self,
+        conn_handle: ConnHandle,
+        write_op: BleGattWriteOperation,
+        handle: u16,
+        offset: u16,
+        data: &[u8],
+    ) -> NrfResult<()> {
+        let params = ffi::ble_gattc_write_params_t {
+            write_op: write_op as u8,
+            flags: 0,
+            handle,
+            offset,
+            len: data.len() as u16,
+            p_value: data.as_ptr(),
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_write(*adapter, conn_handle, &params)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_hv_confirm(&self, conn_handle: ConnHandle, handle: u16) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_hv_confirm(*adapter, conn_handle, handle)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gattc_exchange_mtu_request(
+        &self,
+        conn_handle: ConnHandle,
+        client_rx_mtu: u16,
+    ) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gattc_exchange_mtu_request(*adapter, conn_handle, client_rx_mtu)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    // Initializes the system attributes (CCCDs) of the connection to their default values
+    pub fn ble_gatts_sys_attr_set_default(&self, conn_handle: ConnHandle) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_sys_attr_set(*adapter, conn_handle, null(), 0, 0)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    // Restores system attributes previously read with ble_gatts_sys_attr_get
+    pub fn ble_gatts_sys_attr_set(&self, conn_handle: ConnHandle, data: &[u8]) -> NrfResult<()> {
+        // The SoftDevice wants a word aligned buffer
+        let mut buffer = vec![0u32; (data.len() + 3) / 4];
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                buffer.as_mut_ptr() as *mut u8,
+                data.len(),
+            );
+        }
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_sys_attr_set(
+                *adapter,
+                conn_handle,
+                buffer.as_ptr() as *const u8,
+                data.len() as u16,
+                0,
+            )
+        };
+
+        NrfError::make_result(err)
+    }
+
+    // Reads the system attributes (CCCDs) of the connection, still valid in the disconnected event
+    pub fn ble_gatts_sys_attr_get(&self, conn_handle: ConnHandle) -> NrfResult<Vec<u8>> {
+        let adapter = self.adapter.lock().unwrap();
+
+        // A null buffer returns the required length
+        let mut len = 0u16;
+        let err = unsafe {
+            ffi::sd_ble_gatts_sys_attr_get(*adapter, conn_handle, null_mut(), &mut len, 0)
+        };
+        NrfError::make_result(err)?;
+
+        let mut buffer = vec![0u32; (len as usize + 3) / 4];
+        let err = unsafe {
+            ffi::sd_ble_gatts_sys_attr_get(
+                *adapter,
+                conn_handle,
+                buffer.as_mut_ptr() as *mut u8,
+                &mut len,
+                0,
+            )
+        };
+
+        NrfError::make_result_typed(err, || {
+            let bytes = unsafe {
+                std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 4)
+            };
+            bytes[..len as usize].to_vec()
+        })
+    }
+
+    pub fn ble_gatts_service_add(
+        &self,
+        service_type: BleGattsServiceType,
+        uuid: &BleUuid,
+    ) -> NrfResult<u16> {
+        let uuid = uuid.into();
+        let mut handle = 0_u16;
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_service_add(*adapter, service_type as u8, &uuid, &mut handle)
+        };
+
+        NrfError::make_result_typed(err, || handle)
+    }
+
+    pub fn ble_gatts_characteristic_add(
+        &self,
+        service_handle: u16,
+        params: &BleGattsCharacteristicParams,
+    ) -> NrfResult<BleGattsCharacteristicHandles> {
+        let uuid = (&params.uuid).into();
+        let value_md = attr_metadata(
+            params.read_permission,
+            params.write_permission,
+            params.variable_length,
+            params.write_authorization,
+        );
+        // The CCCD is always readable, writing it requires the same security as reading the value
+        let cccd_md = attr_metadata(
+            BleGapSecurityMode::Open,
+            params.read_permission,
+            false,
+            false,
+        );
+        let user_desc_md = attr_metadata(
+            BleGapSecurityMode::Open,
+            BleGapSecurityMode::NoAccess,
+            false,
+            false,
+        );
+
+        let user_desc = params.user_description.as_ref().map(|d| d.as_bytes());
+        let user_desc_len = user_desc.map_or(0, |d| d.len() as u16);
+        let has_cccd = params
+            .properties
+            .intersects(BleGattCharProperties::NOTIFY | BleGattCharProperties::INDICATE);
+
+        let char_md = ffi::ble_gatts_char_md_t {
+            char_props: params.properties.into(),
+            char_ext_props: ffi::ble_gatt_char_ext_props_t {
+                _bitfield_1: ffi::ble_gatt_char_ext_props_t::new_bitfield_1(0, 0),
+                _bitfield_align_1: [],
+            },
+            p_char_user_desc: user_desc.map_or(null(), |d| d.as_ptr()),
+            char_user_desc_max_size: user_desc_len,
+            char_user_desc_size: user_desc_len,
+            p_char_pf: null(),
+            p_user_desc_md: if user_desc.is_some() {
+                &user_desc_md
+            } else {
+                null()
+            },
+            p_cccd_md: if has_cccd { &cccd_md } else { null() },
+            p_sccd_md: null(),
+        };
+
+        let mut initial_value = params.initial_value.clone();
+        let attr = ffi::ble_gatts_attr_t {
+            p_uuid: &uuid,
+            p_attr_md: &value_md,
+            init_len: initial_value.len() as u16,
+            init_offs: 0,
+            max_len: params.max_length,
+            p_value: initial_value.as_mut_ptr(),
+        };
+        let mut handles = ffi::ble_gatts_char_handles_t {
+            value_handle: 0,
+            user_desc_handle: 0,
+            cccd_handle: 0,
+            sccd_handle: 0,
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_characteristic_add(
+                *adapter,
+                service_handle,
+                &char_md,
+                &attr,
+                &mut handles,
+            )
+        };
+
+        NrfError::make_result_typed(err, || handles.into())
+    }
+
+    pub fn ble_gatts_descriptor_add(
+        &self,
+        char_handle: u16,
+        uuid: &BleUuid,
+        read_permission: BleGapSecurityMode,
+        write_permission: BleGapSecurityMode,
+        max_length: u16,
+        initial_value: &[u8],
+    ) -> NrfResult<u16> {
+        let uuid = uuid.into();
+        let attr_md = attr_metadata(read_permission, write_permission, true, false);
+        let mut initial_value = initial_value.to_vec();
+        let attr = ffi::ble_gatts_attr_t {
+            p_uuid: &uuid,
+            p_attr_md: &attr_md,
+            init_len: initial_value.len() as u16,
+            init_offs: 0,
+            max_len: max_length,
+            p_value: initial_value.as_mut_ptr(),
+        };
+        let mut handle = 0_u16;
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_descriptor_add(*adapter, char_handle, &attr, &mut handle)
+        };
+
+        NrfError::make_result_typed(err, || handle)
+    }
+
+    pub fn ble_gatts_value_set(&self, handle: u16, data: &[u8]) -> NrfResult<()> {
+        let mut value = ffi::ble_gatts_value_t {
+            len: data.len() as u16,
+            offset: 0,
+            p_value: data.as_ptr() as *mut u8,
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_value_set(*adapter, CONN_HANDLE_INVALID, handle, &mut value)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gatts_value_get(&self, handle: u16) -> NrfResult<Vec<u8>> {
+        let mut data = [0u8; ffi::BLE_GATTS_VAR_ATTR_LEN_MAX as usize];
+        let mut value = ffi::ble_gatts_value_t {
+            len: data.len() as u16,
+            offset: 0,
+            p_value: data.as_mut_ptr(),
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_value_get(*adapter, CONN_HANDLE_INVALID, handle, &mut value)
+        };
+
+        NrfError::make_result_typed(err, || data[..value.len as usize].to_vec())
+    }
+
+    pub fn ble_gatts_hvx(
+        &self,
+        conn_handle: ConnHandle,
+        hvx_type: BleGattHvxType,
+        handle: u16,
+        data: &[u8],
+    ) -> NrfResult<()> {
+        let mut len = data.len() as u16;
+        let params = ffi::ble_gatts_hvx_params_t {
+            handle,
+            type_: hvx_type as u8,
+            offset: 0,
+            p_len: &mut len,
+            p_data: data.as_ptr(),
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_hvx(*adapter, conn_handle, &params)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gatts_service_changed(
+        &self,
+        conn_handle: ConnHandle,
+        start_handle: u16,
+        end_handle: u16,
+    ) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_service_changed(*adapter, conn_handle, start_handle, end_handle)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    // The written data is only applied to the value if the write is accepted
+    pub fn ble_gatts_write_authorize_reply(
+        &self,
+        conn_handle: ConnHandle,
+        gatt_status: u16,
+        offset: u16,
+        data: &[u8],
+    ) -> NrfResult<()> {
+        let update = gatt_status == ffi::BLE_GATT_STATUS_SUCCESS as u16;
+        let params = ffi::ble_gatts_rw_authorize_reply_params_t {
+            type_: ffi::BLE_GATTS_AUTHORIZE_TYPE_WRITE as u8,
+            params: ffi::ble_gatts_rw_authorize_reply_params_t__bindgen_ty_1 {
+                write: ffi::ble_gatts_authorize_params_t {
+                    gatt_status,
+                    offset,
+                    len: data.len() as u16,
+                    p_data: data.as_ptr(),
+                    _bitfield_1: ffi::ble_gatts_authorize_params_t::new_bitfield_1(update as u8),
+                    _bitfield_align_1: [],
+                },
+            },
+        };
+
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_rw_authorize_reply(*adapter, conn_handle, &params)
+        };
+
+        NrfError::make_result(err)
+    }
+
+    pub fn ble_gatts_exchange_mtu_reply(
+        &self,
+        conn_handle: ConnHandle,
+        server_rx_mtu: u16,
+    ) -> NrfResult<()> {
+        let err = unsafe {
+            let adapter = self.adapter.lock().unwrap();
+            ffi::sd_ble_gatts_exchange_mtu_reply(*adapter, conn_handle, server_rx_mtu)
+        };
+
+        NrfError::make_result(err)
+    }
+
     pub fn unsubscribe_from_event(&self, event_id: BleEventId, sub_id: Uuid) {
         self.events.unsubscribe(event_id, sub_id)
     }
 
     pub(crate) fn process_event(self: Arc<Self>, ble_event: BleEvent) {
         debug!("[{}] Event: {:?}", self.port, ble_event);
-        match ble_event.data {
-            Some(e) => self.events.dispatch(self.clone(), e),
-            None => {
-                warn!("Unable to decode event, id {}", ble_event.id);
-            }
+        if let BleEventData::UnknownEvent { id } = ble_event.data {
+            warn!("Unable to decode event, id {}", id);
         }
+        self.events.dispatch(self.clone(), ble_event.data);
     }
 }
 
@@ -266,3 +939,22 @@ impl Drop for NrfDriver {
 unsafe impl Send for NrfDriver {}
 
 unsafe impl Sync for NrfDriver {}
+
+fn attr_metadata(
+    read_permission: BleGapSecurityMode,
+    write_permission: BleGapSecurityMode,
+    variable_length: bool,
+    write_authorization: bool,
+) -> ffi::ble_gatts_attr_md_t {
+    ffi::ble_gatts_attr_md_t {
+        read_perm: read_permission.into(),
+        write_perm: write_permission.into(),
+        _bitfield_1: ffi::ble_gatts_attr_md_t::new_bitfield_1(
+            variable_length as u8,
+            ffi::BLE_GATTS_VLOC_STACK as u8,
+            0,
+            write_authorization as u8,
+        ),
+        _bitfield_align_1: [],
+    }
+}
@@ -1,12 +1,14 @@
 use std::sync::Arc;
 
-use blatann_event::{Publisher, Subscribable, Subscriber, Unsubscribable};
+use blatann_event::{Executor, Priority, Publisher, Subscribable, Subscriber, Unsubscribable};
 use uuid::Uuid;
 
 use crate::ble_event::*;
 use crate::common::events::*;
 use crate::driver::NrfDriver;
 use crate::gap::events::*;
+use crate::gattc::events::*;
+use crate::gatts::events::*;
 use std::collections::HashMap;
 
 trait NrfPublisherType: Unsubscribable {
@@ -19,11 +21,15 @@ pub struct NrfEventPublisher<TEvent: BleEventDataType> {
     publisher: Publisher<NrfDriver, TEvent>,
 }
 
-impl<TEvent: BleEventDataType> NrfEventPublisher<TEvent> {
+impl<TEvent: BleEventDataType + 'static> NrfEventPublisher<TEvent> {
     pub fn new(name: &str) -> Self {
+        let publisher = Publisher::new(name);
+        publisher.set_error_handler(|driver: Arc<NrfDriver>, failure| {
+            driver.on_subscriber_error.dispatch(driver.clone(), failure)
+        });
         Self {
             id: TEvent::id(),
-            publisher: Publisher::new(name),
+            publisher,
         }
     }
 
@@ -35,6 +41,11 @@ impl<TEvent: BleEventDataType> NrfEventPublisher<TEvent> {
         self.publisher.name()
     }
 
+    // Moves slow subscribers off the driver's event thread
+    pub fn set_executor(&self, executor: Executor) {
+        self.publisher.set_executor(executor)
+    }
+
     fn dispatch(&self, sender: Arc<NrfDriver>, event: TEvent) {
         self.publisher.dispatch(sender, event)
     }
@@ -62,6 +73,14 @@ impl<TEvent: BleEventDataType> Subscribable<NrfDriver, TEvent> for NrfEventPubli
     fn subscribe_once(&self, subscriber: Arc<dyn Subscriber<NrfDriver, TEvent>>) -> Uuid {
         self.publisher.subscribe_once(subscriber)
     }
+
+    fn subscribe_with_priority(
+        &self,
+        subscriber: Arc<dyn Subscriber<NrfDriver, TEvent>>,
+        priority: Priority,
+    ) -> Uuid {
+        self.publisher.subscribe_with_priority(subscriber, priority)
+    }
 }
 
 impl<TEvent: BleEventDataType> Unsubscribable for NrfEventPublisher<TEvent> {
@@ -72,31 +91,82 @@ impl<TEvent: BleEventDataType> Unsubscribable for NrfEventPublisher<TEvent> {
 
 #[allow(dead_code)]
 pub struct NrfDriverEvents {
+    // Every event from the driver, dispatched before the typed publishers below
+    pub all: Publisher<NrfDriver, BleEventData>,
     pub user_mem_request: NrfEventPublisher<CommonEventMemRequest>,
     pub user_mem_release: NrfEventPublisher<CommonEventMemRelease>,
     pub connected: NrfEventPublisher<GapEventConnected>,
     pub disconnected: NrfEventPublisher<GapEventDisconnected>,
+    pub sec_params_request: NrfEventPublisher<GapEventSecParamsRequest>,
+    pub sec_info_request: NrfEventPublisher<GapEventSecInfoRequest>,
+    pub auth_status: NrfEventPublisher<GapEventAuthStatus>,
+    pub conn_sec_update: NrfEventPublisher<GapEventConnSecUpdate>,
     pub gap_timeout: NrfEventPublisher<GapEventTimeout>,
     pub phy_update_request: NrfEventPublisher<GapEventPhyUpdateRequest>,
     pub phy_update: NrfEventPublisher<GapEventPhyUpdate>,
     pub data_length_update_request: NrfEventPublisher<GapEventDataLengthUpdateRequest>,
     pub data_length_update: NrfEventPublisher<GapEventDataLengthUpdate>,
+    pub primary_service_discovery_response:
+        NrfEventPublisher<GattcEventPrimaryServiceDiscoveryResponse>,
+    pub characteristic_discovery_response:
+        NrfEventPublisher<GattcEventCharacteristicDiscoveryResponse>,
+    pub descriptor_discovery_response: NrfEventPublisher<GattcEventDescriptorDiscoveryResponse>,
+    pub read_response: NrfEventPublisher<GattcEventReadResponse>,
+    pub write_response: NrfEventPublisher<GattcEventWriteResponse>,
+    pub hvx: NrfEventPublisher<GattcEventHvx>,
+    pub write_cmd_tx_complete: NrfEventPublisher<GattcEventWriteCmdTxComplete>,
+    pub exchange_mtu_response: NrfEventPublisher<GattcEventExchangeMtuResponse>,
+    pub gatts_write: NrfEventPublisher<GattsEventWrite>,
+    pub rw_authorize_request: NrfEventPublisher<GattsEventRwAuthorizeRequest>,
+    pub sys_attr_missing: NrfEventPublisher<GattsEventSysAttrMissing>,
+    pub service_changed_confirm: NrfEventPublisher<GattsEventScConfirm>,
+    pub hvn_tx_complete: NrfEventPublisher<GattsEventHvnTxComplete>,
+    pub exchange_mtu_request: NrfEventPublisher<GattsEventExchangeMtuRequest>,
 }
 
 impl NrfDriverEvents {
     pub(crate) fn new() -> Self {
+        let all = Publisher::new("All Events");
+        all.set_error_handler(|driver: Arc<NrfDriver>, failure| {
+            driver.on_subscriber_error.dispatch(driver.clone(), failure)
+        });
         Self {
+            all,
             // Common
             user_mem_request: NrfEventPublisher::new("User Mem Request"),
             user_mem_release: NrfEventPublisher::new("User Mem Release"),
             // Gap
             connected: NrfEventPublisher::new("Connected"),
             disconnected: NrfEventPublisher::new("Disconnected"),
+            sec_params_request: NrfEventPublisher::new("Sec Params Request"),
+            sec_info_request: NrfEventPublisher::new("Sec Info Request"),
+            auth_status: NrfEventPublisher::new("Auth Status"),
+            conn_sec_update: NrfEventPublisher::new("Conn Sec Update"),
             gap_timeout: NrfEventPublisher::new("Gap Timeout"),
             phy_update_request: NrfEventPublisher::new("Phy Update Request"),
             phy_update: NrfEventPublisher::new("Phy Update"),
             data_length_update_request: NrfEventPublisher::new("Data Length Update Request"),
             data_length_update: NrfEventPublisher::new("Data Length Update"),
+            // Gattc
+            primary_service_discovery_response: NrfEventPublisher::new(
+                "Primary Service Discovery Response",
+            ),
+            characteristic_discovery_response: NrfEventPublisher::new(
+                "Characteristic Discovery Response",
+            ),
+            descriptor_discovery_response: NrfEventPublisher::new("Descriptor Discovery Response"),
+            read_response: NrfEventPublisher::new("Read Response"),
+            write_response: NrfEventPublisher::new("Write Response"),
+            hvx: NrfEventPublisher::new("Hvx"),
+            write_cmd_tx_complete: NrfEventPublisher::new("Write Cmd Tx Complete"),
+            exchange_mtu_response: NrfEventPublisher::new("Exchange Mtu Response"),
+            // Gatts
+            gatts_write: NrfEventPublisher::new("Gatts Write"),
+            rw_authorize_request: NrfEventPublisher::new("Rw Authorize Request"),
+            sys_attr_missing: NrfEventPublisher::new("Sys Attr Missing"),
+            service_changed_confirm: NrfEventPublisher::new("Service Changed Confirm"),
+            hvn_tx_complete: NrfEventPublisher::new("Hvn Tx Complete"),
+            exchange_mtu_request: NrfEventPublisher::new("Exchange Mtu Request"),
         }
     }
 
@@ -107,15 +177,34 @@ impl NrfDriverEvents {
             &self.gap_timeout,
             &self.connected,
             &self.disconnected,
+            &self.sec_params_request,
+            &self.sec_info_request,
+            &self.auth_status,
+            &self.conn_sec_update,
             &self.gap_timeout,
             &self.phy_update_request,
             &self.phy_update,
             &self.data_length_update_request,
             &self.data_length_update,
+            &self.primary_service_discovery_response,
+            &self.characteristic_discovery_response,
+            &self.descriptor_discovery_response,
+            &self.read_response,
+            &self.write_response,
+            &self.hvx,
+            &self.write_cmd_tx_complete,
+            &self.exchange_mtu_response,
+            &self.gatts_write,
+            &self.rw_authorize_request,
+            &self.sys_attr_missing,
+            &self.service_changed_confirm,
+            &self.hvn_tx_complete,
+            &self.exchange_mtu_request,
         ]
     }
 
     pub(crate) fn dispatch(&self, driver: Arc<NrfDriver>, ble_event: BleEventData) {
+        self.all.dispatch(driver.clone(), ble_event.clone());
         match ble_event {
             BleEventData::Common(sub_event) => match sub_event {
                 CommonEvent::MemRequest(e) => self.user_mem_request.dispatch(driver, e),
@@ -125,6 +214,10 @@ impl NrfDriverEvents {
                 GapEvent::Timeout(e) => self.gap_timeout.dispatch(driver, e),
                 GapEvent::Connected(e) => self.connected.dispatch(driver, e),
                 GapEvent::Disconnected(e) => self.disconnected.dispatch(driver, e),
+                GapEvent::SecParamsRequest(e) => self.sec_params_request.dispatch(driver, e),
+                GapEvent::SecInfoRequest(e) => self.sec_info_request.dispatch(driver, e),
+                GapEvent::AuthStatus(e) => self.auth_status.dispatch(driver, e),
+                GapEvent::ConnSecUpdate(e) => self.conn_sec_update.dispatch(driver, e),
                 GapEvent::PhyUpdateRequest(e) => self.phy_update_request.dispatch(driver, e),
                 GapEvent::PhyUpdate(e) => self.phy_update.dispatch(driver, e),
                 GapEvent::DataLengthUpdateRequest(e) => {
@@ -132,6 +225,33 @@ impl NrfDriverEvents {
                 }
                 GapEvent::DataLengthUpdate(e) => self.data_length_update.dispatch(driver, e),
             },
+            BleEventData::Gattc(sub_event) => match sub_event {
+                GattcEvent::PrimaryServiceDiscoveryResponse(e) => {
+                    self.primary_service_discovery_response.dispatch(driver, e)
+                }
+                GattcEvent::CharacteristicDiscoveryResponse(e) => {
+                    self.characteristic_discovery_response.dispatch(driver, e)
+                }
+                GattcEvent::DescriptorDiscoveryResponse(e) => {
+                    self.descriptor_discovery_response.dispatch(driver, e)
+                }
+                GattcEvent::ReadResponse(e) => self.read_response.dispatch(driver, e),
+                GattcEvent::WriteResponse(e) => self.write_response.dispatch(driver, e),
+                GattcEvent::Hvx(e) => self.hvx.dispatch(driver, e),
+                GattcEvent::WriteCmdTxComplete(e) => self.write_cmd_tx_complete.dispatch(driver, e),
+                GattcEvent::ExchangeMtuResponse(e) => {
+                    self.exchange_mtu_response.dispatch(driver, e)
+                }
+            },
+            BleEventData::Gatts(sub_event) => match sub_event {
+                GattsEvent::Write(e) => self.gatts_write.dispatch(driver, e),
+                GattsEvent::RwAuthorizeRequest(e) => self.rw_authorize_request.dispatch(driver, e),
+                GattsEvent::SysAttrMissing(e) => self.sys_attr_missing.dispatch(driver, e),
+                GattsEvent::ScConfirm(e) => self.service_changed_confirm.dispatch(driver, e),
+                GattsEvent::HvnTxComplete(e) => self.hvn_tx_complete.dispatch(driver, e),
+                GattsEvent::ExchangeMtuRequest(e) => self.exchange_mtu_request.dispatch(driver, e),
+            },
+            BleEventData::UnknownEvent { .. } => {}
         };
     }
 
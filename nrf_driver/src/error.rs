@@ -80,6 +80,7 @@ impl NrfErrorType {
         NrfError {
             error_type: self,
             error_code: self as u32,
+            required_ram_base: None,
         }
     }
 
@@ -87,6 +88,7 @@ impl NrfErrorType {
         let result = NrfError {
             error_type: self,
             error_code: self as u32,
+            required_ram_base: None,
         };
         if let NrfErrorType::Success = self {
             Ok(())
@@ -102,6 +104,7 @@ impl NrfErrorType {
         let result = NrfError {
             error_type: self,
             error_code: self as u32,
+            required_ram_base: None,
         };
         if let NrfErrorType::Success = self {
             Ok(f())
@@ -115,6 +118,8 @@ impl NrfErrorType {
 pub struct NrfError {
     pub error_type: NrfErrorType,
     pub error_code: u32,
+    // Set when sd_ble_enable fails with NoMem: the app RAM base the BLE configuration needs
+    pub required_ram_base: Option<u32>,
 }
 
 impl NrfError {
@@ -122,6 +127,7 @@ impl NrfError {
         Self {
             error_type: NrfErrorType::from(err),
             error_code: err,
+            required_ram_base: None,
         }
     }
 
@@ -145,7 +151,13 @@ impl NrfError {
     }
 
     pub fn to_string(&self) -> String {
-        return format!("{:?}({})", self.error_type, self.error_code);
+        return match self.required_ram_base {
+            Some(ram_base) => format!(
+                "{:?}({}), required app RAM base: 0x{:08X}",
+                self.error_type, self.error_code, ram_base
+            ),
+            None => format!("{:?}({})", self.error_type, self.error_code),
+        };
     }
 
     pub fn to_result(self) -> NrfResult<()> {
@@ -173,3 +185,5 @@ impl fmt::Display for NrfError {
         write!(f, "{}", self.to_string())
     }
 }
+
+impl std::error::Error for NrfError {}
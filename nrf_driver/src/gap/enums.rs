@@ -1,7 +1,9 @@
+use num_traits::FromPrimitive;
+
 use crate::ffi;
 
 #[repr(u8)]
-#[derive(FromPrimitive, Copy, Clone, Debug)]
+#[derive(FromPrimitive, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum BleGapAddressType {
     Public = ffi::BLE_GAP_ADDR_TYPE_PUBLIC as u8,
     Static = ffi::BLE_GAP_ADDR_TYPE_RANDOM_STATIC as u8,
@@ -65,6 +67,12 @@ pub enum BleAdvDataType {
     ManufacturerSpecificData = ffi::BLE_GAP_AD_TYPE_MANUFACTURER_SPECIFIC_DATA as u8,
 }
 
+impl BleAdvDataType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        FromPrimitive::from_u8(value)
+    }
+}
+
 impl From<BleAdvDataType> for u8 {
     fn from(value: BleAdvDataType) -> Self {
         value as u8
@@ -80,7 +88,7 @@ pub enum BleGapRole {
 }
 
 bitflags! {
-    #[derive(Copy, Clone, Debug)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
     pub struct BleGapPhy: u8 {
         const AUTO = ffi::BLE_GAP_PHY_AUTO as u8;
         const ONE_MBPS = ffi::BLE_GAP_PHY_1MBPS as u8;
@@ -94,3 +102,114 @@ impl BleGapPhy {
         Self::from_bits(value).unwrap_or_else(|| BleGapPhy::AUTO)
     }
 }
+
+#[repr(u16)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGapAppearance {
+    Unknown = ffi::BLE_APPEARANCE_UNKNOWN as u16,
+    GenericPhone = ffi::BLE_APPEARANCE_GENERIC_PHONE as u16,
+    GenericComputer = ffi::BLE_APPEARANCE_GENERIC_COMPUTER as u16,
+    GenericWatch = ffi::BLE_APPEARANCE_GENERIC_WATCH as u16,
+    WatchSportsWatch = ffi::BLE_APPEARANCE_WATCH_SPORTS_WATCH as u16,
+    GenericClock = ffi::BLE_APPEARANCE_GENERIC_CLOCK as u16,
+    GenericDisplay = ffi::BLE_APPEARANCE_GENERIC_DISPLAY as u16,
+    GenericRemoteControl = ffi::BLE_APPEARANCE_GENERIC_REMOTE_CONTROL as u16,
+    GenericEyeGlasses = ffi::BLE_APPEARANCE_GENERIC_EYE_GLASSES as u16,
+    GenericTag = ffi::BLE_APPEARANCE_GENERIC_TAG as u16,
+    GenericKeyring = ffi::BLE_APPEARANCE_GENERIC_KEYRING as u16,
+    GenericMediaPlayer = ffi::BLE_APPEARANCE_GENERIC_MEDIA_PLAYER as u16,
+    GenericBarcodeScanner = ffi::BLE_APPEARANCE_GENERIC_BARCODE_SCANNER as u16,
+    GenericThermometer = ffi::BLE_APPEARANCE_GENERIC_THERMOMETER as u16,
+    ThermometerEar = ffi::BLE_APPEARANCE_THERMOMETER_EAR as u16,
+    GenericHeartRateSensor = ffi::BLE_APPEARANCE_GENERIC_HEART_RATE_SENSOR as u16,
+    HeartRateSensorHeartRateBelt = ffi::BLE_APPEARANCE_HEART_RATE_SENSOR_HEART_RATE_BELT as u16,
+    GenericBloodPressure = ffi::BLE_APPEARANCE_GENERIC_BLOOD_PRESSURE as u16,
+    BloodPressureArm = ffi::BLE_APPEARANCE_BLOOD_PRESSURE_ARM as u16,
+    BloodPressureWrist = ffi::BLE_APPEARANCE_BLOOD_PRESSURE_WRIST as u16,
+    GenericHid = ffi::BLE_APPEARANCE_GENERIC_HID as u16,
+    HidKeyboard = ffi::BLE_APPEARANCE_HID_KEYBOARD as u16,
+    HidMouse = ffi::BLE_APPEARANCE_HID_MOUSE as u16,
+    HidJoystick = ffi::BLE_APPEARANCE_HID_JOYSTICK as u16,
+    HidGamepad = ffi::BLE_APPEARANCE_HID_GAMEPAD as u16,
+    HidDigitizerTablet = ffi::BLE_APPEARANCE_HID_DIGITIZERSUBTYPE as u16,
+    HidCardReader = ffi::BLE_APPEARANCE_HID_CARD_READER as u16,
+    HidDigitalPen = ffi::BLE_APPEARANCE_HID_DIGITAL_PEN as u16,
+    HidBarcodeScanner = ffi::BLE_APPEARANCE_HID_BARCODE as u16,
+    GenericGlucoseMeter = ffi::BLE_APPEARANCE_GENERIC_GLUCOSE_METER as u16,
+    GenericRunningWalkingSensor = ffi::BLE_APPEARANCE_GENERIC_RUNNING_WALKING_SENSOR as u16,
+    RunningWalkingSensorInShoe = ffi::BLE_APPEARANCE_RUNNING_WALKING_SENSOR_IN_SHOE as u16,
+    RunningWalkingSensorOnShoe = ffi::BLE_APPEARANCE_RUNNING_WALKING_SENSOR_ON_SHOE as u16,
+    RunningWalkingSensorOnHip = ffi::BLE_APPEARANCE_RUNNING_WALKING_SENSOR_ON_HIP as u16,
+    GenericCycling = ffi::BLE_APPEARANCE_GENERIC_CYCLING as u16,
+    CyclingComputer = ffi::BLE_APPEARANCE_CYCLING_CYCLING_COMPUTER as u16,
+    CyclingSpeedSensor = ffi::BLE_APPEARANCE_CYCLING_SPEED_SENSOR as u16,
+    CyclingCadenceSensor = ffi::BLE_APPEARANCE_CYCLING_CADENCE_SENSOR as u16,
+    CyclingPowerSensor = ffi::BLE_APPEARANCE_CYCLING_POWER_SENSOR as u16,
+    CyclingSpeedCadenceSensor = ffi::BLE_APPEARANCE_CYCLING_SPEED_CADENCE_SENSOR as u16,
+    GenericPulseOximeter = ffi::BLE_APPEARANCE_GENERIC_PULSE_OXIMETER as u16,
+    PulseOximeterFingertip = ffi::BLE_APPEARANCE_PULSE_OXIMETER_FINGERTIP as u16,
+    PulseOximeterWristWorn = ffi::BLE_APPEARANCE_PULSE_OXIMETER_WRIST_WORN as u16,
+    GenericWeightScale = ffi::BLE_APPEARANCE_GENERIC_WEIGHT_SCALE as u16,
+    GenericOutdoorSportsActivity = ffi::BLE_APPEARANCE_GENERIC_OUTDOOR_SPORTS_ACT as u16,
+    OutdoorSportsLocationDisplay = ffi::BLE_APPEARANCE_OUTDOOR_SPORTS_ACT_LOC_DISP as u16,
+    OutdoorSportsLocationNavigationDisplay =
+        ffi::BLE_APPEARANCE_OUTDOOR_SPORTS_ACT_LOC_AND_NAV_DISP as u16,
+    OutdoorSportsLocationPod = ffi::BLE_APPEARANCE_OUTDOOR_SPORTS_ACT_LOC_POD as u16,
+    OutdoorSportsLocationNavigationPod =
+        ffi::BLE_APPEARANCE_OUTDOOR_SPORTS_ACT_LOC_AND_NAV_POD as u16,
+}
+
+impl BleGapAppearance {
+    pub fn from_u16_or_unknown(value: u16) -> Self {
+        FromPrimitive::from_u16(value).unwrap_or(BleGapAppearance::Unknown)
+    }
+}
+
+impl From<BleGapAppearance> for u16 {
+    fn from(value: BleGapAppearance) -> Self {
+        value as u16
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BleGapSecurityMode {
+    NoAccess,
+    Open,
+    EncryptedNoMitm,
+    EncryptedMitm,
+    LescEncryptedMitm,
+    SignedNoMitm,
+    SignedMitm,
+}
+
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGapIoCaps {
+    DisplayOnly = ffi::BLE_GAP_IO_CAPS_DISPLAY_ONLY as u8,
+    DisplayYesNo = ffi::BLE_GAP_IO_CAPS_DISPLAY_YESNO as u8,
+    KeyboardOnly = ffi::BLE_GAP_IO_CAPS_KEYBOARD_ONLY as u8,
+    None = ffi::BLE_GAP_IO_CAPS_NONE as u8,
+    KeyboardDisplay = ffi::BLE_GAP_IO_CAPS_KEYBOARD_DISPLAY as u8,
+}
+
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGapSecStatus {
+    Success = ffi::BLE_GAP_SEC_STATUS_SUCCESS as u8,
+    Timeout = ffi::BLE_GAP_SEC_STATUS_TIMEOUT as u8,
+    PduInvalid = ffi::BLE_GAP_SEC_STATUS_PDU_INVALID as u8,
+    PasskeyEntryFailed = ffi::BLE_GAP_SEC_STATUS_PASSKEY_ENTRY_FAILED as u8,
+    OobNotAvailable = ffi::BLE_GAP_SEC_STATUS_OOB_NOT_AVAILABLE as u8,
+    AuthenticationRequirements = ffi::BLE_GAP_SEC_STATUS_AUTH_REQ as u8,
+    ConfirmValue = ffi::BLE_GAP_SEC_STATUS_CONFIRM_VALUE as u8,
+    PairingNotSupported = ffi::BLE_GAP_SEC_STATUS_PAIRING_NOT_SUPP as u8,
+    EncryptionKeySize = ffi::BLE_GAP_SEC_STATUS_ENC_KEY_SIZE as u8,
+    SmpCommandUnsupported = ffi::BLE_GAP_SEC_STATUS_SMP_CMD_UNSUPPORTED as u8,
+    Unspecified = ffi::BLE_GAP_SEC_STATUS_UNSPECIFIED as u8,
+    RepeatedAttempts = ffi::BLE_GAP_SEC_STATUS_REPEATED_ATTEMPTS as u8,
+    InvalidParams = ffi::BLE_GAP_SEC_STATUS_INVALID_PARAMS as u8,
+    DhkeyFailure = ffi::BLE_GAP_SEC_STATUS_DHKEY_FAILURE as u8,
+    NumericComparisonFailure = ffi::BLE_GAP_SEC_STATUS_NUM_COMP_FAILURE as u8,
+    BrEdrInProgress = ffi::BLE_GAP_SEC_STATUS_BR_EDR_IN_PROG as u8,
+    CrossTransportKeyDisallowed = ffi::BLE_GAP_SEC_STATUS_X_TRANS_KEY_DISALLOWED as u8,
+}
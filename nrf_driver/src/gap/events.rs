@@ -188,3 +188,120 @@ impl BleEventDataType for GapEventDataLengthUpdate {
         GapEventId::DataLengthUpdate.into()
     }
 }
+
+#[derive(Debug, Copy, Clone)]
+pub struct GapEventSecParamsRequest {
+    pub conn_handle: ConnHandle,
+    pub peer_params: BleGapSecParams,
+}
+
+impl GapEventSecParamsRequest {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gap_evt_sec_params_request_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            peer_params: (*val).peer_params.into(),
+        }
+    }
+}
+
+impl BleEventDataType for GapEventSecParamsRequest {
+    fn id() -> BleEventId {
+        GapEventId::SecParamsRequest.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GapEventSecInfoRequest {
+    pub conn_handle: ConnHandle,
+    pub peer_address: BleGapAddress,
+    pub master_id: BleGapMasterId,
+    pub enc_info: bool,
+    pub id_info: bool,
+    pub sign_info: bool,
+}
+
+impl GapEventSecInfoRequest {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gap_evt_sec_info_request_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            peer_address: (*val).peer_addr.into(),
+            master_id: (*val).master_id.into(),
+            enc_info: (*val).enc_info() != 0,
+            id_info: (*val).id_info() != 0,
+            sign_info: (*val).sign_info() != 0,
+        }
+    }
+}
+
+impl BleEventDataType for GapEventSecInfoRequest {
+    fn id() -> BleEventId {
+        GapEventId::SecInfoRequest.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GapEventAuthStatus {
+    pub conn_handle: ConnHandle,
+    pub auth_status: BleGapSecStatus,
+    pub error_src: u8,
+    pub bonded: bool,
+    pub lesc: bool,
+    pub kdist_own: BleGapSecKeyDist,
+    pub kdist_peer: BleGapSecKeyDist,
+}
+
+impl GapEventAuthStatus {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gap_evt_auth_status_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            auth_status: FromPrimitive::from_u8((*val).auth_status)
+                .unwrap_or(BleGapSecStatus::Unspecified),
+            error_src: (*val).error_src(),
+            bonded: (*val).bonded() != 0,
+            lesc: (*val).lesc() != 0,
+            kdist_own: (*val).kdist_own.into(),
+            kdist_peer: (*val).kdist_peer.into(),
+        }
+    }
+}
+
+impl BleEventDataType for GapEventAuthStatus {
+    fn id() -> BleEventId {
+        GapEventId::AuthStatus.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GapEventConnSecUpdate {
+    pub conn_handle: ConnHandle,
+    pub security_mode: BleGapSecurityMode,
+    pub encryption_key_size: u8,
+}
+
+impl GapEventConnSecUpdate {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gap_evt_conn_sec_update_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            security_mode: (*val).conn_sec.sec_mode.into(),
+            encryption_key_size: (*val).conn_sec.encr_key_size,
+        }
+    }
+}
+
+impl BleEventDataType for GapEventConnSecUpdate {
+    fn id() -> BleEventId {
+        GapEventId::ConnSecUpdate.into()
+    }
+}
@@ -9,7 +9,7 @@ use super::enums::*;
 
 const ADDR_LEN: usize = 6;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct BleGapAddress {
     pub address_type: BleGapAddressType,
     pub address: [u8; ADDR_LEN],
@@ -221,3 +221,205 @@ impl Into<ffi::ble_gap_data_length_params_t> for BleGapDataLengthParams {
         }
     }
 }
+
+impl From<ffi::ble_gap_conn_sec_mode_t> for BleGapSecurityMode {
+    fn from(mode: ffi::ble_gap_conn_sec_mode_t) -> Self {
+        match (mode.sm(), mode.lv()) {
+            (1, 1) => BleGapSecurityMode::Open,
+            (1, 2) => BleGapSecurityMode::EncryptedNoMitm,
+            (1, 3) => BleGapSecurityMode::EncryptedMitm,
+            (1, 4) => BleGapSecurityMode::LescEncryptedMitm,
+            (2, 1) => BleGapSecurityMode::SignedNoMitm,
+            (2, 2) => BleGapSecurityMode::SignedMitm,
+            _ => BleGapSecurityMode::NoAccess,
+        }
+    }
+}
+
+impl Into<ffi::ble_gap_conn_sec_mode_t> for BleGapSecurityMode {
+    fn into(self) -> ffi::ble_gap_conn_sec_mode_t {
+        let (sm, lv) = match self {
+            BleGapSecurityMode::NoAccess => (0, 0),
+            BleGapSecurityMode::Open => (1, 1),
+            BleGapSecurityMode::EncryptedNoMitm => (1, 2),
+            BleGapSecurityMode::EncryptedMitm => (1, 3),
+            BleGapSecurityMode::LescEncryptedMitm => (1, 4),
+            BleGapSecurityMode::SignedNoMitm => (2, 1),
+            BleGapSecurityMode::SignedMitm => (2, 2),
+        };
+
+        ffi::ble_gap_conn_sec_mode_t {
+            _bitfield_1: ffi::ble_gap_conn_sec_mode_t::new_bitfield_1(sm, lv),
+            _bitfield_align_1: [],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct BleGapSecKeyDist {
+    pub enc_key: bool,
+    pub id_key: bool,
+    pub sign_key: bool,
+    pub link_key: bool,
+}
+
+impl From<ffi::ble_gap_sec_kdist_t> for BleGapSecKeyDist {
+    fn from(kdist: ffi::ble_gap_sec_kdist_t) -> Self {
+        Self {
+            enc_key: kdist.enc() != 0,
+            id_key: kdist.id() != 0,
+            sign_key: kdist.sign() != 0,
+            link_key: kdist.link() != 0,
+        }
+    }
+}
+
+impl Into<ffi::ble_gap_sec_kdist_t> for BleGapSecKeyDist {
+    fn into(self) -> ffi::ble_gap_sec_kdist_t {
+        ffi::ble_gap_sec_kdist_t {
+            _bitfield_1: ffi::ble_gap_sec_kdist_t::new_bitfield_1(
+                self.enc_key as u8,
+                self.id_key as u8,
+                self.sign_key as u8,
+                self.link_key as u8,
+            ),
+            _bitfield_align_1: [],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGapSecParams {
+    pub bond: bool,
+    pub mitm: bool,
+    pub lesc: bool,
+    pub keypress: bool,
+    pub io_caps: BleGapIoCaps,
+    pub oob: bool,
+    pub min_key_size: u8,
+    pub max_key_size: u8,
+    pub kdist_own: BleGapSecKeyDist,
+    pub kdist_peer: BleGapSecKeyDist,
+}
+
+impl From<ffi::ble_gap_sec_params_t> for BleGapSecParams {
+    fn from(params: ffi::ble_gap_sec_params_t) -> Self {
+        Self {
+            bond: params.bond() != 0,
+            mitm: params.mitm() != 0,
+            lesc: params.lesc() != 0,
+            keypress: params.keypress() != 0,
+            io_caps: FromPrimitive::from_u8(params.io_caps()).unwrap_or(BleGapIoCaps::None),
+            oob: params.oob() != 0,
+            min_key_size: params.min_key_size,
+            max_key_size: params.max_key_size,
+            kdist_own: params.kdist_own.into(),
+            kdist_peer: params.kdist_peer.into(),
+        }
+    }
+}
+
+impl Into<ffi::ble_gap_sec_params_t> for &BleGapSecParams {
+    fn into(self) -> ffi::ble_gap_sec_params_t {
+        ffi::ble_gap_sec_params_t {
+            min_key_size: self.min_key_size,
+            max_key_size: self.max_key_size,
+            kdist_own: self.kdist_own.into(),
+            kdist_peer: self.kdist_peer.into(),
+            _bitfield_1: ffi::ble_gap_sec_params_t::new_bitfield_1(
+                self.bond as u8,
+                self.mitm as u8,
+                self.lesc as u8,
+                self.keypress as u8,
+                self.io_caps as u8,
+                self.oob as u8,
+            ),
+            _bitfield_align_1: [],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct BleGapMasterId {
+    pub ediv: u16,
+    pub rand: [u8; 8],
+}
+
+impl From<ffi::ble_gap_master_id_t> for BleGapMasterId {
+    fn from(master_id: ffi::ble_gap_master_id_t) -> Self {
+        Self {
+            ediv: master_id.ediv,
+            rand: master_id.rand,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct BleGapEncInfo {
+    pub ltk: [u8; ffi::BLE_GAP_SEC_KEY_LEN as usize],
+    pub lesc: bool,
+    pub auth: bool,
+    pub ltk_len: u8,
+}
+
+impl From<ffi::ble_gap_enc_info_t> for BleGapEncInfo {
+    fn from(enc_info: ffi::ble_gap_enc_info_t) -> Self {
+        Self {
+            ltk: enc_info.ltk,
+            lesc: enc_info.lesc() != 0,
+            auth: enc_info.auth() != 0,
+            ltk_len: enc_info.ltk_len(),
+        }
+    }
+}
+
+impl Into<ffi::ble_gap_enc_info_t> for &BleGapEncInfo {
+    fn into(self) -> ffi::ble_gap_enc_info_t {
+        ffi::ble_gap_enc_info_t {
+            ltk: self.ltk,
+            _bitfield_1: ffi::ble_gap_enc_info_t::new_bitfield_1(
+                self.lesc as u8,
+                self.auth as u8,
+                self.ltk_len,
+            ),
+            _bitfield_align_1: [],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BleGapEncKey {
+    pub enc_info: BleGapEncInfo,
+    pub master_id: BleGapMasterId,
+}
+
+impl From<ffi::ble_gap_enc_key_t> for BleGapEncKey {
+    fn from(key: ffi::ble_gap_enc_key_t) -> Self {
+        Self {
+            enc_info: key.enc_info.into(),
+            master_id: key.master_id.into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BleGapIdKey {
+    pub irk: [u8; ffi::BLE_GAP_SEC_KEY_LEN as usize],
+    pub id_address: BleGapAddress,
+}
+
+impl From<ffi::ble_gap_id_key_t> for BleGapIdKey {
+    fn from(key: ffi::ble_gap_id_key_t) -> Self {
+        Self {
+            irk: key.id_info.irk,
+            id_address: key.id_addr_info.into(),
+        }
+    }
+}
+
+// Keys exchanged during pairing, the auth status event's key distribution says which are valid
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BleGapSecKeys {
+    pub own_enc_key: BleGapEncKey,
+    pub peer_id_key: BleGapIdKey,
+}
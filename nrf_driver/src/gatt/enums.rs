@@ -0,0 +1,70 @@
+use num_traits::FromPrimitive;
+
+use crate::ffi;
+
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGattWriteOperation {
+    Invalid = ffi::BLE_GATT_OP_INVALID as u8,
+    WriteRequest = ffi::BLE_GATT_OP_WRITE_REQ as u8,
+    WriteCommand = ffi::BLE_GATT_OP_WRITE_CMD as u8,
+    SignedWriteCommand = ffi::BLE_GATT_OP_SIGN_WRITE_CMD as u8,
+    PrepareWriteRequest = ffi::BLE_GATT_OP_PREP_WRITE_REQ as u8,
+    ExecuteWriteRequest = ffi::BLE_GATT_OP_EXEC_WRITE_REQ as u8,
+}
+
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGattHvxType {
+    Invalid = ffi::BLE_GATT_HVX_INVALID as u8,
+    Notification = ffi::BLE_GATT_HVX_NOTIFICATION as u8,
+    Indication = ffi::BLE_GATT_HVX_INDICATION as u8,
+}
+
+#[repr(u16)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGattStatusCode {
+    Success = ffi::BLE_GATT_STATUS_SUCCESS as u16,
+    Unknown = ffi::BLE_GATT_STATUS_UNKNOWN as u16,
+    Invalid = ffi::BLE_GATT_STATUS_ATTERR_INVALID as u16,
+    InvalidHandle = ffi::BLE_GATT_STATUS_ATTERR_INVALID_HANDLE as u16,
+    ReadNotPermitted = ffi::BLE_GATT_STATUS_ATTERR_READ_NOT_PERMITTED as u16,
+    WriteNotPermitted = ffi::BLE_GATT_STATUS_ATTERR_WRITE_NOT_PERMITTED as u16,
+    InvalidPdu = ffi::BLE_GATT_STATUS_ATTERR_INVALID_PDU as u16,
+    InsufficientAuthentication = ffi::BLE_GATT_STATUS_ATTERR_INSUF_AUTHENTICATION as u16,
+    RequestNotSupported = ffi::BLE_GATT_STATUS_ATTERR_REQUEST_NOT_SUPPORTED as u16,
+    InvalidOffset = ffi::BLE_GATT_STATUS_ATTERR_INVALID_OFFSET as u16,
+    InsufficientAuthorization = ffi::BLE_GATT_STATUS_ATTERR_INSUF_AUTHORIZATION as u16,
+    PrepareQueueFull = ffi::BLE_GATT_STATUS_ATTERR_PREPARE_QUEUE_FULL as u16,
+    AttributeNotFound = ffi::BLE_GATT_STATUS_ATTERR_ATTRIBUTE_NOT_FOUND as u16,
+    AttributeNotLong = ffi::BLE_GATT_STATUS_ATTERR_ATTRIBUTE_NOT_LONG as u16,
+    InsufficientEncryptionKeySize = ffi::BLE_GATT_STATUS_ATTERR_INSUF_ENC_KEY_SIZE as u16,
+    InvalidAttributeValueLength = ffi::BLE_GATT_STATUS_ATTERR_INVALID_ATT_VAL_LENGTH as u16,
+    UnlikelyError = ffi::BLE_GATT_STATUS_ATTERR_UNLIKELY_ERROR as u16,
+    InsufficientEncryption = ffi::BLE_GATT_STATUS_ATTERR_INSUF_ENCRYPTION as u16,
+    UnsupportedGroupType = ffi::BLE_GATT_STATUS_ATTERR_UNSUPPORTED_GROUP_TYPE as u16,
+    InsufficientResources = ffi::BLE_GATT_STATUS_ATTERR_INSUF_RESOURCES as u16,
+    CccdConfigError = ffi::BLE_GATT_STATUS_ATTERR_CPS_CCCD_CONFIG_ERROR as u16,
+    ProcedureAlreadyInProgress = ffi::BLE_GATT_STATUS_ATTERR_CPS_PROC_ALR_IN_PROG as u16,
+    OutOfRange = ffi::BLE_GATT_STATUS_ATTERR_CPS_OUT_OF_RANGE as u16,
+}
+
+impl BleGattStatusCode {
+    pub fn from_u16_or_unknown(value: u16) -> Self {
+        FromPrimitive::from_u16(value).unwrap_or(BleGattStatusCode::Unknown)
+    }
+}
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct BleGattCharProperties: u8 {
+        const BROADCAST = 0x01;
+        const READ = 0x02;
+        const WRITE_WITHOUT_RESPONSE = 0x04;
+        const WRITE = 0x08;
+        const NOTIFY = 0x10;
+        const INDICATE = 0x20;
+        const AUTH_SIGNED_WRITE = 0x40;
+        const EXTENDED_PROPERTIES = 0x80;
+    }
+}
@@ -0,0 +1,41 @@
+use crate::ffi;
+
+use super::enums::*;
+
+impl From<ffi::ble_gatt_char_props_t> for BleGattCharProperties {
+    fn from(props: ffi::ble_gatt_char_props_t) -> Self {
+        let mut properties = BleGattCharProperties::empty();
+        properties.set(BleGattCharProperties::BROADCAST, props.broadcast() != 0);
+        properties.set(BleGattCharProperties::READ, props.read() != 0);
+        properties.set(
+            BleGattCharProperties::WRITE_WITHOUT_RESPONSE,
+            props.write_wo_resp() != 0,
+        );
+        properties.set(BleGattCharProperties::WRITE, props.write() != 0);
+        properties.set(BleGattCharProperties::NOTIFY, props.notify() != 0);
+        properties.set(BleGattCharProperties::INDICATE, props.indicate() != 0);
+        properties.set(
+            BleGattCharProperties::AUTH_SIGNED_WRITE,
+            props.auth_signed_wr() != 0,
+        );
+        properties
+    }
+}
+
+impl Into<ffi::ble_gatt_char_props_t> for BleGattCharProperties {
+    fn into(self) -> ffi::ble_gatt_char_props_t {
+        let flag = |f: BleGattCharProperties| self.contains(f) as u8;
+        ffi::ble_gatt_char_props_t {
+            _bitfield_1: ffi::ble_gatt_char_props_t::new_bitfield_1(
+                flag(BleGattCharProperties::BROADCAST),
+                flag(BleGattCharProperties::READ),
+                flag(BleGattCharProperties::WRITE_WITHOUT_RESPONSE),
+                flag(BleGattCharProperties::WRITE),
+                flag(BleGattCharProperties::NOTIFY),
+                flag(BleGattCharProperties::INDICATE),
+                flag(BleGattCharProperties::AUTH_SIGNED_WRITE),
+            ),
+            _bitfield_align_1: [],
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use num_traits::FromPrimitive;
+
+use crate::ble_event::{BleEventDataType, BleEventId, GattcEventId};
+use crate::common::types::ConnHandle;
+use crate::ffi;
+use crate::gatt::enums::{BleGattHvxType, BleGattStatusCode, BleGattWriteOperation};
+
+use super::types::*;
+
+#[derive(Debug, Clone)]
+pub struct GattcEventPrimaryServiceDiscoveryResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub services: Vec<BleGattcService>,
+}
+
+impl GattcEventPrimaryServiceDiscoveryResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        val: *const ffi::ble_gattc_evt_prim_srvc_disc_rsp_t,
+    ) -> Self {
+        let services = std::slice::from_raw_parts((*val).services.as_ptr(), (*val).count as usize);
+        Self {
+            conn_handle,
+            status,
+            services: services.iter().map(|s| (*s).into()).collect(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventPrimaryServiceDiscoveryResponse {
+    fn id() -> BleEventId {
+        GattcEventId::PrimaryServiceDiscoveryResponse.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GattcEventCharacteristicDiscoveryResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub characteristics: Vec<BleGattcCharacteristic>,
+}
+
+impl GattcEventCharacteristicDiscoveryResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        val: *const ffi::ble_gattc_evt_char_disc_rsp_t,
+    ) -> Self {
+        let characteristics =
+            std::slice::from_raw_parts((*val).chars.as_ptr(), (*val).count as usize);
+        Self {
+            conn_handle,
+            status,
+            characteristics: characteristics.iter().map(|c| (*c).into()).collect(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventCharacteristicDiscoveryResponse {
+    fn id() -> BleEventId {
+        GattcEventId::CharacteristicDiscoveryResponse.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GattcEventDescriptorDiscoveryResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub descriptors: Vec<BleGattcDescriptor>,
+}
+
+impl GattcEventDescriptorDiscoveryResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        val: *const ffi::ble_gattc_evt_desc_disc_rsp_t,
+    ) -> Self {
+        let descriptors = std::slice::from_raw_parts((*val).descs.as_ptr(), (*val).count as usize);
+        Self {
+            conn_handle,
+            status,
+            descriptors: descriptors.iter().map(|d| (*d).into()).collect(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventDescriptorDiscoveryResponse {
+    fn id() -> BleEventId {
+        GattcEventId::DescriptorDiscoveryResponse.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GattcEventReadResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub error_handle: u16,
+    pub handle: u16,
+    pub offset: u16,
+    pub data: Vec<u8>,
+}
+
+impl GattcEventReadResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        error_handle: u16,
+        val: *const ffi::ble_gattc_evt_read_rsp_t,
+    ) -> Self {
+        let data = std::slice::from_raw_parts((*val).data.as_ptr(), (*val).len as usize);
+        Self {
+            conn_handle,
+            status,
+            error_handle,
+            handle: (*val).handle,
+            offset: (*val).offset,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventReadResponse {
+    fn id() -> BleEventId {
+        GattcEventId::ReadResponse.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GattcEventWriteResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub error_handle: u16,
+    pub handle: u16,
+    pub write_op: BleGattWriteOperation,
+    pub offset: u16,
+    pub data: Vec<u8>,
+}
+
+impl GattcEventWriteResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        error_handle: u16,
+        val: *const ffi::ble_gattc_evt_write_rsp_t,
+    ) -> Self {
+        let data = std::slice::from_raw_parts((*val).data.as_ptr(), (*val).len as usize);
+        Self {
+            conn_handle,
+            status,
+            error_handle,
+            handle: (*val).handle,
+            write_op: FromPrimitive::from_u8((*val).write_op)
+                .unwrap_or(BleGattWriteOperation::Invalid),
+            offset: (*val).offset,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventWriteResponse {
+    fn id() -> BleEventId {
+        GattcEventId::WriteResponse.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GattcEventHvx {
+    pub conn_handle: ConnHandle,
+    pub handle: u16,
+    pub hvx_type: BleGattHvxType,
+    pub data: Vec<u8>,
+}
+
+impl GattcEventHvx {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gattc_evt_hvx_t,
+    ) -> Self {
+        let data = std::slice::from_raw_parts((*val).data.as_ptr(), (*val).len as usize);
+        Self {
+            conn_handle,
+            handle: (*val).handle,
+            hvx_type: FromPrimitive::from_u8((*val).type_).unwrap_or(BleGattHvxType::Invalid),
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventHvx {
+    fn id() -> BleEventId {
+        GattcEventId::Hvx.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GattcEventWriteCmdTxComplete {
+    pub conn_handle: ConnHandle,
+    pub count: u8,
+}
+
+impl GattcEventWriteCmdTxComplete {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gattc_evt_write_cmd_tx_complete_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            count: (*val).count,
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventWriteCmdTxComplete {
+    fn id() -> BleEventId {
+        GattcEventId::WriteCmdTxComplete.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GattcEventExchangeMtuResponse {
+    pub conn_handle: ConnHandle,
+    pub status: BleGattStatusCode,
+    pub server_rx_mtu: u16,
+}
+
+impl GattcEventExchangeMtuResponse {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        status: BleGattStatusCode,
+        val: *const ffi::ble_gattc_evt_exchange_mtu_rsp_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            status,
+            server_rx_mtu: (*val).server_rx_mtu,
+        }
+    }
+}
+
+impl BleEventDataType for GattcEventExchangeMtuResponse {
+    fn id() -> BleEventId {
+        GattcEventId::ExchangeMtuResponse.into()
+    }
+}
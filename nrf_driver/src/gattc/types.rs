@@ -0,0 +1,88 @@
+use crate::common::types::BleUuid;
+use crate::ffi;
+use crate::gatt::enums::BleGattCharProperties;
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGattcHandleRange {
+    pub start_handle: u16,
+    pub end_handle: u16,
+}
+
+impl BleGattcHandleRange {
+    pub fn new(start_handle: u16, end_handle: u16) -> Self {
+        Self {
+            start_handle,
+            end_handle,
+        }
+    }
+}
+
+impl From<ffi::ble_gattc_handle_range_t> for BleGattcHandleRange {
+    fn from(range: ffi::ble_gattc_handle_range_t) -> Self {
+        Self::new(range.start_handle, range.end_handle)
+    }
+}
+
+impl Into<ffi::ble_gattc_handle_range_t> for &BleGattcHandleRange {
+    fn into(self) -> ffi::ble_gattc_handle_range_t {
+        ffi::ble_gattc_handle_range_t {
+            start_handle: self.start_handle,
+            end_handle: self.end_handle,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGattcService {
+    pub uuid: BleUuid,
+    pub handle_range: BleGattcHandleRange,
+}
+
+impl From<ffi::ble_gattc_service_t> for BleGattcService {
+    fn from(service: ffi::ble_gattc_service_t) -> Self {
+        Self {
+            uuid: service.uuid.into(),
+            handle_range: service.handle_range.into(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGattcCharacteristic {
+    pub uuid: BleUuid,
+    pub properties: BleGattCharProperties,
+    pub handle_decl: u16,
+    pub handle_value: u16,
+}
+
+impl From<ffi::ble_gattc_char_t> for BleGattcCharacteristic {
+    fn from(characteristic: ffi::ble_gattc_char_t) -> Self {
+        let mut properties: BleGattCharProperties = characteristic.char_props.into();
+        properties.set(
+            BleGattCharProperties::EXTENDED_PROPERTIES,
+            characteristic.char_ext_props() != 0,
+        );
+
+        Self {
+            uuid: characteristic.uuid.into(),
+            properties,
+            handle_decl: characteristic.handle_decl,
+            handle_value: characteristic.handle_value,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGattcDescriptor {
+    pub handle: u16,
+    pub uuid: BleUuid,
+}
+
+impl From<ffi::ble_gattc_desc_t> for BleGattcDescriptor {
+    fn from(descriptor: ffi::ble_gattc_desc_t) -> Self {
+        Self {
+            handle: descriptor.handle,
+            uuid: descriptor.uuid.into(),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use crate::ffi;
+
+#[repr(u8)]
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq)]
+pub enum BleGattsServiceType {
+    Invalid = ffi::BLE_GATTS_SRVC_TYPE_INVALID as u8,
+    Primary = ffi::BLE_GATTS_SRVC_TYPE_PRIMARY as u8,
+    Secondary = ffi::BLE_GATTS_SRVC_TYPE_SECONDARY as u8,
+}
@@ -0,0 +1,178 @@
+use num_traits::FromPrimitive;
+
+use crate::ble_event::{BleEventDataType, BleEventId, GattsEventId};
+use crate::common::types::{BleUuid, ConnHandle};
+use crate::ffi;
+use crate::gatt::enums::BleGattWriteOperation;
+
+#[derive(Debug, Clone)]
+pub struct GattsEventWrite {
+    pub conn_handle: ConnHandle,
+    pub handle: u16,
+    pub uuid: BleUuid,
+    pub write_op: BleGattWriteOperation,
+    pub auth_required: bool,
+    pub offset: u16,
+    pub data: Vec<u8>,
+}
+
+impl GattsEventWrite {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gatts_evt_write_t,
+    ) -> Self {
+        let data = std::slice::from_raw_parts((*val).data.as_ptr(), (*val).len as usize);
+        Self {
+            conn_handle,
+            handle: (*val).handle,
+            uuid: (*val).uuid.into(),
+            write_op: FromPrimitive::from_u8((*val).op).unwrap_or(BleGattWriteOperation::Invalid),
+            auth_required: (*val).auth_required != 0,
+            offset: (*val).offset,
+            data: data.to_vec(),
+        }
+    }
+}
+
+impl BleEventDataType for GattsEventWrite {
+    fn id() -> BleEventId {
+        GattsEventId::Write.into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BleGattsAuthorizeRequest {
+    Read {
+        handle: u16,
+        uuid: BleUuid,
+        offset: u16,
+    },
+    Write(GattsEventWrite),
+}
+
+#[derive(Debug, Clone)]
+pub struct GattsEventRwAuthorizeRequest {
+    pub conn_handle: ConnHandle,
+    pub request: BleGattsAuthorizeRequest,
+}
+
+impl GattsEventRwAuthorizeRequest {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gatts_evt_rw_authorize_request_t,
+    ) -> Self {
+        let request = if (*val).type_ == ffi::BLE_GATTS_AUTHORIZE_TYPE_READ as u8 {
+            let read = &(*val).request.read;
+            BleGattsAuthorizeRequest::Read {
+                handle: read.handle,
+                uuid: read.uuid.into(),
+                offset: read.offset,
+            }
+        } else {
+            BleGattsAuthorizeRequest::Write(GattsEventWrite::from_c(
+                conn_handle,
+                &(*val).request.write,
+            ))
+        };
+        Self {
+            conn_handle,
+            request,
+        }
+    }
+}
+
+impl BleEventDataType for GattsEventRwAuthorizeRequest {
+    fn id() -> BleEventId {
+        GattsEventId::RwAuthorizeRequest.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GattsEventSysAttrMissing {
+    pub conn_handle: ConnHandle,
+    pub hint: u8,
+}
+
+impl GattsEventSysAttrMissing {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gatts_evt_sys_attr_missing_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            hint: (*val).hint,
+        }
+    }
+}
+
+impl BleEventDataType for GattsEventSysAttrMissing {
+    fn id() -> BleEventId {
+        GattsEventId::SysAttrMissing.into()
+    }
+}
+
+// No params
+#[derive(Debug, Copy, Clone)]
+pub struct GattsEventScConfirm {
+    pub conn_handle: ConnHandle,
+}
+
+impl GattsEventScConfirm {
+    pub(crate) fn from_c(conn_handle: ConnHandle) -> Self {
+        Self { conn_handle }
+    }
+}
+
+impl BleEventDataType for GattsEventScConfirm {
+    fn id() -> BleEventId {
+        GattsEventId::ScConfirm.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GattsEventHvnTxComplete {
+    pub conn_handle: ConnHandle,
+    pub count: u8,
+}
+
+impl GattsEventHvnTxComplete {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gatts_evt_hvn_tx_complete_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            count: (*val).count,
+        }
+    }
+}
+
+impl BleEventDataType for GattsEventHvnTxComplete {
+    fn id() -> BleEventId {
+        GattsEventId::HvnTxComplete.into()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GattsEventExchangeMtuRequest {
+    pub conn_handle: ConnHandle,
+    pub client_rx_mtu: u16,
+}
+
+impl GattsEventExchangeMtuRequest {
+    pub(crate) unsafe fn from_c(
+        conn_handle: ConnHandle,
+        val: *const ffi::ble_gatts_evt_exchange_mtu_request_t,
+    ) -> Self {
+        Self {
+            conn_handle,
+            client_rx_mtu: (*val).client_rx_mtu,
+        }
+    }
+}
+
+impl BleEventDataType for GattsEventExchangeMtuRequest {
+    fn id() -> BleEventId {
+        GattsEventId::ExchangeMtuRequest.into()
+    }
+}
@@ -0,0 +1,3 @@
+pub mod enums;
+pub mod events;
+pub mod types;
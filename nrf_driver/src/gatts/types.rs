@@ -0,0 +1,99 @@
+use crate::common::types::BleUuid;
+use crate::ffi;
+use crate::gap::enums::BleGapSecurityMode;
+use crate::gatt::enums::BleGattCharProperties;
+
+// Generic over the uuid so higher layers can describe characteristics with their own uuid type
+#[derive(Debug, Clone)]
+pub struct BleGattsCharacteristicParams<U = BleUuid> {
+    pub uuid: U,
+    pub properties: BleGattCharProperties,
+    pub read_permission: BleGapSecurityMode,
+    pub write_permission: BleGapSecurityMode,
+    pub max_length: u16,
+    pub variable_length: bool,
+    // Writes to the value are held until they're accepted with an authorize reply
+    pub write_authorization: bool,
+    pub initial_value: Vec<u8>,
+    pub user_description: Option<String>,
+}
+
+impl<U> BleGattsCharacteristicParams<U> {
+    pub fn new(uuid: U, properties: BleGattCharProperties, max_length: u16) -> Self {
+        Self {
+            uuid,
+            properties,
+            read_permission: BleGapSecurityMode::Open,
+            write_permission: BleGapSecurityMode::Open,
+            max_length,
+            variable_length: true,
+            write_authorization: false,
+            initial_value: vec![],
+            user_description: None,
+        }
+    }
+
+    pub fn with_permissions(
+        mut self,
+        read_permission: BleGapSecurityMode,
+        write_permission: BleGapSecurityMode,
+    ) -> Self {
+        self.read_permission = read_permission;
+        self.write_permission = write_permission;
+        self
+    }
+
+    pub fn with_fixed_length(mut self) -> Self {
+        self.variable_length = false;
+        self
+    }
+
+    pub fn with_write_authorization(mut self) -> Self {
+        self.write_authorization = true;
+        self
+    }
+
+    pub fn with_initial_value(mut self, value: &[u8]) -> Self {
+        self.initial_value = value.to_vec();
+        self
+    }
+
+    pub fn with_user_description(mut self, description: &str) -> Self {
+        self.user_description = Some(description.to_string());
+        self
+    }
+
+    // The same parameters with the uuid converted to another representation
+    pub fn replace_uuid<V>(&self, uuid: V) -> BleGattsCharacteristicParams<V> {
+        BleGattsCharacteristicParams {
+            uuid,
+            properties: self.properties,
+            read_permission: self.read_permission,
+            write_permission: self.write_permission,
+            max_length: self.max_length,
+            variable_length: self.variable_length,
+            write_authorization: self.write_authorization,
+            initial_value: self.initial_value.clone(),
+            user_description: self.user_description.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BleGattsCharacteristicHandles {
+    pub value_handle: u16,
+    pub user_desc_handle: u16,
+    pub cccd_handle: u16,
+    pub sccd_handle: u16,
+}
+
+impl From<ffi::ble_gatts_char_handles_t> for BleGattsCharacteristicHandles {
+    fn from(handles: ffi::ble_gatts_char_handles_t) -> Self {
+        Self {
+            value_handle: handles.value_handle,
+            user_desc_handle: handles.user_desc_handle,
+            cccd_handle: handles.cccd_handle,
+            sccd_handle: handles.sccd_handle,
+        }
+    }
+}
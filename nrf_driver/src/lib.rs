@@ -19,6 +19,9 @@ pub mod driver;
 pub mod driver_events;
 pub mod error;
 pub mod gap;
+pub mod gatt;
+pub mod gattc;
+pub mod gatts;
 pub mod manager;
 pub mod utils;
 
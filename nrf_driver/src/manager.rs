@@ -1,5 +1,6 @@
 use num_traits::FromPrimitive;
 use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{mpsc, Arc, Mutex, Weak};
 use std::thread;
 
@@ -96,7 +97,13 @@ fn run_event_loop(driver: Arc<NrfDriver>, receiver: mpsc::Receiver<BleEvent>) {
             Err(_) => return,
         };
 
-        driver.clone().process_event(ble_event);
+        // Subscribers are isolated by their publishers, this keeps the loop alive if the
+        // driver itself panics while decoding or dispatching
+        let driver = driver.clone();
+        let port = driver.port.clone();
+        if panic::catch_unwind(AssertUnwindSafe(|| driver.process_event(ble_event))).is_err() {
+            error!("[{}] Event processing panicked, continuing", port);
+        }
     }
 }
 